@@ -0,0 +1,257 @@
+use crate::error::XzardgzError;
+use crate::providers::types::Message;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Limits on what an agent may consume. Unset limits are unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Budget {
+    /// Estimated prompt plus completion tokens across all provider calls
+    #[serde(default)]
+    pub max_tokens: Option<u64>,
+    /// Wall-clock time
+    #[serde(default)]
+    pub max_seconds: Option<u64>,
+    #[serde(default)]
+    pub max_provider_calls: Option<u64>,
+    #[serde(default)]
+    pub max_tool_calls: Option<u64>,
+}
+
+impl Budget {
+    pub fn is_unlimited(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Budgets for a single agent run and for a whole workflow
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BudgetConfig {
+    #[serde(default)]
+    pub run: Budget,
+    #[serde(default)]
+    pub workflow: Budget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetKind {
+    Tokens,
+    Time,
+    ProviderCalls,
+    ToolCalls,
+    /// Provider round trips within one turn, a fixed cap of the agent loop
+    Iterations,
+}
+
+impl fmt::Display for BudgetKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let unit = match self {
+            BudgetKind::Tokens => "tokens",
+            BudgetKind::Time => "seconds",
+            BudgetKind::ProviderCalls => "provider calls",
+            BudgetKind::ToolCalls => "tool calls",
+            BudgetKind::Iterations => "iterations",
+        };
+        write!(f, "{}", unit)
+    }
+}
+
+/// The limit that stopped a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Exceeded {
+    pub kind: BudgetKind,
+    pub used: u64,
+    pub limit: u64,
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} {}", self.used, self.limit, self.kind)
+    }
+}
+
+impl Exceeded {
+    /// The error returned from the agent loop, carrying the answer so far
+    pub fn into_error(self, partial: Option<String>) -> XzardgzError {
+        XzardgzError::BudgetExceeded {
+            kind: self.kind,
+            used: self.used,
+            limit: self.limit,
+            partial,
+        }
+    }
+}
+
+/// What a run has consumed so far
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct Usage {
+    pub tokens: u64,
+    pub provider_calls: u64,
+    pub tool_calls: u64,
+    pub elapsed: Duration,
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "~{} tokens, {} provider calls, {} tool calls in {:.1}s",
+            self.tokens,
+            self.provider_calls,
+            self.tool_calls,
+            self.elapsed.as_secs_f64()
+        )
+    }
+}
+
+/// Counts usage against a `Budget`. Shared through an `Arc` when one budget
+/// spans several agent runs, as with a workflow.
+#[derive(Debug)]
+pub struct BudgetTracker {
+    budget: Budget,
+    started: Instant,
+    tokens: AtomicU64,
+    provider_calls: AtomicU64,
+    tool_calls: AtomicU64,
+}
+
+impl BudgetTracker {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            started: Instant::now(),
+            tokens: AtomicU64::new(0),
+            provider_calls: AtomicU64::new(0),
+            tool_calls: AtomicU64::new(0),
+        }
+    }
+
+    pub fn usage(&self) -> Usage {
+        Usage {
+            tokens: self.tokens.load(Ordering::SeqCst),
+            provider_calls: self.provider_calls.load(Ordering::SeqCst),
+            tool_calls: self.tool_calls.load(Ordering::SeqCst),
+            elapsed: self.started.elapsed(),
+        }
+    }
+
+    /// When the time budget runs out, if there is one
+    pub fn deadline(&self) -> Option<Instant> {
+        self.budget
+            .max_seconds
+            .map(|seconds| self.started + Duration::from_secs(seconds))
+    }
+
+    /// Whether another provider call is allowed
+    pub fn check_provider_call(&self) -> Result<(), Exceeded> {
+        self.check_time()?;
+        let usage = self.usage();
+        check(BudgetKind::Tokens, usage.tokens, self.budget.max_tokens)?;
+        check(
+            BudgetKind::ProviderCalls,
+            usage.provider_calls,
+            self.budget.max_provider_calls,
+        )
+    }
+
+    /// Whether another tool call is allowed
+    pub fn check_tool_call(&self) -> Result<(), Exceeded> {
+        self.check_time()?;
+        check(
+            BudgetKind::ToolCalls,
+            self.tool_calls.load(Ordering::SeqCst),
+            self.budget.max_tool_calls,
+        )
+    }
+
+    pub fn check_time(&self) -> Result<(), Exceeded> {
+        match self.deadline() {
+            Some(deadline) if Instant::now() >= deadline => Err(self.time_exceeded()),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn record_provider_call(&self, tokens: u64) {
+        self.provider_calls.fetch_add(1, Ordering::SeqCst);
+        self.tokens.fetch_add(tokens, Ordering::SeqCst);
+    }
+
+    pub fn record_tool_call(&self) {
+        self.tool_calls.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// The `Exceeded` to report once the deadline has passed
+    pub fn time_exceeded(&self) -> Exceeded {
+        Exceeded {
+            kind: BudgetKind::Time,
+            used: self.started.elapsed().as_secs(),
+            limit: self.budget.max_seconds.unwrap_or_default(),
+        }
+    }
+}
+
+fn check(kind: BudgetKind, used: u64, limit: Option<u64>) -> Result<(), Exceeded> {
+    match limit {
+        Some(limit) if used >= limit => Err(Exceeded { kind, used, limit }),
+        _ => Ok(()),
+    }
+}
+
+/// Every budget that applies to one agent run, e.g. its own and its workflow's
+#[derive(Debug, Default)]
+pub struct Budgets(Vec<Arc<BudgetTracker>>);
+
+impl Budgets {
+    pub fn new(trackers: Vec<Arc<BudgetTracker>>) -> Self {
+        Self(trackers)
+    }
+
+    pub fn check_provider_call(&self) -> Result<(), Exceeded> {
+        self.0.iter().try_for_each(|t| t.check_provider_call())
+    }
+
+    pub fn check_tool_call(&self) -> Result<(), Exceeded> {
+        self.0.iter().try_for_each(|t| t.check_tool_call())
+    }
+
+    pub fn record_provider_call(&self, tokens: u64) {
+        self.0.iter().for_each(|t| t.record_provider_call(tokens));
+    }
+
+    pub fn record_tool_call(&self) {
+        self.0.iter().for_each(|t| t.record_tool_call());
+    }
+
+    /// Wait until the earliest deadline passes; never resolves without one
+    pub async fn expired(&self) -> Exceeded {
+        let earliest = self
+            .0
+            .iter()
+            .filter_map(|t| t.deadline().map(|deadline| (deadline, t)))
+            .min_by_key(|(deadline, _)| *deadline);
+        match earliest {
+            Some((deadline, tracker)) => {
+                tokio::time::sleep_until(deadline.into()).await;
+                tracker.time_exceeded()
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Rough token estimate (4 characters per token), as providers do not report usage
+pub fn estimate_tokens(messages: &[Message]) -> u64 {
+    let chars: usize = messages
+        .iter()
+        .map(|m| {
+            m.content.len()
+                + m.tool_calls.as_ref().map_or(0, |calls| {
+                    calls.iter().map(|c| c.function.arguments.len()).sum()
+                })
+        })
+        .sum();
+    (chars / 4) as u64
+}
@@ -0,0 +1,127 @@
+use crate::agent::budget::{Budgets, estimate_tokens};
+use crate::agent::context::ConversationContext;
+use crate::error::{ProviderError, WorkflowError, XzardgzError};
+use crate::providers::base::Provider;
+use crate::providers::types::{Message, Role};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, MutexGuard};
+
+/// Prefix of the synthetic message that replaces summarized turns
+pub const SUMMARY_PREFIX: &str = "Summary of earlier conversation:";
+
+/// How `ConversationContext` shrinks once it exceeds its token budget
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CompactionStrategy {
+    /// Drop the oldest turns, never separating tool calls from their results
+    Truncate,
+    /// Like `Truncate`, but always keep the first user message (the original task)
+    PinFirst,
+    /// Keep the first user message and replace dropped turns with an LLM summary
+    #[default]
+    Summarize,
+}
+
+/// Ask the provider to condense `messages` into a single synthetic summary message
+pub async fn summarize(
+    provider: &dyn Provider,
+    messages: &[Message],
+) -> Result<Message, ProviderError> {
+    let mut transcript = String::new();
+    for message in messages {
+        let speaker = match message.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+            Role::Tool => "Tool",
+        };
+        transcript.push_str(&format!("{}: {}\n", speaker, message.content));
+        if let Some(calls) = &message.tool_calls {
+            for call in calls {
+                transcript.push_str(&format!(
+                    "  (called {} with {})\n",
+                    call.function.name, call.function.arguments
+                ));
+            }
+        }
+    }
+
+    let prompt = format!(
+        r#"Summarize the following conversation excerpt so it can replace the original messages.
+Keep facts, decisions, file paths, tool results and open questions. Be concise.
+
+{}"#,
+        transcript
+    );
+
+    let response = provider.complete(&[Message::user(prompt)], &[]).await?;
+    Ok(Message::system(format!(
+        "{}\n{}",
+        SUMMARY_PREFIX,
+        response.content.trim()
+    )))
+}
+
+/// Compact `context` according to its strategy. Summarization failures fall back
+/// to truncation so a flaky provider never blocks the agent loop, and so does
+/// an exhausted provider call or token budget, which the summary counts against.
+pub async fn compact(
+    provider: &dyn Provider,
+    context: &Mutex<ConversationContext>,
+    budgets: &Budgets,
+) -> Result<bool, XzardgzError> {
+    let dropped = {
+        let mut context = lock(context)?;
+        match context.compaction_range() {
+            None => return Ok(false),
+            Some(range) if context.strategy() == CompactionStrategy::Summarize => {
+                context.get_messages()[range].to_vec()
+            }
+            Some(_) => return context.compact_if_needed(),
+        }
+    };
+
+    let summary = match budgets.check_provider_call() {
+        Ok(()) => {
+            let summary = summarize(provider, &dropped).await;
+            let reply = summary
+                .as_ref()
+                .map_or(0, |summary| estimate_tokens(std::slice::from_ref(summary)));
+            budgets.record_provider_call(estimate_tokens(&dropped) + reply);
+            match summary {
+                Ok(summary) => Some(summary),
+                Err(e) => {
+                    tracing::warn!("Summarizing context failed, truncating instead: {}", e);
+                    None
+                }
+            }
+        }
+        Err(exceeded) => {
+            tracing::warn!("Budget exceeded ({}), truncating instead", exceeded);
+            None
+        }
+    };
+
+    // The context may have grown while the provider was summarizing, so recompute
+    // the range and only replace it if it still covers the summarized messages.
+    let mut context = lock(context)?;
+    match context.compaction_range() {
+        Some(range) if range.len() >= dropped.len() => {
+            let start = range.start;
+            context.replace_range(start..start + dropped.len(), summary);
+            context.compact_if_needed()?;
+            Ok(true)
+        }
+        _ => context.compact_if_needed(),
+    }
+}
+
+fn lock(
+    context: &Mutex<ConversationContext>,
+) -> Result<MutexGuard<'_, ConversationContext>, XzardgzError> {
+    context.lock().map_err(|_| {
+        XzardgzError::Workflow(WorkflowError::Execution(
+            "Context lock poisoned".to_string(),
+        ))
+    })
+}
@@ -1,23 +1,47 @@
-use crate::agent::message::Message;
+use crate::agent::compaction::CompactionStrategy;
+use crate::agent::message::{Message, Role};
 use crate::error::XzardgzError;
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationContext {
     messages: Vec<Message>,
-    #[allow(dead_code)]
     system_prompt: String,
     max_tokens: usize,
+    #[serde(default)]
+    strategy: CompactionStrategy,
 }
 
 impl ConversationContext {
+    /// An empty conversation compacted with the default strategy, as configured
+    /// contexts and restored sessions are
     pub fn new(system_prompt: String, max_tokens: usize) -> Self {
         Self {
             messages: Vec::new(),
             system_prompt,
             max_tokens,
+            strategy: CompactionStrategy::default(),
         }
     }
 
+    pub fn with_strategy(mut self, strategy: CompactionStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn strategy(&self) -> CompactionStrategy {
+        self.strategy
+    }
+
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    pub fn system_prompt(&self) -> &str {
+        &self.system_prompt
+    }
+
     pub fn add_message(&mut self, message: Message) {
         self.messages.push(message);
     }
@@ -32,27 +56,97 @@ impl ConversationContext {
 
     // Placeholder for token counting
     pub fn current_tokens(&self) -> usize {
+        Self::estimate_tokens(&self.messages)
+    }
+
+    fn estimate_tokens(messages: &[Message]) -> usize {
         // Very rough estimation: 4 chars per token
-        let content_len: usize = self.messages.iter().map(|m| m.content.len()).sum();
+        let content_len: usize = messages.iter().map(|m| m.content.len()).sum();
         content_len / 4
     }
 
-    pub fn compact_if_needed(&mut self) -> Result<bool, XzardgzError> {
-        if self.current_tokens() > self.max_tokens {
-            // Simple strategy: Remove oldest non-system messages
-            // Keep system prompt (it's separate)
-            // Keep last N messages?
+    /// Messages that must survive compaction (the original task, unless plain truncation)
+    fn pinned_len(&self) -> usize {
+        match self.strategy {
+            CompactionStrategy::Truncate => 0,
+            CompactionStrategy::PinFirst | CompactionStrategy::Summarize => {
+                match self.messages.first() {
+                    Some(first) if first.role == Role::User => 1,
+                    _ => 0,
+                }
+            }
+        }
+    }
 
-            // For now, just remove the second message (after system if we treated system as message 0,
-            // but here system_prompt is separate).
-            // So remove messages[0] until we fit.
+    /// End (exclusive) of the turn starting at `start`. An assistant message that
+    /// requested tools owns the tool results that follow it, so the two are
+    /// always dropped together.
+    fn turn_end(&self, start: usize) -> usize {
+        let mut end = start + 1;
+        let opens_tool_calls = self.messages[start]
+            .tool_calls
+            .as_ref()
+            .is_some_and(|calls| !calls.is_empty());
+        if opens_tool_calls || self.messages[start].role == Role::Tool {
+            while end < self.messages.len() && self.messages[end].role == Role::Tool {
+                end += 1;
+            }
+        }
+        end
+    }
 
-            while self.current_tokens() > self.max_tokens && !self.messages.is_empty() {
-                self.messages.remove(0);
+    /// Range of messages to drop so the context fits its token budget, aligned to
+    /// whole turns. `None` when no compaction is needed or possible. The latest
+    /// user message is never dropped, so the provider always sees what it is
+    /// answering: turns before it go first, and the messages after it only
+    /// when there is nothing before it to drop.
+    pub fn compaction_range(&self) -> Option<Range<usize>> {
+        let total = self.current_tokens();
+        if total <= self.max_tokens {
+            return None;
+        }
+
+        // Summaries shrink to half the budget, leaving room for the summary itself
+        // so the next turn doesn't immediately trigger another summarization.
+        let target = match self.strategy {
+            CompactionStrategy::Summarize => self.max_tokens / 2,
+            CompactionStrategy::Truncate | CompactionStrategy::PinFirst => self.max_tokens,
+        };
+
+        let pinned = self.pinned_len();
+        let latest = self.messages.iter().rposition(|m| m.role == Role::User);
+        let (start, stop) = match latest {
+            Some(latest) if latest == pinned => (latest + 1, self.messages.len()),
+            Some(latest) if latest > pinned => (pinned, latest),
+            _ => (pinned, self.messages.len()),
+        };
+        let mut end = start;
+        let mut dropped = 0;
+        while end < stop && total - dropped > target {
+            let next = self.turn_end(end);
+            dropped += Self::estimate_tokens(&self.messages[end..next]);
+            end = next;
+        }
+
+        if end == start { None } else { Some(start..end) }
+    }
+
+    /// Replace a range of messages, optionally with a synthetic summary message
+    pub fn replace_range(&mut self, range: Range<usize>, replacement: Option<Message>) {
+        self.messages.splice(range, replacement);
+    }
+
+    /// Truncate the context to fit the token budget. `Summarize` needs a provider
+    /// (see `agent::compaction::compact`), so here it keeps the first user
+    /// message like `PinFirst` but drops turns down to half the budget, the room
+    /// a summary would have left.
+    pub fn compact_if_needed(&mut self) -> Result<bool, XzardgzError> {
+        match self.compaction_range() {
+            Some(range) => {
+                self.replace_range(range, None);
+                Ok(true)
             }
-            Ok(true)
-        } else {
-            Ok(false)
+            None => Ok(false),
         }
     }
 }
@@ -1,10 +1,22 @@
+use crate::agent::budget::{Budget, BudgetKind, BudgetTracker, Budgets, Exceeded, estimate_tokens};
+use crate::agent::compaction::{self, CompactionStrategy};
 use crate::agent::context::ConversationContext;
+use crate::agent::events::{AgentEvent, AgentObserver};
+use crate::agent::review::{ReviewReport, Reviewer};
+use crate::agent::transcript::Transcript;
 use crate::error::XzardgzError;
 use crate::providers::base::Provider;
-use crate::providers::types::{Message, Role};
-use crate::tools::executor::ToolExecutionDispatcher;
+use crate::providers::types::{Message, Role, ToolCall};
+use crate::tools::approval::Approver;
+use crate::tools::audit::AuditLog;
+use crate::tools::executor::{ApprovalRecord, ToolExecutionDispatcher};
+use crate::tools::profile::ToolProfile;
 use crate::tools::registry::ToolRegistry;
+use crate::tools::{ToolContext, ToolResult};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
 
 #[allow(dead_code)]
 use std::sync::Mutex;
@@ -15,6 +27,13 @@ pub struct Agent {
     context: Mutex<ConversationContext>,
     tool_registry: Arc<ToolRegistry>,
     tool_dispatcher: ToolExecutionDispatcher,
+    observers: Vec<Arc<dyn AgentObserver>>,
+    reviewer: Option<Reviewer>,
+    reviews: Mutex<Vec<ReviewReport>>,
+    budget: Budget,
+    shared_budgets: Vec<Arc<BudgetTracker>>,
+    transcript: Mutex<Transcript>,
+    turn: AtomicUsize,
 }
 
 impl Agent {
@@ -26,86 +45,368 @@ impl Agent {
         let registry_arc = Arc::new(tool_registry);
         let dispatcher = ToolExecutionDispatcher::new(registry_arc.clone());
 
+        let transcript = Transcript::new(provider.metadata().name, None, system_prompt.clone());
+
         Self {
             provider,
-            context: Mutex::new(ConversationContext::new(system_prompt, 4096)), // Default context window
+            context: Mutex::new(
+                ConversationContext::new(system_prompt, 4096), // Default context window
+            ),
             tool_registry: registry_arc,
             tool_dispatcher: dispatcher,
+            observers: Vec::new(),
+            reviewer: None,
+            reviews: Mutex::new(Vec::new()),
+            budget: Budget::default(),
+            shared_budgets: Vec::new(),
+            transcript: Mutex::new(transcript),
+            turn: AtomicUsize::new(0),
+        }
+    }
+
+    /// Subscribe to events from the agent loop
+    pub fn with_observer(mut self, observer: Arc<dyn AgentObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    fn emit(&self, event: AgentEvent) {
+        for observer in &self.observers {
+            observer.on_event(&event);
         }
     }
 
+    /// Require approval before mutating tools run
+    pub fn with_approver(mut self, approver: Arc<dyn Approver>) -> Self {
+        self.tool_dispatcher = self.tool_dispatcher.with_approver(approver);
+        self
+    }
+
+    /// Record every tool call in `log`
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.tool_dispatcher = self.tool_dispatcher.with_audit_log(log);
+        self
+    }
+
+    /// Have mutating tools report what they would do instead of running
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.tool_dispatcher = self.tool_dispatcher.with_dry_run(dry_run);
+        self
+    }
+
+    /// Cap the size of each tool result added to the conversation
+    pub fn with_tool_output_limit(mut self, bytes: usize) -> Self {
+        self.tool_dispatcher = self.tool_dispatcher.with_output_limit(bytes);
+        self
+    }
+
+    /// Approval decisions made during this agent's lifetime
+    pub fn approvals(&self) -> Vec<ApprovalRecord> {
+        self.tool_dispatcher.approvals()
+    }
+
+    /// Critique every final answer and revise it until it meets the rubric threshold
+    pub fn with_reviewer(mut self, reviewer: Reviewer) -> Self {
+        self.reviewer = Some(reviewer);
+        self
+    }
+
+    /// Review rounds for the answers given so far
+    pub fn reviews(&self) -> Vec<ReviewReport> {
+        self.reviews
+            .lock()
+            .map(|reviews| reviews.clone())
+            .unwrap_or_default()
+    }
+
+    /// Limits applied to each call of `run`, counted afresh every time
+    pub fn with_budget(mut self, budget: Budget) -> Self {
+        self.budget = budget;
+        self
+    }
+
+    /// Limits spanning many runs, such as every step of a workflow, or the
+    /// run that started this agent as a sub-agent. May be given more than once.
+    pub fn with_shared_budget(mut self, tracker: Arc<BudgetTracker>) -> Self {
+        self.shared_budgets.push(tracker);
+        self
+    }
+
+    /// Select how the conversation is compacted once it outgrows the context window
+    pub fn with_compaction(mut self, strategy: CompactionStrategy) -> Self {
+        let context = self
+            .context
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        self.context = Mutex::new(context.with_strategy(strategy));
+        self
+    }
+
+    /// Continue an earlier conversation, e.g. one restored from a session
+    pub fn with_context(mut self, context: ConversationContext) -> Self {
+        self.context = Mutex::new(context);
+        self
+    }
+
+    /// Continue the conversation recorded in `transcript`, keeping its history
+    pub fn with_transcript(mut self, transcript: Transcript) -> Self {
+        let context = self
+            .context
+            .into_inner()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let restored = transcript
+            .to_context(context.max_tokens())
+            .with_strategy(context.strategy());
+        self.context = Mutex::new(restored);
+        self.turn = AtomicUsize::new(transcript.turns());
+        self.transcript = Mutex::new(transcript);
+        self
+    }
+
+    /// Every message so far with timings, including compacted ones
+    pub fn transcript(&self) -> Result<Transcript, XzardgzError> {
+        let transcript = self.transcript.lock().map_err(|_| {
+            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
+                "Transcript lock poisoned".to_string(),
+            ))
+        })?;
+        Ok(transcript.clone())
+    }
+
+    /// Snapshot of the conversation so far
+    pub fn context(&self) -> Result<ConversationContext, XzardgzError> {
+        let context = self.context.lock().map_err(|_| {
+            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
+                "Context lock poisoned".to_string(),
+            ))
+        })?;
+        Ok(context.clone())
+    }
+
+    fn message_count(&self) -> Result<usize, XzardgzError> {
+        let context = self.context.lock().map_err(|_| {
+            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
+                "Context lock poisoned".to_string(),
+            ))
+        })?;
+        Ok(context.get_messages().len())
+    }
+
     pub async fn run(&self, input: &str) -> Result<String, XzardgzError> {
-        // 1. Add user message
-        {
-            let mut context = self.context.lock().map_err(|_| {
-                XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                    "Context lock poisoned".to_string(),
-                ))
-            })?;
-            context.add_message(Message::user(input));
+        self.run_with_cancel(input, &CancellationToken::new()).await
+    }
+
+    /// Like `run`, but aborts the turn with `XzardgzError::Cancelled` once `cancel`
+    /// fires. The conversation stays consistent, so the agent can be reused.
+    pub async fn run_with_cancel(
+        &self,
+        input: &str,
+        cancel: &CancellationToken,
+    ) -> Result<String, XzardgzError> {
+        self.run_scoped(input, &ToolContext::new(cancel.clone()))
+            .await
+    }
+
+    /// Like `run_with_cancel`, as part of the run described by `context`, which
+    /// is how a sub-agent shares its parent's cancellation and delegation count.
+    /// When `context.tools` is set, only those of the agent's tools are offered.
+    pub async fn run_in(&self, input: &str, context: &ToolContext) -> Result<String, XzardgzError> {
+        self.run_scoped(input, context).await
+    }
+
+    /// Like `run_with_cancel`, but the model only sees, and may only call,
+    /// the agent's tools that belong to `profile`
+    pub async fn run_with_profile(
+        &self,
+        input: &str,
+        cancel: &CancellationToken,
+        profile: &ToolProfile,
+    ) -> Result<String, XzardgzError> {
+        let context = ToolContext {
+            tools: Some(profile.apply(&self.tool_registry)),
+            ..ToolContext::new(cancel.clone())
+        };
+        self.run_scoped(input, &context).await
+    }
+
+    async fn run_scoped(&self, input: &str, context: &ToolContext) -> Result<String, XzardgzError> {
+        let started = Instant::now();
+        self.turn.fetch_add(1, Ordering::SeqCst);
+        self.emit(AgentEvent::TurnStarted {
+            input: input.to_string(),
+        });
+
+        let mut trackers = vec![Arc::new(BudgetTracker::new(self.budget.clone()))];
+        trackers.extend(self.shared_budgets.iter().cloned());
+        // Sub-agents started during the turn count against the same budgets
+        let context = &ToolContext {
+            budgets: trackers.clone(),
+            ..context.clone()
+        };
+        let budgets = Budgets::new(trackers);
+
+        let result = match self.run_turn(input, context, &budgets).await {
+            Ok(answer) => self.review(input, answer, context, &budgets).await,
+            Err(e) => Err(e),
+        };
+        match &result {
+            Ok(response) => self.emit(AgentEvent::TurnCompleted {
+                response: response.clone(),
+                duration: started.elapsed(),
+            }),
+            Err(e) => self.emit(AgentEvent::Error {
+                message: e.to_string(),
+            }),
         }
+        result
+    }
 
-        // 2. Loop for tool execution
+    async fn run_turn(
+        &self,
+        input: &str,
+        context: &ToolContext,
+        budgets: &Budgets,
+    ) -> Result<String, XzardgzError> {
+        let cancel = &context.cancel;
+        let scope = context.tools.as_ref();
+        let registry = scope.unwrap_or(&self.tool_registry);
+        // 1. Add user message
+        self.add_message(Message::user(input), None, None)?;
+
+        // 2. Loop for tool execution, remembering the latest answer in case a
+        // budget runs out before the final one
+        let mut partial: Option<String> = None;
         let mut iterations = 0;
         const MAX_ITERATIONS: usize = 5;
 
         loop {
             if iterations >= MAX_ITERATIONS {
-                return Err(XzardgzError::Workflow(
-                    crate::error::WorkflowError::Execution(
-                        "Max agent iterations reached".to_string(),
-                    ),
-                ));
+                let exceeded = Exceeded {
+                    kind: BudgetKind::Iterations,
+                    used: iterations as u64,
+                    limit: MAX_ITERATIONS as u64,
+                };
+                return Err(exceeded.into_error(partial));
             }
             iterations += 1;
 
-            // 3. Call provider
+            // 3. Compact before the context outgrows the window, then call provider
+            let messages_before = self.message_count()?;
+            let compacted = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Err(XzardgzError::Cancelled),
+                exceeded = budgets.expired() => return Err(exceeded.into_error(partial)),
+                compacted = compaction::compact(self.provider.as_ref(), &self.context, budgets) => compacted?,
+            };
+            if compacted {
+                self.emit(AgentEvent::ContextCompacted {
+                    messages_before,
+                    messages_after: self.message_count()?,
+                });
+            }
+
             let (messages, tools) = {
                 let context = self.context.lock().map_err(|_| {
                     XzardgzError::Workflow(crate::error::WorkflowError::Execution(
                         "Context lock poisoned".to_string(),
                     ))
                 })?;
-                (
-                    context.get_messages().to_vec(),
-                    self.tool_registry.list_tools(),
-                )
+                (context.get_messages().to_vec(), registry.list_tools())
             };
 
-            let response = self.provider.complete(&messages, &tools).await?;
+            budgets
+                .check_provider_call()
+                .map_err(|exceeded| exceeded.into_error(partial.clone()))?;
+            self.emit(AgentEvent::ProviderRequest {
+                iteration: iterations,
+                message_count: messages.len(),
+                tool_count: tools.len(),
+            });
+            let requested = Instant::now();
+            let response = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Err(XzardgzError::Cancelled),
+                exceeded = budgets.expired() => return Err(exceeded.into_error(partial)),
+                response = self.provider.complete(&messages, &tools) => response?,
+            };
+            let duration = requested.elapsed();
+            let tokens =
+                estimate_tokens(&messages) + estimate_tokens(std::slice::from_ref(&response));
+            budgets.record_provider_call(tokens);
+            if !response.content.trim().is_empty() {
+                partial = Some(response.content.clone());
+            }
+            self.emit(AgentEvent::ProviderResponse {
+                iteration: iterations,
+                tool_calls: response.tool_calls.as_ref().map_or(0, Vec::len),
+                duration,
+            });
 
             // 4. Process response
-            {
-                let mut context = self.context.lock().map_err(|_| {
-                    XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                        "Context lock poisoned".to_string(),
-                    ))
-                })?;
-                context.add_message(response.clone());
-            }
+            self.add_message(response.clone(), Some(duration), Some(tokens))?;
 
             if let Some(tool_calls) = &response.tool_calls {
                 if tool_calls.is_empty() {
                     return Ok(response.content);
                 }
 
-                for call in tool_calls {
-                    let result = self.tool_dispatcher.execute(call).await?;
+                for (index, call) in tool_calls.iter().enumerate() {
+                    self.emit(AgentEvent::ToolCallStarted {
+                        id: call.id.clone(),
+                        name: call.function.name.clone(),
+                        arguments: call.function.arguments.clone(),
+                    });
+                    let started = Instant::now();
+                    let result = match budgets.check_tool_call() {
+                        // The model may still name a tool it saw before the scope narrowed
+                        Ok(())
+                            if scope.is_some_and(|scope| {
+                                scope.get_tool(&call.function.name).is_none()
+                            }) =>
+                        {
+                            Ok(ToolResult::failure(format!(
+                                "Tool {} is not available here",
+                                call.function.name
+                            )))
+                        }
+                        Ok(()) => tokio::select! {
+                            biased;
+                            _ = cancel.cancelled() => Err(XzardgzError::Cancelled),
+                            exceeded = budgets.expired() => Err(exceeded.into_error(partial.clone())),
+                            result = self.tool_dispatcher.execute_in(call, context) => Ok(result?),
+                        },
+                        Err(exceeded) => Err(exceeded.into_error(partial.clone())),
+                    };
+                    let result = match result {
+                        Ok(result) => result,
+                        Err(e) => {
+                            // Answer the remaining calls so no request is left without a result
+                            let reason = if matches!(e, XzardgzError::Cancelled) {
+                                "Cancelled by user".to_string()
+                            } else {
+                                e.to_string()
+                            };
+                            for skipped in &tool_calls[index..] {
+                                self.add_tool_result(skipped, format!("Error: {}", reason), None)?;
+                            }
+                            return Err(e);
+                        }
+                    };
+                    budgets.record_tool_call();
+                    self.emit(AgentEvent::ToolCallFinished {
+                        id: call.id.clone(),
+                        name: call.function.name.clone(),
+                        output: result.output.clone(),
+                        error: result.error.clone(),
+                        duration: started.elapsed(),
+                    });
 
-                    // Add tool result message
-                    let tool_msg = Message {
-                        role: Role::Tool,
-                        content: result.output, // Or error?
-                        tool_calls: None,
-                        tool_call_id: Some(call.id.clone()),
-                        name: Some(call.function.name.clone()),
+                    // Add tool result message, surfacing failures to the model
+                    let content = match result.error {
+                        Some(error) => format!("Error: {}", error),
+                        None => result.output,
                     };
-                    let mut context = self.context.lock().map_err(|_| {
-                        XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                            "Context lock poisoned".to_string(),
-                        ))
-                    })?;
-                    context.add_message(tool_msg);
+                    self.add_tool_result(call, content, Some(started.elapsed()))?;
                 }
                 // Loop continues to send tool results back to provider
             } else {
@@ -114,4 +415,121 @@ impl Agent {
             }
         }
     }
+
+    /// Score `answer` and ask for revisions until it passes or the revision
+    /// limit is hit. A failing reviewer leaves the answer as it is.
+    async fn review(
+        &self,
+        input: &str,
+        mut answer: String,
+        context: &ToolContext,
+        budgets: &Budgets,
+    ) -> Result<String, XzardgzError> {
+        let cancel = &context.cancel;
+        let Some(reviewer) = &self.reviewer else {
+            return Ok(answer);
+        };
+
+        let mut report = ReviewReport::new(input);
+        loop {
+            // Reviews are provider calls of the run like any other
+            if let Err(exceeded) = budgets.check_provider_call() {
+                return Err(exceeded.into_error(Some(answer)));
+            }
+            let scores = tokio::select! {
+                biased;
+                _ = cancel.cancelled() => return Err(XzardgzError::Cancelled),
+                exceeded = budgets.expired() => return Err(exceeded.into_error(Some(answer))),
+                scores = reviewer.score(input, &answer, None) => scores,
+            };
+            budgets.record_provider_call(estimate_tokens(&[
+                Message::user(input),
+                Message::assistant(answer.as_str()),
+            ]));
+            let scores = match scores {
+                Ok(scores) => scores,
+                Err(e) => {
+                    tracing::warn!("Review failed, keeping answer unreviewed: {}", e);
+                    break;
+                }
+            };
+
+            let passed = reviewer.passes(&scores);
+            self.emit(AgentEvent::ReviewCompleted {
+                round: report.rounds.len() + 1,
+                scores: scores.clone(),
+                passed,
+            });
+            report.rounds.push(scores);
+            report.passed = passed;
+            if passed || report.revisions() >= reviewer.config().max_revisions {
+                break;
+            }
+
+            let prompt = Reviewer::revision_prompt(&report.rounds[report.rounds.len() - 1]);
+            answer = match self.run_turn(&prompt, context, budgets).await {
+                Ok(revised) => revised,
+                // The reviewed answer is a better partial result than none
+                Err(XzardgzError::BudgetExceeded {
+                    kind,
+                    used,
+                    limit,
+                    partial,
+                }) => {
+                    return Err(XzardgzError::BudgetExceeded {
+                        kind,
+                        used,
+                        limit,
+                        partial: partial.or(Some(answer)),
+                    });
+                }
+                Err(e) => return Err(e),
+            };
+        }
+
+        if let Ok(mut reviews) = self.reviews.lock() {
+            reviews.push(report);
+        }
+        Ok(answer)
+    }
+
+    fn add_tool_result(
+        &self,
+        call: &ToolCall,
+        content: String,
+        duration: Option<Duration>,
+    ) -> Result<(), XzardgzError> {
+        let tool_msg = Message {
+            role: Role::Tool,
+            content,
+            tool_calls: None,
+            tool_call_id: Some(call.id.clone()),
+            name: Some(call.function.name.clone()),
+        };
+        self.add_message(tool_msg, duration, None)
+    }
+
+    /// Append to the context and record in the transcript
+    fn add_message(
+        &self,
+        message: Message,
+        duration: Option<Duration>,
+        tokens: Option<u64>,
+    ) -> Result<(), XzardgzError> {
+        if let Ok(mut transcript) = self.transcript.lock() {
+            transcript.record(
+                self.turn.load(Ordering::SeqCst),
+                message.clone(),
+                duration,
+                tokens,
+            );
+        }
+        let mut context = self.context.lock().map_err(|_| {
+            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
+                "Context lock poisoned".to_string(),
+            ))
+        })?;
+        context.add_message(message);
+        Ok(())
+    }
 }
@@ -0,0 +1,142 @@
+use crate::agent::review::ReviewScores;
+use std::time::Duration;
+
+/// Something that happened inside `Agent::run`
+#[derive(Debug, Clone)]
+pub enum AgentEvent {
+    TurnStarted {
+        input: String,
+    },
+    TurnCompleted {
+        response: String,
+        duration: Duration,
+    },
+    ProviderRequest {
+        iteration: usize,
+        message_count: usize,
+        tool_count: usize,
+    },
+    ProviderResponse {
+        iteration: usize,
+        tool_calls: usize,
+        duration: Duration,
+    },
+    ToolCallStarted {
+        id: String,
+        name: String,
+        arguments: String,
+    },
+    ToolCallFinished {
+        id: String,
+        name: String,
+        output: String,
+        error: Option<String>,
+        duration: Duration,
+    },
+    ContextCompacted {
+        messages_before: usize,
+        messages_after: usize,
+    },
+    ReviewCompleted {
+        round: usize,
+        scores: ReviewScores,
+        passed: bool,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Subscriber to agent events. Called synchronously from the agent loop, so
+/// implementations should hand slow work off (e.g. to a channel).
+pub trait AgentObserver: Send + Sync {
+    fn on_event(&self, event: &AgentEvent);
+}
+
+/// Forwards every event to `tracing`
+pub struct TracingObserver;
+
+impl AgentObserver for TracingObserver {
+    fn on_event(&self, event: &AgentEvent) {
+        match event {
+            AgentEvent::TurnStarted { input } => tracing::debug!("Turn started: {}", input),
+            AgentEvent::TurnCompleted { duration, .. } => {
+                tracing::debug!("Turn completed in {:?}", duration)
+            }
+            AgentEvent::ProviderRequest {
+                iteration,
+                message_count,
+                tool_count,
+            } => tracing::debug!(
+                "Provider request #{} ({} messages, {} tools)",
+                iteration,
+                message_count,
+                tool_count
+            ),
+            AgentEvent::ProviderResponse {
+                iteration,
+                tool_calls,
+                duration,
+            } => tracing::debug!(
+                "Provider response #{} in {:?} ({} tool calls)",
+                iteration,
+                duration,
+                tool_calls
+            ),
+            AgentEvent::ToolCallStarted {
+                name, arguments, ..
+            } => tracing::info!("Calling tool {} {}", name, arguments),
+            AgentEvent::ToolCallFinished {
+                name,
+                error,
+                duration,
+                ..
+            } => match error {
+                Some(error) => tracing::warn!("Tool {} failed in {:?}: {}", name, duration, error),
+                None => tracing::debug!("Tool {} finished in {:?}", name, duration),
+            },
+            AgentEvent::ContextCompacted {
+                messages_before,
+                messages_after,
+            } => tracing::info!(
+                "Context compacted from {} to {} messages",
+                messages_before,
+                messages_after
+            ),
+            AgentEvent::ReviewCompleted {
+                round,
+                scores,
+                passed,
+            } => tracing::info!(
+                "Review round {}: {} ({})",
+                round,
+                scores,
+                if *passed { "passed" } else { "revising" }
+            ),
+            AgentEvent::Error { message } => tracing::error!("Agent error: {}", message),
+        }
+    }
+}
+
+/// Republishes events on a broadcast channel for async subscribers (dashboards,
+/// TUIs). Events are dropped when nobody is listening.
+pub struct BroadcastObserver {
+    sender: tokio::sync::broadcast::Sender<AgentEvent>,
+}
+
+impl BroadcastObserver {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = tokio::sync::broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<AgentEvent> {
+        self.sender.subscribe()
+    }
+}
+
+impl AgentObserver for BroadcastObserver {
+    fn on_event(&self, event: &AgentEvent) {
+        let _ = self.sender.send(event.clone());
+    }
+}
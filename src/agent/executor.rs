@@ -1,3 +1,5 @@
+use crate::agent::budget::{BudgetKind, Budgets, Exceeded};
+use crate::agent::compaction;
 use crate::agent::context::ConversationContext;
 use crate::error::XzardgzError;
 use crate::providers::base::Provider;
@@ -43,6 +45,8 @@ impl AgentExecutor {
         for iteration in 0..self.max_iterations {
             tracing::debug!("Agent iteration {}/{}", iteration + 1, self.max_iterations);
 
+            compaction::compact(self.provider.as_ref(), &self.context, &Budgets::default()).await?;
+
             // Get conversation state and call provider
             let (messages, tools) = {
                 let context = self.context.lock().map_err(|_| {
@@ -76,9 +80,13 @@ impl AgentExecutor {
                     let result = self.tool_dispatcher.execute(call).await?;
 
                     // Add tool result message
+                    let content = match result.error {
+                        Some(error) => format!("Error: {}", error),
+                        None => result.output,
+                    };
                     let tool_msg = Message {
                         role: Role::Tool,
-                        content: result.output,
+                        content,
                         tool_calls: None,
                         tool_call_id: Some(call.id.clone()),
                         name: Some(call.function.name.clone()),
@@ -98,8 +106,11 @@ impl AgentExecutor {
             }
         }
 
-        Err(XzardgzError::Workflow(
-            crate::error::WorkflowError::Execution("Max agent iterations reached".to_string()),
-        ))
+        let exceeded = Exceeded {
+            kind: BudgetKind::Iterations,
+            used: self.max_iterations as u64,
+            limit: self.max_iterations as u64,
+        };
+        Err(exceeded.into_error(None))
     }
 }
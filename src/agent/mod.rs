@@ -1,5 +1,11 @@
+pub mod budget;
+pub mod compaction;
 pub mod context;
 pub mod core;
+pub mod events;
 pub mod executor;
 pub mod message;
+pub mod review;
+pub mod session;
 pub mod state;
+pub mod transcript;
@@ -0,0 +1,193 @@
+use crate::docgen::diataxis::DocCategory;
+use crate::error::{WorkflowError, XzardgzError};
+use crate::providers::base::Provider;
+use crate::providers::types::Message;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// Highest score a reviewer can give for one criterion
+pub const MAX_SCORE: u8 = 5;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewConfig {
+    /// Run the critique pass after every final answer
+    pub enabled: bool,
+    /// Lowest acceptable score (1-5) for every rubric criterion
+    pub threshold: u8,
+    /// Revisions attempted before the last answer is accepted as is
+    pub max_revisions: usize,
+}
+
+impl Default for ReviewConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: 4,
+            max_revisions: 2,
+        }
+    }
+}
+
+/// Rubric scores from one review round
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReviewScores {
+    /// Claims are supported by the context the writer was given
+    pub accuracy: u8,
+    /// Content matches the requested Diataxis category
+    pub diataxis_fit: u8,
+    /// Everything the task asked for is covered
+    pub completeness: u8,
+    /// What to change to raise the scores
+    #[serde(default)]
+    pub feedback: String,
+}
+
+impl ReviewScores {
+    pub fn lowest(&self) -> u8 {
+        self.accuracy.min(self.diataxis_fit).min(self.completeness)
+    }
+}
+
+impl fmt::Display for ReviewScores {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "accuracy {}/{max}, diataxis fit {}/{max}, completeness {}/{max}",
+            self.accuracy,
+            self.diataxis_fit,
+            self.completeness,
+            max = MAX_SCORE
+        )
+    }
+}
+
+/// Every review round for one answer, oldest first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewReport {
+    pub task: String,
+    pub rounds: Vec<ReviewScores>,
+    pub passed: bool,
+}
+
+impl ReviewReport {
+    pub fn new(task: impl Into<String>) -> Self {
+        Self {
+            task: task.into(),
+            rounds: Vec::new(),
+            passed: false,
+        }
+    }
+
+    /// Scores of the answer that was finally kept
+    pub fn final_scores(&self) -> Option<&ReviewScores> {
+        self.rounds.last()
+    }
+
+    pub fn revisions(&self) -> usize {
+        self.rounds.len().saturating_sub(1)
+    }
+}
+
+/// Scores drafts against the rubric with a separate provider request, so the
+/// reviewer never sees (or pollutes) the writer's conversation.
+pub struct Reviewer {
+    provider: Arc<dyn Provider>,
+    config: ReviewConfig,
+}
+
+impl Reviewer {
+    pub fn new(provider: Arc<dyn Provider>, config: ReviewConfig) -> Self {
+        Self { provider, config }
+    }
+
+    pub fn config(&self) -> &ReviewConfig {
+        &self.config
+    }
+
+    pub fn passes(&self, scores: &ReviewScores) -> bool {
+        scores.lowest() >= self.config.threshold
+    }
+
+    /// Score `draft`, written in response to `task`. Without a `category` the
+    /// Diataxis criterion only checks the answer suits the kind of request.
+    pub async fn score(
+        &self,
+        task: &str,
+        draft: &str,
+        category: Option<DocCategory>,
+    ) -> Result<ReviewScores, XzardgzError> {
+        let prompt = Self::build_prompt(task, draft, category);
+        let response = self
+            .provider
+            .complete(&[Message::user(prompt)], &[])
+            .await?;
+        Self::parse(&response.content)
+    }
+
+    /// Accept the scores as raw JSON or wrapped in a markdown code block
+    pub fn parse(response: &str) -> Result<ReviewScores, XzardgzError> {
+        let json = match (response.find('{'), response.rfind('}')) {
+            (Some(start), Some(end)) if start < end => &response[start..=end],
+            _ => response,
+        };
+        let scores: ReviewScores = serde_json::from_str(json).map_err(|e| {
+            WorkflowError::Parse(format!(
+                "Invalid review response: {}. Response: {}",
+                e, response
+            ))
+        })?;
+        for score in [scores.accuracy, scores.diataxis_fit, scores.completeness] {
+            if !(1..=MAX_SCORE).contains(&score) {
+                return Err(WorkflowError::Parse(format!(
+                    "Review score {} is outside 1-{}",
+                    score, MAX_SCORE
+                ))
+                .into());
+            }
+        }
+        Ok(scores)
+    }
+
+    /// Follow-up prompt asking the writer to address the reviewer's feedback
+    pub fn revision_prompt(scores: &ReviewScores) -> String {
+        format!(
+            r#"A reviewer scored your answer: {}.
+Feedback: {}
+
+Revise your answer to address the feedback. Reply with the complete revised answer in the same format as before."#,
+            scores, scores.feedback
+        )
+    }
+
+    fn build_prompt(task: &str, draft: &str, category: Option<DocCategory>) -> String {
+        let diataxis = match category {
+            Some(category) => format!(
+                "how well the draft fits a Diataxis {} document (and only that)",
+                category
+            ),
+            None => "whether the draft suits the kind of answer the task asks for; \
+                documentation must fit a single Diataxis category"
+                .to_string(),
+        };
+        format!(
+            r#"You are reviewing a draft written for the task below. Score it from 1 (poor) to {max} (excellent) on:
+- accuracy: claims are supported by the context in the task; nothing is invented
+- diataxis_fit: {diataxis}
+- completeness: everything the task asks for is covered
+
+Task:
+{task}
+
+Draft:
+{draft}
+
+Return only a JSON object:
+{{"accuracy": 1, "diataxis_fit": 1, "completeness": 1, "feedback": "Specific changes that would raise the scores"}}"#,
+            max = MAX_SCORE,
+            diataxis = diataxis,
+            task = task,
+            draft = draft
+        )
+    }
+}
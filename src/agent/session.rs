@@ -0,0 +1,219 @@
+use crate::agent::context::ConversationContext;
+use crate::agent::message::Role;
+use crate::error::SessionError;
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A persisted chat conversation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub forked_from: Option<String>,
+    pub context: ConversationContext,
+}
+
+/// One line of `chat --list`
+#[derive(Debug, Clone)]
+pub struct SessionSummary {
+    pub id: String,
+    pub updated_at: DateTime<Utc>,
+    pub message_count: usize,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    Md,
+    Json,
+}
+
+impl Session {
+    pub fn new(provider: String, model: Option<String>, context: ConversationContext) -> Self {
+        let now = Utc::now();
+        Self {
+            id: now.format("%Y%m%d-%H%M%S-%3f").to_string(),
+            created_at: now,
+            updated_at: now,
+            provider,
+            model,
+            forked_from: None,
+            context,
+        }
+    }
+
+    /// Copy of this session under a new id, remembering where it came from
+    pub fn fork(&self) -> Self {
+        let mut forked = Session::new(
+            self.provider.clone(),
+            self.model.clone(),
+            self.context.clone(),
+        );
+        forked.forked_from = Some(self.id.clone());
+        forked
+    }
+
+    /// First user message, shortened for listings
+    pub fn title(&self) -> String {
+        let first = self
+            .context
+            .get_messages()
+            .iter()
+            .find(|m| m.role == Role::User)
+            .map(|m| m.content.lines().next().unwrap_or_default().to_string())
+            .unwrap_or_else(|| "(empty)".to_string());
+        if first.chars().count() > 60 {
+            format!("{}...", first.chars().take(57).collect::<String>())
+        } else {
+            first
+        }
+    }
+
+    pub fn export(&self, format: ExportFormat) -> Result<String, SessionError> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| SessionError::Serialization(e.to_string())),
+            ExportFormat::Md => Ok(self.to_markdown()),
+        }
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = format!("# Session {}\n\n", self.id);
+        out.push_str(&format!("- Provider: {}\n", self.provider));
+        if let Some(model) = &self.model {
+            out.push_str(&format!("- Model: {}\n", model));
+        }
+        out.push_str(&format!("- Created: {}\n", self.created_at.to_rfc3339()));
+        out.push_str(&format!("- Updated: {}\n", self.updated_at.to_rfc3339()));
+        if let Some(parent) = &self.forked_from {
+            out.push_str(&format!("- Forked from: {}\n", parent));
+        }
+        out.push_str(&format!(
+            "\n## System\n\n{}\n",
+            self.context.system_prompt()
+        ));
+
+        for message in self.context.get_messages() {
+            let heading = match message.role {
+                Role::System => "System".to_string(),
+                Role::User => "User".to_string(),
+                Role::Assistant => "Assistant".to_string(),
+                Role::Tool => format!(
+                    "Tool result ({})",
+                    message.name.as_deref().unwrap_or("unknown")
+                ),
+            };
+            out.push_str(&format!("\n## {}\n\n", heading));
+            if !message.content.is_empty() {
+                out.push_str(&message.content);
+                out.push('\n');
+            }
+            for call in message.tool_calls.iter().flatten() {
+                out.push_str(&format!(
+                    "\n```json\n// {} ({})\n{}\n```\n",
+                    call.function.name, call.id, call.function.arguments
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// JSON files, one per session, under the XDG data directory
+pub struct SessionStore {
+    root: PathBuf,
+}
+
+impl SessionStore {
+    pub fn new<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: root.as_ref().to_path_buf(),
+        }
+    }
+
+    /// `$XDG_DATA_HOME/xzardgz/sessions` (or the platform equivalent)
+    pub fn open_default() -> Result<Self, SessionError> {
+        let dirs = ProjectDirs::from("", "", "xzardgz").ok_or_else(|| {
+            SessionError::Storage("Could not determine data directory".to_string())
+        })?;
+        Ok(Self::new(dirs.data_dir().join("sessions")))
+    }
+
+    /// File of session `id`. Ids are generated as `20250101-120000-000`, so
+    /// anything but letters, digits, `-` and `_` is refused rather than joined
+    /// into a path that could leave the session directory.
+    fn path(&self, id: &str) -> Result<PathBuf, SessionError> {
+        let valid = !id.is_empty()
+            && id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid {
+            return Err(SessionError::InvalidId(id.to_string()));
+        }
+        Ok(self.root.join(format!("{}.json", id)))
+    }
+
+    pub fn save(&self, session: &mut Session) -> Result<(), SessionError> {
+        fs::create_dir_all(&self.root).map_err(|e| {
+            SessionError::Storage(format!("Failed to create {:?}: {}", self.root, e))
+        })?;
+        session.updated_at = Utc::now();
+        let content = serde_json::to_string_pretty(session)
+            .map_err(|e| SessionError::Serialization(e.to_string()))?;
+        let path = self.path(&session.id)?;
+        fs::write(&path, content)
+            .map_err(|e| SessionError::Storage(format!("Failed to write {:?}: {}", path, e)))
+    }
+
+    pub fn load(&self, id: &str) -> Result<Session, SessionError> {
+        let path = self.path(id)?;
+        if !path.exists() {
+            return Err(SessionError::NotFound(id.to_string()));
+        }
+        let content = fs::read_to_string(&path)
+            .map_err(|e| SessionError::Storage(format!("Failed to read {:?}: {}", path, e)))?;
+        serde_json::from_str(&content).map_err(|e| SessionError::Serialization(e.to_string()))
+    }
+
+    /// All stored sessions, most recently updated first
+    pub fn list(&self) -> Result<Vec<SessionSummary>, SessionError> {
+        if !self.root.exists() {
+            return Ok(Vec::new());
+        }
+
+        let entries = fs::read_dir(&self.root)
+            .map_err(|e| SessionError::Storage(format!("Failed to read {:?}: {}", self.root, e)))?;
+
+        let mut summaries = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            match self.load(id) {
+                Ok(session) => summaries.push(SessionSummary {
+                    id: session.id.clone(),
+                    updated_at: session.updated_at,
+                    message_count: session.context.get_messages().len(),
+                    title: session.title(),
+                }),
+                Err(e) => tracing::warn!("Skipping unreadable session {:?}: {}", path, e),
+            }
+        }
+
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(summaries)
+    }
+}
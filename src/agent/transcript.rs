@@ -0,0 +1,207 @@
+use crate::agent::budget::Usage;
+use crate::agent::context::ConversationContext;
+use crate::agent::core::Agent;
+use crate::agent::message::{Message, Role};
+use crate::agent::session::ExportFormat;
+use crate::error::{SessionError, XzardgzError};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Duration;
+
+/// Complete record of an agent conversation. Unlike `ConversationContext` it is
+/// never compacted, and it keeps timings and usage for each message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Transcript {
+    pub provider: String,
+    #[serde(default)]
+    pub model: Option<String>,
+    pub system_prompt: String,
+    pub started_at: DateTime<Utc>,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// Which call of `Agent::run` produced the message, starting at 1
+    pub turn: usize,
+    pub timestamp: DateTime<Utc>,
+    pub message: Message,
+    /// Provider response time for assistant messages, run time for tool results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Estimated tokens of the provider call that produced an assistant message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+}
+
+impl Transcript {
+    pub fn new(provider: String, model: Option<String>, system_prompt: String) -> Self {
+        Self {
+            provider,
+            model,
+            system_prompt,
+            started_at: Utc::now(),
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn record(
+        &mut self,
+        turn: usize,
+        message: Message,
+        duration: Option<Duration>,
+        tokens: Option<u64>,
+    ) {
+        self.entries.push(TranscriptEntry {
+            turn,
+            timestamp: Utc::now(),
+            message,
+            duration_ms: duration.map(|d| d.as_millis() as u64),
+            tokens,
+        });
+    }
+
+    /// Highest turn number recorded so far
+    pub fn turns(&self) -> usize {
+        self.entries.last().map_or(0, |entry| entry.turn)
+    }
+
+    /// The user message that started each turn, in order
+    pub fn inputs(&self) -> Vec<&str> {
+        let mut inputs = Vec::new();
+        let mut last_turn = 0;
+        for entry in &self.entries {
+            if entry.turn != last_turn && entry.message.role == Role::User {
+                inputs.push(entry.message.content.as_str());
+                last_turn = entry.turn;
+            }
+        }
+        inputs
+    }
+
+    /// Totals over the whole transcript; `elapsed` is time spent waiting on
+    /// the provider and tools
+    pub fn usage(&self) -> Usage {
+        Self::sum(self.entries.iter())
+    }
+
+    /// Usage of each turn, indexed from turn 1
+    pub fn usage_by_turn(&self) -> Vec<Usage> {
+        (1..=self.turns())
+            .map(|turn| Self::sum(self.entries.iter().filter(|e| e.turn == turn)))
+            .collect()
+    }
+
+    fn sum<'a>(entries: impl Iterator<Item = &'a TranscriptEntry>) -> Usage {
+        let mut usage = Usage::default();
+        for entry in entries {
+            match entry.message.role {
+                Role::Assistant => usage.provider_calls += 1,
+                Role::Tool => usage.tool_calls += 1,
+                _ => {}
+            }
+            usage.tokens += entry.tokens.unwrap_or_default();
+            usage.elapsed += Duration::from_millis(entry.duration_ms.unwrap_or_default());
+        }
+        usage
+    }
+
+    /// Conversation to continue from, with every recorded message
+    pub fn to_context(&self, max_tokens: usize) -> ConversationContext {
+        let mut context = ConversationContext::new(self.system_prompt.clone(), max_tokens);
+        for entry in &self.entries {
+            context.add_message(entry.message.clone());
+        }
+        context
+    }
+
+    pub fn export(&self, format: ExportFormat) -> Result<String, SessionError> {
+        match format {
+            ExportFormat::Json => serde_json::to_string_pretty(self)
+                .map_err(|e| SessionError::Serialization(e.to_string())),
+            ExportFormat::Md => Ok(self.to_markdown()),
+        }
+    }
+
+    /// Only the JSON form can be loaded back; Markdown is for reading
+    pub fn from_json(content: &str) -> Result<Self, SessionError> {
+        serde_json::from_str(content).map_err(|e| SessionError::Serialization(e.to_string()))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SessionError> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| SessionError::Storage(format!("Failed to read {:?}: {}", path, e)))?;
+        Self::from_json(&content)
+    }
+
+    /// Write as Markdown for `.md` paths and as JSON otherwise
+    pub fn save(&self, path: &Path) -> Result<(), SessionError> {
+        let format = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("md") => ExportFormat::Md,
+            _ => ExportFormat::Json,
+        };
+        std::fs::write(path, self.export(format)?)
+            .map_err(|e| SessionError::Storage(format!("Failed to write {:?}: {}", path, e)))
+    }
+
+    /// Send the recorded inputs, in order, to `agent` (typically backed by a
+    /// different provider) and return the new transcript for comparison.
+    /// Failed turns are logged and the replay moves on.
+    pub async fn replay(&self, agent: &Agent) -> Result<Transcript, XzardgzError> {
+        for (index, input) in self.inputs().into_iter().enumerate() {
+            if let Err(e) = agent.run(input).await {
+                tracing::warn!("Replay of turn {} failed: {}", index + 1, e);
+            }
+        }
+        agent.transcript()
+    }
+
+    fn to_markdown(&self) -> String {
+        let mut out = String::from("# Transcript\n\n");
+        out.push_str(&format!("- Provider: {}\n", self.provider));
+        if let Some(model) = &self.model {
+            out.push_str(&format!("- Model: {}\n", model));
+        }
+        out.push_str(&format!("- Started: {}\n", self.started_at.to_rfc3339()));
+        out.push_str(&format!("- Usage: {}\n", self.usage()));
+        out.push_str(&format!("\n## System\n\n{}\n", self.system_prompt));
+
+        let mut turn = 0;
+        for entry in &self.entries {
+            if entry.turn != turn {
+                turn = entry.turn;
+                out.push_str(&format!("\n## Turn {}\n", turn));
+            }
+            let message = &entry.message;
+            let heading = match message.role {
+                Role::System => "System".to_string(),
+                Role::User => "User".to_string(),
+                Role::Assistant => "Assistant".to_string(),
+                Role::Tool => format!(
+                    "Tool result ({})",
+                    message.name.as_deref().unwrap_or("unknown")
+                ),
+            };
+            let mut details = vec![entry.timestamp.format("%H:%M:%S").to_string()];
+            if let Some(ms) = entry.duration_ms {
+                details.push(format!("{} ms", ms));
+            }
+            if let Some(tokens) = entry.tokens {
+                details.push(format!("~{} tokens", tokens));
+            }
+            out.push_str(&format!("\n### {} ({})\n\n", heading, details.join(", ")));
+            if !message.content.is_empty() {
+                out.push_str(&message.content);
+                out.push('\n');
+            }
+            for call in message.tool_calls.iter().flatten() {
+                out.push_str(&format!(
+                    "\n```json\n// {} ({})\n{}\n```\n",
+                    call.function.name, call.id, call.function.arguments
+                ));
+            }
+        }
+        out
+    }
+}
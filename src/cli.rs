@@ -6,6 +6,15 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
+
+    /// Report what file, edit and command tools would do without changing anything
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Tool profile for chat, run, generate and mcp serve (read-only, docs-writer,
+    /// full or one from tool_profiles in the config)
+    #[arg(long, global = true, value_name = "PROFILE")]
+    pub tools: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -15,12 +24,63 @@ pub enum Commands {
         /// Path to the plan file
         #[arg(required = true)]
         plan: String,
+
+        /// Continue an interrupted run, skipping completed steps
+        #[arg(long)]
+        resume: bool,
+
+        /// Write the agent transcript here (Markdown for .md, JSON otherwise)
+        #[arg(long)]
+        transcript: Option<String>,
     },
     /// Start an interactive chat session
     Chat {
         /// Optional initial message
         #[arg(short, long)]
         message: Option<String>,
+
+        /// Resume a saved session by id
+        #[arg(long, conflicts_with_all = ["fork", "from_transcript", "list", "export"])]
+        resume: Option<String>,
+
+        /// Start a new session from a copy of a saved one
+        #[arg(long, conflicts_with_all = ["from_transcript", "list", "export"])]
+        fork: Option<String>,
+
+        /// Start a new session continuing a JSON transcript
+        #[arg(long, conflicts_with_all = ["list", "export"])]
+        from_transcript: Option<String>,
+
+        /// Write the transcript here after every turn (Markdown for .md, JSON otherwise)
+        #[arg(long, conflicts_with_all = ["list", "export"])]
+        transcript: Option<String>,
+
+        /// List saved sessions
+        #[arg(long, conflicts_with = "export")]
+        list: bool,
+
+        /// Print a saved session instead of chatting
+        #[arg(long)]
+        export: Option<String>,
+
+        /// Export format
+        #[arg(long, value_enum, default_value = "md", requires = "export")]
+        format: crate::agent::session::ExportFormat,
+    },
+    /// Inspect and replay conversation transcripts
+    Transcript {
+        #[command(subcommand)]
+        command: TranscriptCommands,
+    },
+    /// Create workflow plans
+    Plan {
+        #[command(subcommand)]
+        command: PlanCommands,
+    },
+    /// Use the Model Context Protocol
+    Mcp {
+        #[command(subcommand)]
+        command: McpCommands,
     },
     /// Authenticate with providers
     Auth {
@@ -56,3 +116,64 @@ pub enum AuthCommands {
     /// Login to GitHub Copilot
     Login,
 }
+
+#[derive(Subcommand)]
+pub enum McpCommands {
+    /// Offer the workspace tools to MCP clients over stdin and stdout
+    Serve,
+}
+
+#[derive(Subcommand)]
+pub enum TranscriptCommands {
+    /// Print a JSON transcript
+    Show {
+        /// Path to the transcript
+        path: String,
+
+        /// Output format
+        #[arg(long, value_enum, default_value = "md")]
+        format: crate::agent::session::ExportFormat,
+    },
+    /// Send a transcript's user messages to another provider for comparison
+    Replay {
+        /// Path to the JSON transcript
+        path: String,
+
+        /// Provider to replay against (defaults to the configured one)
+        #[arg(long)]
+        provider: Option<String>,
+
+        /// Model to replay against
+        #[arg(long)]
+        model: Option<String>,
+
+        /// Where to write the replayed transcript
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PlanCommands {
+    /// Generate a plan from a natural-language goal
+    New {
+        /// What the plan should achieve
+        goal: String,
+
+        /// Repository path
+        #[arg(short, long, default_value = ".")]
+        repository: String,
+
+        /// Where to write the plan
+        #[arg(short, long, default_value = "plan.yaml")]
+        output: String,
+
+        /// Open the plan in $EDITOR before saving
+        #[arg(long)]
+        review: bool,
+
+        /// Follow-up prompts allowed to fix an invalid plan
+        #[arg(long, default_value_t = 3)]
+        max_repairs: usize,
+    },
+}
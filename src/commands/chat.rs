@@ -1,14 +1,33 @@
+use crate::agent::budget::Exceeded;
 use crate::agent::core::Agent;
+use crate::agent::events::TracingObserver;
+use crate::agent::review::Reviewer;
+use crate::agent::session::{ExportFormat, Session, SessionStore};
+use crate::agent::transcript::Transcript;
+use crate::commands::delegate_tool;
+use crate::commands::interrupt::InterruptHandler;
 use crate::config::Config;
 use crate::error::XzardgzError;
 use crate::providers::factory::ProviderFactory;
-use crate::tools::file_ops::{ReadFileTool, WriteFileTool};
-use crate::tools::git_ops::GitStatusTool;
-use crate::tools::registry::ToolRegistry;
+use crate::tools::approval::{Approver, PolicyApprover, read_line};
+use crate::tools::audit::AuditLog;
+use crate::tools::builtin::workspace_tools;
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::delegate::DelegateTaskTool;
+use crate::tools::workspace::Workspace;
 use std::io::{self, Write};
+use std::path::Path;
 use std::sync::Arc;
 
-pub async fn run(initial_message: Option<String>) -> Result<(), XzardgzError> {
+pub async fn run(
+    initial_message: Option<String>,
+    resume: Option<String>,
+    fork: Option<String>,
+    from_transcript: Option<String>,
+    transcript_path: Option<String>,
+    dry_run: bool,
+    tools: Option<String>,
+) -> Result<(), XzardgzError> {
     // 1. Load Config
     let config = Config::load()?;
     println!("Loaded config: Provider={}", config.provider.provider_type);
@@ -17,32 +36,124 @@ pub async fn run(initial_message: Option<String>) -> Result<(), XzardgzError> {
     let provider = ProviderFactory::create(&config.provider)?;
 
     // 3. Create Tool Registry
-    let mut registry = ToolRegistry::new();
-    registry.register(ReadFileTool::definition(), Arc::new(ReadFileTool));
-    registry.register(WriteFileTool::definition(), Arc::new(WriteFileTool));
-    registry.register(GitStatusTool::definition(), Arc::new(GitStatusTool));
+    let workspace = Arc::new(Workspace::from_config(&config.workspace)?);
+    let commands = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        config.commands.clone(),
+    ));
+    let mut registry = workspace_tools(&workspace, &commands, &config.repository.ignore_patterns)?;
+    registry.register_custom_tools(&config.custom_tools, &commands)?;
+    registry.import_mcp_servers(&config.mcp.servers).await;
+    let profiles = config.tool_profiles.clone().with_default(tools);
+    let profile = profiles.selected()?;
+    let mut registry = profile.apply(&registry);
+    println!(
+        "Tool profile: {} ({} tools)",
+        profiles.default,
+        registry.list_tools().len()
+    );
 
-    // 4. Create Agent
+    let audit = AuditLog::for_run(&config.audit, "chat")?.map(Arc::new);
+    if let Some(audit) = &audit {
+        println!("Audit log: {}", audit.path().display());
+    }
+    if dry_run {
+        println!("Dry run: file, edit and command tools will not change anything");
+    }
+
+    let approver: Arc<dyn Approver> = Arc::new(PolicyApprover::new(&config.approval));
+    let delegate = delegate_tool(
+        &config,
+        provider.clone(),
+        registry.clone(),
+        approver.clone(),
+        audit.as_ref(),
+        dry_run,
+    );
+    if profile.allows("delegate_task", false) {
+        registry.register(DelegateTaskTool::definition(), Arc::new(delegate));
+    }
+
+    // 4. Create Agent, restoring a saved session if requested
+    let store = SessionStore::open_default()?;
     let system_prompt =
         "You are XZardgz, an autonomous AI agent. You can read/write files and check git status."
             .to_string();
-    let agent = Agent::new(provider, system_prompt, registry);
+    let mut agent = Agent::new(provider.clone(), system_prompt, registry);
+    let transcript = from_transcript
+        .map(|path| Transcript::load(Path::new(&path)))
+        .transpose()?;
+    let mut session = if let Some(transcript) = &transcript {
+        let session = Session::new(
+            config.provider.provider_type.clone(),
+            config.provider.model.clone(),
+            transcript.to_context(agent.context()?.max_tokens()),
+        );
+        println!(
+            "Started session {} from a transcript ({} turns)",
+            session.id,
+            transcript.turns()
+        );
+        session
+    } else if let Some(id) = resume {
+        let session = store.load(&id)?;
+        println!(
+            "Resumed session {} ({} messages)",
+            session.id,
+            session.context.get_messages().len()
+        );
+        session
+    } else if let Some(id) = fork {
+        let session = store.load(&id)?.fork();
+        println!("Forked session {} into {}", id, session.id);
+        session
+    } else {
+        let session = Session::new(
+            config.provider.provider_type.clone(),
+            config.provider.model.clone(),
+            agent.context()?,
+        );
+        println!("Started session {}", session.id);
+        session
+    };
+    agent = agent
+        .with_context(session.context.clone())
+        .with_compaction(config.agent.compaction)
+        .with_tool_output_limit(config.agent.max_tool_output_bytes)
+        .with_observer(Arc::new(TracingObserver))
+        .with_approver(approver)
+        .with_budget(config.agent.budgets.run.clone())
+        .with_dry_run(dry_run);
+    if let Some(audit) = audit {
+        agent = agent.with_audit_log(audit);
+    }
+    if let Some(transcript) = transcript {
+        agent = agent.with_transcript(transcript);
+    }
+    if config.agent.review.enabled {
+        agent = agent.with_reviewer(Reviewer::new(provider, config.agent.review.clone()));
+    }
 
-    // 5. Run Loop
+    // 5. Run Loop. Ctrl-C cancels the current turn; a second Ctrl-C exits.
+    let interrupts = InterruptHandler::install();
     if let Some(msg) = initial_message {
         println!("User: {}", msg);
-        let response = agent.run(&msg).await?;
-        println!("Agent: {}", response);
+        let response = agent.run_with_cancel(&msg, &interrupts.begin()).await;
+        interrupts.end();
+        save_session(&store, &mut session, &agent)?;
+        save_transcript(&agent, &config, transcript_path.as_deref())?;
+        println!("Agent: {}", response?);
     } else {
         println!("Starting interactive chat. Type 'exit' or 'quit' to leave.");
         loop {
             print!("> ");
             io::stdout().flush().map_err(XzardgzError::Io)?;
 
-            let mut input = String::new();
-            io::stdin()
-                .read_line(&mut input)
-                .map_err(XzardgzError::Io)?;
+            // Shared with approval prompts, so a prompt left by a cancelled
+            // turn cannot take this line
+            let Some(input) = read_line().await.map_err(XzardgzError::Io)? else {
+                break;
+            };
             let input = input.trim();
 
             if input.eq_ignore_ascii_case("exit") || input.eq_ignore_ascii_case("quit") {
@@ -53,12 +164,77 @@ pub async fn run(initial_message: Option<String>) -> Result<(), XzardgzError> {
                 continue;
             }
 
-            match agent.run(input).await {
+            match agent.run_with_cancel(input, &interrupts.begin()).await {
                 Ok(response) => println!("Agent: {}", response),
+                Err(XzardgzError::Cancelled) => println!("Turn cancelled."),
+                Err(XzardgzError::BudgetExceeded {
+                    kind,
+                    used,
+                    limit,
+                    partial,
+                }) => {
+                    if let Some(partial) = partial {
+                        println!("Agent (partial): {}", partial);
+                    }
+                    println!(
+                        "Turn stopped: budget exceeded ({}).",
+                        Exceeded { kind, used, limit }
+                    );
+                }
                 Err(e) => println!("Error: {}", e),
             }
+            interrupts.end();
+            save_session(&store, &mut session, &agent)?;
+            save_transcript(&agent, &config, transcript_path.as_deref())?;
         }
+        println!("Session saved as {}", session.id);
+    }
+
+    Ok(())
+}
+
+fn save_session(
+    store: &SessionStore,
+    session: &mut Session,
+    agent: &Agent,
+) -> Result<(), XzardgzError> {
+    session.context = agent.context()?;
+    store.save(session)?;
+    Ok(())
+}
+
+fn save_transcript(agent: &Agent, config: &Config, path: Option<&str>) -> Result<(), XzardgzError> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+    let mut transcript = agent.transcript()?;
+    transcript.model = config.provider.model.clone();
+    transcript.save(Path::new(path))?;
+    Ok(())
+}
+
+pub fn list_sessions() -> Result<(), XzardgzError> {
+    let store = SessionStore::open_default()?;
+    let sessions = store.list()?;
+    if sessions.is_empty() {
+        println!("No saved sessions.");
+        return Ok(());
     }
+    for summary in sessions {
+        println!(
+            "{}  {}  {:>4} messages  {}",
+            summary.id,
+            summary.updated_at.format("%Y-%m-%d %H:%M"),
+            summary.message_count,
+            summary.title
+        );
+    }
+    Ok(())
+}
 
+pub fn export_session(id: &str, format: ExportFormat) -> Result<(), XzardgzError> {
+    let store = SessionStore::open_default()?;
+    let session = store.load(id)?;
+    println!("{}", session.export(format)?);
     Ok(())
 }
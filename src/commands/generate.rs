@@ -1,4 +1,7 @@
 use crate::agent::core::Agent;
+use crate::agent::events::TracingObserver;
+use crate::agent::review::Reviewer;
+use crate::commands::interrupt::InterruptHandler;
 use crate::config::Config;
 use crate::docgen::diataxis::DocCategory;
 use crate::docgen::generator::DocGenerator;
@@ -6,7 +9,11 @@ use crate::docgen::templates::TemplateRegistry;
 use crate::docgen::writer::DocumentWriter;
 use crate::error::Result;
 use crate::providers::factory::ProviderFactory;
-use crate::tools::registry::ToolRegistry;
+use crate::tools::approval::PolicyApprover;
+use crate::tools::audit::AuditLog;
+use crate::tools::builtin::workspace_tools;
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::workspace::Workspace;
 use std::path::PathBuf;
 use std::sync::Arc;
 
@@ -16,34 +23,84 @@ pub async fn execute(
     topic: String,
     output: String,
     overwrite: bool,
+    dry_run: bool,
+    tools: Option<String>,
 ) -> Result<()> {
     println!("Generating {} documentation for '{}'...", category, topic);
 
     let config = Config::load()?;
     let provider = ProviderFactory::create(&config.provider)?;
-    let tools = ToolRegistry::new();
-    let agent = Arc::new(Agent::new(
-        provider,
-        "You are a documentation expert.".to_string(),
-        tools,
+
+    // The agent can look around the repository with whatever the tool profile allows
+    let workspace = Arc::new(Workspace::new(&repository, &config.workspace)?);
+    let commands = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        config.commands.clone(),
     ));
+    let mut registry = workspace_tools(&workspace, &commands, &config.repository.ignore_patterns)?;
+    registry.register_custom_tools(&config.custom_tools, &commands)?;
+    registry.import_mcp_servers(&config.mcp.servers).await;
+    let registry = config
+        .tool_profiles
+        .clone()
+        .with_default(tools)
+        .selected()?
+        .apply(&registry);
+
+    let audit = AuditLog::for_run(&config.audit, "generate")?.map(Arc::new);
+    if let Some(audit) = &audit {
+        println!("Audit log: {}", audit.path().display());
+    }
+
+    let mut agent = Agent::new(
+        provider.clone(),
+        "You are a documentation expert.".to_string(),
+        registry,
+    )
+    .with_compaction(config.agent.compaction)
+    .with_tool_output_limit(config.agent.max_tool_output_bytes)
+    .with_observer(Arc::new(TracingObserver))
+    .with_approver(Arc::new(PolicyApprover::new(&config.approval)))
+    .with_budget(config.agent.budgets.run.clone())
+    .with_dry_run(dry_run);
+    if let Some(audit) = audit {
+        agent = agent.with_audit_log(audit);
+    }
     let templates = Arc::new(TemplateRegistry::new()?);
-    let generator = DocGenerator::new(agent, templates);
-    let writer = DocumentWriter::new(PathBuf::from(output), overwrite);
+    // Ctrl-C stops generation; a second Ctrl-C exits
+    let interrupts = InterruptHandler::install();
+    // Documents are reviewed as rendered markdown, so the agent itself runs without a reviewer
+    let mut generator =
+        DocGenerator::new(Arc::new(agent), templates).with_cancellation(interrupts.begin());
+    if config.agent.review.enabled {
+        generator = generator.with_reviewer(Reviewer::new(provider, config.agent.review.clone()));
+    }
+    let output = PathBuf::from(output);
+    let writer = DocumentWriter::new(output.clone(), overwrite);
 
     // TODO: Scan repository to get context
     // For now, we'll just use a placeholder or read a summary file if it exists
     let context = format!("Repository at {}", repository);
 
-    let content = generator.generate(category, &topic, &context).await?;
-    let path = writer
-        .write(
-            category,
-            &format!("{}.md", topic.replace(" ", "_").to_lowercase()),
-            &content,
-        )
-        .await?;
-
-    println!("Documentation generated at: {:?}", path);
+    let content = generator.generate(category, &topic, &context).await;
+    interrupts.end();
+    let content = content?;
+    let filename = format!("{}.md", topic.replace(" ", "_").to_lowercase());
+    if dry_run {
+        let path = output.join(category.directory()).join(&filename);
+        println!("[dry run] Would write {:?}:\n{}", path, content);
+    } else {
+        let path = writer.write(category, &filename, &content).await?;
+        println!("Documentation generated at: {:?}", path);
+    }
+    for report in generator.reviews() {
+        if let Some(scores) = report.final_scores() {
+            println!(
+                "Review: {} after {} revision(s)",
+                scores,
+                report.revisions()
+            );
+        }
+    }
     Ok(())
 }
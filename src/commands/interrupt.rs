@@ -0,0 +1,53 @@
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+/// Exit code conventionally used for SIGINT
+const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Ctrl-C handling for long-running commands: the first Ctrl-C cancels the
+/// current operation, a second one (or one while idle) exits the process.
+#[derive(Clone)]
+pub struct InterruptHandler {
+    current: Arc<Mutex<Option<CancellationToken>>>,
+}
+
+impl InterruptHandler {
+    pub fn install() -> Self {
+        let handler = Self {
+            current: Arc::new(Mutex::new(None)),
+        };
+        let current = handler.current.clone();
+        tokio::spawn(async move {
+            loop {
+                if tokio::signal::ctrl_c().await.is_err() {
+                    return;
+                }
+                let token = current.lock().ok().and_then(|current| current.clone());
+                match token {
+                    Some(token) if !token.is_cancelled() => {
+                        token.cancel();
+                        eprintln!("\nInterrupted. Press Ctrl-C again to exit.");
+                    }
+                    _ => std::process::exit(INTERRUPTED_EXIT_CODE),
+                }
+            }
+        });
+        handler
+    }
+
+    /// Token for the operation that is about to start
+    pub fn begin(&self) -> CancellationToken {
+        let token = CancellationToken::new();
+        if let Ok(mut current) = self.current.lock() {
+            *current = Some(token.clone());
+        }
+        token
+    }
+
+    /// Mark the current operation finished, so the next Ctrl-C exits
+    pub fn end(&self) {
+        if let Ok(mut current) = self.current.lock() {
+            *current = None;
+        }
+    }
+}
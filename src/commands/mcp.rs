@@ -0,0 +1,52 @@
+use crate::config::Config;
+use crate::error::XzardgzError;
+use crate::mcp::server::McpServer;
+use crate::tools::audit::AuditLog;
+use crate::tools::builtin::workspace_tools;
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::workspace::Workspace;
+use std::sync::Arc;
+use tokio::io::BufReader;
+
+/// Serve the workspace tools over MCP on stdin and stdout
+pub async fn serve(dry_run: bool, tools: Option<String>) -> Result<(), XzardgzError> {
+    let config = Config::load()?;
+    let workspace = Arc::new(Workspace::from_config(&config.workspace)?);
+    let commands = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        config.commands.clone(),
+    ));
+    let mut registry = workspace_tools(&workspace, &commands, &config.repository.ignore_patterns)?;
+    registry.register_custom_tools(&config.custom_tools, &commands)?;
+    let registry = config
+        .tool_profiles
+        .clone()
+        .with_default(tools)
+        .selected()?
+        .apply(&registry);
+
+    // The client approves calls itself, but tools denied by policy are never offered
+    let names: Vec<String> = registry
+        .list_tools()
+        .into_iter()
+        .map(|tool| tool.name)
+        .filter(|name| !config.approval.auto_deny.contains(name))
+        .collect();
+    let mut server = McpServer::new(registry.subset(&names))
+        .with_output_limit(config.agent.max_tool_output_bytes)
+        .with_dry_run(dry_run);
+    if let Some(audit) = AuditLog::for_run(&config.audit, "mcp")? {
+        tracing::info!("Audit log: {}", audit.path().display());
+        server = server.with_audit_log(Arc::new(audit));
+    }
+
+    tracing::info!(
+        "Serving {} tools over MCP for {}",
+        names.len(),
+        workspace.root().display()
+    );
+    server
+        .serve(BufReader::new(tokio::io::stdin()), tokio::io::stdout())
+        .await?;
+    Ok(())
+}
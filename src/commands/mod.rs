@@ -1,4 +1,37 @@
 pub mod auth;
 pub mod chat;
 pub mod generate;
+pub mod interrupt;
+pub mod mcp;
+pub mod plan;
 pub mod run;
+pub mod transcript;
+
+use crate::config::Config;
+use crate::providers::base::Provider;
+use crate::tools::approval::Approver;
+use crate::tools::audit::AuditLog;
+use crate::tools::delegate::DelegateTaskTool;
+use crate::tools::registry::ToolRegistry;
+use std::sync::Arc;
+
+/// The `delegate_task` tool for a command's agent. Sub-agents get the same
+/// tools, approval policy and limits as the main agent.
+pub(crate) fn delegate_tool(
+    config: &Config,
+    provider: Arc<dyn Provider>,
+    tools: ToolRegistry,
+    approver: Arc<dyn Approver>,
+    audit: Option<&Arc<AuditLog>>,
+    dry_run: bool,
+) -> DelegateTaskTool {
+    let mut delegate = DelegateTaskTool::new(provider, tools, config.agent.delegation.clone())
+        .with_approver(approver)
+        .with_tool_output_limit(config.agent.max_tool_output_bytes)
+        .with_compaction(config.agent.compaction)
+        .with_dry_run(dry_run);
+    if let Some(audit) = audit {
+        delegate = delegate.with_audit_log(audit.clone());
+    }
+    delegate
+}
@@ -0,0 +1,149 @@
+use crate::agent::core::Agent;
+use crate::agent::events::TracingObserver;
+use crate::config::Config;
+use crate::error::{WorkflowError, XzardgzError};
+use crate::providers::factory::ProviderFactory;
+use crate::repository::scanner::RepositoryScanner;
+use crate::tools::audit::AuditLog;
+use crate::tools::file_ops::ReadFileTool;
+use crate::tools::git_ops::{
+    GitBlameTool, GitBranchesTool, GitDiffTool, GitLogTool, GitShowTool, GitStatusTool,
+};
+use crate::tools::registry::ToolRegistry;
+use crate::tools::workspace::Workspace;
+use crate::workflow::generator::PlanGenerator;
+use crate::workflow::parser::parse_plan;
+use crate::workflow::plan::Plan;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Files listed in the prompt before the rest are elided
+const MAX_LISTED_FILES: usize = 200;
+
+pub async fn new(
+    goal: String,
+    repository: String,
+    output: String,
+    review: bool,
+    max_repairs: usize,
+    dry_run: bool,
+) -> Result<(), XzardgzError> {
+    println!("Planning: {}", goal);
+
+    let config = Config::load()?;
+    let provider = ProviderFactory::create(&config.provider)?;
+
+    // Planning only inspects the repository, so it gets read-only tools
+    let workspace = Arc::new(Workspace::new(&repository, &config.workspace)?);
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitStatusTool::definition(),
+        Arc::new(GitStatusTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitDiffTool::definition(),
+        Arc::new(GitDiffTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitLogTool::definition(),
+        Arc::new(GitLogTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitShowTool::definition(),
+        Arc::new(GitShowTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBlameTool::definition(),
+        Arc::new(GitBlameTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBranchesTool::definition(),
+        Arc::new(GitBranchesTool::new(workspace.clone())),
+    );
+
+    let system_prompt =
+        "You are a planning agent. You design workflow plans for documentation tasks.".to_string();
+    let mut agent = Agent::new(provider, system_prompt, registry)
+        .with_compaction(config.agent.compaction)
+        .with_tool_output_limit(config.agent.max_tool_output_bytes)
+        .with_observer(Arc::new(TracingObserver));
+    if let Some(audit) = AuditLog::for_run(&config.audit, "plan")? {
+        agent = agent.with_audit_log(Arc::new(audit));
+    }
+    let agent = Arc::new(agent);
+
+    let scanner = RepositoryScanner::new(&repository, config.repository.ignore_patterns.clone());
+    let context = repository_context(&scanner, Path::new(&repository))?;
+
+    let generator = PlanGenerator::new(agent, max_repairs);
+    let mut plan = generator.generate(&goal, &context).await?;
+
+    if review {
+        plan = review_plan(&plan)?;
+    }
+
+    let yaml = serde_yaml::to_string(&plan)
+        .map_err(|e| WorkflowError::Parse(format!("Failed to serialize plan: {}", e)))?;
+    if dry_run {
+        println!("[dry run] Would write this plan to {}:\n{}", output, yaml);
+        return Ok(());
+    }
+    std::fs::write(&output, yaml).map_err(XzardgzError::Io)?;
+
+    println!(
+        "Plan written to {}. Run it with: xzardgz run {}",
+        output, output
+    );
+    Ok(())
+}
+
+fn repository_context(scanner: &RepositoryScanner, root: &Path) -> Result<String, XzardgzError> {
+    let files = scanner.scan()?;
+    let mut listing: Vec<String> = files
+        .iter()
+        .take(MAX_LISTED_FILES)
+        .map(|path| {
+            path.strip_prefix(root)
+                .unwrap_or(path)
+                .display()
+                .to_string()
+        })
+        .collect();
+    if files.len() > MAX_LISTED_FILES {
+        listing.push(format!("... and {} more", files.len() - MAX_LISTED_FILES));
+    }
+    Ok(listing.join("\n"))
+}
+
+/// Open the plan in `$EDITOR` and re-validate whatever the user saves. The
+/// file gets a fresh random name, and is removed however the review ends.
+fn review_plan(plan: &Plan) -> Result<Plan, XzardgzError> {
+    let yaml = serde_yaml::to_string(plan)
+        .map_err(|e| WorkflowError::Parse(format!("Failed to serialize plan: {}", e)))?;
+    let mut temp = tempfile::Builder::new()
+        .prefix("xzardgz-plan-")
+        .suffix(".yaml")
+        .tempfile()
+        .map_err(XzardgzError::Io)?;
+    temp.write_all(yaml.as_bytes()).map_err(XzardgzError::Io)?;
+    let file = temp.path();
+
+    let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".to_string());
+    let status = std::process::Command::new(&editor)
+        .arg(file)
+        .status()
+        .map_err(XzardgzError::Io)?;
+    if !status.success() {
+        return Err(WorkflowError::Execution(format!("{} exited with {}", editor, status)).into());
+    }
+
+    let content = std::fs::read_to_string(file).map_err(XzardgzError::Io)?;
+    let reviewed = parse_plan(&content, "yaml")?;
+    reviewed.validate().map_err(WorkflowError::Parse)?;
+    Ok(reviewed)
+}
@@ -1,16 +1,31 @@
+use crate::agent::budget::BudgetTracker;
 use crate::agent::core::Agent;
+use crate::agent::events::TracingObserver;
+use crate::agent::review::Reviewer;
+use crate::commands::delegate_tool;
+use crate::commands::interrupt::InterruptHandler;
 use crate::config::Config;
-use crate::error::XzardgzError;
+use crate::error::{WorkflowError, XzardgzError};
 use crate::providers::factory::ProviderFactory;
-use crate::tools::file_ops::{ReadFileTool, WriteFileTool};
-use crate::tools::git_ops::GitStatusTool;
-use crate::tools::registry::ToolRegistry;
-use crate::workflow::executor::WorkflowExecutor;
+use crate::tools::approval::{Approver, PolicyApprover};
+use crate::tools::audit::AuditLog;
+use crate::tools::builtin::workspace_tools;
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::delegate::DelegateTaskTool;
+use crate::tools::workspace::Workspace;
+use crate::workflow::executor::{StepReview, WorkflowExecutor};
 use crate::workflow::parser::parse_plan;
+use crate::workflow::state::RunState;
 use std::path::Path;
 use std::sync::Arc;
 
-pub async fn execute(plan_path: String) -> Result<(), XzardgzError> {
+pub async fn execute(
+    plan_path: String,
+    resume: bool,
+    transcript_path: Option<String>,
+    dry_run: bool,
+    tools: Option<String>,
+) -> Result<(), XzardgzError> {
     println!("Executing plan from: {}", plan_path);
 
     // 1. Read plan file
@@ -29,20 +44,163 @@ pub async fn execute(plan_path: String) -> Result<(), XzardgzError> {
     let config = Config::load()?;
     let provider = ProviderFactory::create(&config.provider)?;
 
-    let mut registry = ToolRegistry::new();
-    registry.register(ReadFileTool::definition(), Arc::new(ReadFileTool));
-    registry.register(WriteFileTool::definition(), Arc::new(WriteFileTool));
-    registry.register(GitStatusTool::definition(), Arc::new(GitStatusTool));
+    let workspace = Arc::new(Workspace::from_config(&config.workspace)?);
+    let commands = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        config.commands.clone(),
+    ));
+    let mut registry = workspace_tools(&workspace, &commands, &config.repository.ignore_patterns)?;
+    registry.register_custom_tools(&config.custom_tools, &commands)?;
+
+    // Everything the run does, sub-agents included, counts against the workflow budget
+    let budget = Arc::new(BudgetTracker::new(config.agent.budgets.workflow.clone()));
+
+    registry.import_mcp_servers(&config.mcp.servers).await;
+    let profiles = config.tool_profiles.clone().with_default(tools);
+    let profile = profiles.selected()?;
+    let mut registry = profile.apply(&registry);
+    println!(
+        "Tool profile: {} ({} tools)",
+        profiles.default,
+        registry.list_tools().len()
+    );
+
+    let audit = AuditLog::for_run(&config.audit, "run")?.map(Arc::new);
+    if let Some(audit) = &audit {
+        println!("Audit log: {}", audit.path().display());
+    }
+    if dry_run {
+        println!("Dry run: file, edit and command tools will not change anything");
+    }
+
+    let approver: Arc<dyn Approver> = Arc::new(PolicyApprover::new(&config.approval));
+    let delegate = delegate_tool(
+        &config,
+        provider.clone(),
+        registry.clone(),
+        approver.clone(),
+        audit.as_ref(),
+        dry_run,
+    );
+    if profile.allows("delegate_task", false) {
+        registry.register(DelegateTaskTool::definition(), Arc::new(delegate));
+    }
 
     let system_prompt = "You are an autonomous agent executing a workflow plan.".to_string();
-    let agent = Arc::new(Agent::new(provider, system_prompt, registry));
+    let mut agent = Agent::new(provider.clone(), system_prompt, registry)
+        .with_compaction(config.agent.compaction)
+        .with_tool_output_limit(config.agent.max_tool_output_bytes)
+        .with_observer(Arc::new(TracingObserver))
+        .with_approver(approver)
+        .with_budget(config.agent.budgets.run.clone())
+        .with_shared_budget(budget.clone())
+        .with_dry_run(dry_run);
+    if let Some(audit) = audit {
+        agent = agent.with_audit_log(audit);
+    }
+    if config.agent.review.enabled {
+        agent = agent.with_reviewer(Reviewer::new(provider, config.agent.review.clone()));
+    }
+    let agent = Arc::new(agent);
 
-    // 4. Initialize Executor
-    let mut executor = WorkflowExecutor::new(agent, plan);
+    // 4. Initialize Executor, picking up an interrupted run if requested
+    let state_path = RunState::path_for(Path::new(&plan_path));
+    let interrupts = InterruptHandler::install();
+    let mut executor = WorkflowExecutor::new(agent.clone(), plan)
+        .with_cancellation(interrupts.begin())
+        .with_budget(budget.clone())
+        .with_command_runner(commands)
+        .with_dry_run(dry_run)
+        .with_tool_profiles(profiles);
+    if resume && state_path.exists() {
+        let state = RunState::load(&state_path)?;
+        let completed = state.completed_steps.len();
+        executor = executor.resume_from(state)?;
+        println!("Resuming after {} completed steps", completed);
+    }
 
     // 5. Execute
-    executor.execute().await?;
+    let result = executor.execute().await;
+    if let Some(path) = &transcript_path {
+        let mut transcript = agent.transcript()?;
+        transcript.model = config.provider.model.clone();
+        transcript.save(Path::new(path))?;
+    }
 
+    match result {
+        Ok(()) => {
+            interrupts.end();
+            if state_path.exists() && !dry_run {
+                std::fs::remove_file(&state_path).map_err(XzardgzError::Io)?;
+            }
+        }
+        Err(WorkflowError::Interrupted(step)) => {
+            interrupts.end();
+            if dry_run {
+                return Err(WorkflowError::Interrupted(step).into());
+            }
+            executor.state().save(&state_path)?;
+            println!(
+                "Interrupted at step {}. Resume with: xzardgz run {} --resume",
+                step, plan_path
+            );
+            return Err(WorkflowError::Interrupted(step).into());
+        }
+        Err(WorkflowError::BudgetExceeded {
+            step,
+            reason,
+            partial,
+        }) => {
+            interrupts.end();
+            // A dry run changed nothing, so there is no progress to resume
+            if !dry_run {
+                executor.state().save(&state_path)?;
+            }
+            if let Some(partial) = &partial {
+                println!("Partial result of step {}:\n{}", step, partial);
+            }
+            print_review_report(executor.reviews());
+            println!("Usage: {}", budget.usage());
+            println!(
+                "Stopped at step {}: budget exceeded ({}). Resume with: xzardgz run {} --resume",
+                step, reason, plan_path
+            );
+            return Err(WorkflowError::BudgetExceeded {
+                step,
+                reason,
+                partial,
+            }
+            .into());
+        }
+        Err(e) => return Err(e.into()),
+    }
+
+    print_review_report(executor.reviews());
+    println!("Usage: {}", budget.usage());
     println!("Plan execution completed successfully.");
     Ok(())
 }
+
+fn print_review_report(reviews: &[StepReview]) {
+    if reviews.is_empty() {
+        return;
+    }
+    println!("Review report:");
+    for review in reviews {
+        let Some(scores) = review.report.final_scores() else {
+            println!("  {}: not reviewed", review.step);
+            continue;
+        };
+        println!(
+            "  {}: {} after {} revision(s){}",
+            review.step,
+            scores,
+            review.report.revisions(),
+            if review.report.passed {
+                ""
+            } else {
+                " (below threshold)"
+            }
+        );
+    }
+}
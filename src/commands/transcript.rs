@@ -0,0 +1,107 @@
+use crate::agent::budget::Usage;
+use crate::agent::core::Agent;
+use crate::agent::events::TracingObserver;
+use crate::agent::session::ExportFormat;
+use crate::agent::transcript::Transcript;
+use crate::config::Config;
+use crate::error::XzardgzError;
+use crate::providers::factory::ProviderFactory;
+use crate::tools::file_ops::ReadFileTool;
+use crate::tools::git_ops::{
+    GitBlameTool, GitBranchesTool, GitDiffTool, GitLogTool, GitShowTool, GitStatusTool,
+};
+use crate::tools::registry::ToolRegistry;
+use crate::tools::workspace::Workspace;
+use std::path::Path;
+use std::sync::Arc;
+
+pub fn show(path: &str, format: ExportFormat) -> Result<(), XzardgzError> {
+    let transcript = Transcript::load(Path::new(path))?;
+    println!("{}", transcript.export(format)?);
+    Ok(())
+}
+
+pub async fn replay(
+    path: &str,
+    provider_type: Option<String>,
+    model: Option<String>,
+    output: Option<String>,
+) -> Result<(), XzardgzError> {
+    let original = Transcript::load(Path::new(path))?;
+
+    let mut config = Config::load()?;
+    if let Some(provider_type) = provider_type {
+        config.provider.provider_type = provider_type;
+        config.provider.model = None;
+    }
+    if model.is_some() {
+        config.provider.model = model;
+    }
+    let provider = ProviderFactory::create(&config.provider)?;
+
+    // Replays are for comparison, so the agent only gets read-only tools
+    let workspace = Arc::new(Workspace::from_config(&config.workspace)?);
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitStatusTool::definition(),
+        Arc::new(GitStatusTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitDiffTool::definition(),
+        Arc::new(GitDiffTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitLogTool::definition(),
+        Arc::new(GitLogTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitShowTool::definition(),
+        Arc::new(GitShowTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBlameTool::definition(),
+        Arc::new(GitBlameTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBranchesTool::definition(),
+        Arc::new(GitBranchesTool::new(workspace.clone())),
+    );
+
+    let agent = Agent::new(provider, original.system_prompt.clone(), registry)
+        .with_compaction(config.agent.compaction)
+        .with_tool_output_limit(config.agent.max_tool_output_bytes)
+        .with_observer(Arc::new(TracingObserver));
+
+    println!(
+        "Replaying {} turns against {}",
+        original.inputs().len(),
+        config.provider.provider_type
+    );
+    let mut replayed = original.replay(&agent).await?;
+    replayed.model = config.provider.model.clone();
+
+    let before = original.usage_by_turn();
+    let after = replayed.usage_by_turn();
+    for turn in 0..before.len().max(after.len()) {
+        println!("Turn {}", turn + 1);
+        println!("  original: {}", describe(before.get(turn)));
+        println!("  replay:   {}", describe(after.get(turn)));
+    }
+    println!("Total");
+    println!("  original: {}", original.usage());
+    println!("  replay:   {}", replayed.usage());
+
+    if let Some(output) = output {
+        replayed.save(Path::new(&output))?;
+        println!("Replayed transcript written to {}", output);
+    }
+    Ok(())
+}
+
+fn describe(usage: Option<&Usage>) -> String {
+    usage.map_or_else(|| "-".to_string(), Usage::to_string)
+}
@@ -1,4 +1,16 @@
+use crate::agent::budget::BudgetConfig;
+use crate::agent::compaction::CompactionStrategy;
+use crate::agent::review::ReviewConfig;
 use crate::error::ConfigError;
+use crate::mcp::McpConfig;
+use crate::tools::approval::ApprovalConfig;
+use crate::tools::audit::AuditConfig;
+use crate::tools::command_ops::CommandConfig;
+use crate::tools::custom_ops::CustomToolConfig;
+use crate::tools::delegate::DelegationConfig;
+use crate::tools::executor::DEFAULT_MAX_OUTPUT_BYTES;
+use crate::tools::profile::ToolProfilesConfig;
+use crate::tools::workspace::WorkspaceConfig;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -7,6 +19,20 @@ pub struct Config {
     pub agent: AgentConfig,
     pub repository: RepositoryConfig,
     pub documentation: DocumentationConfig,
+    #[serde(default)]
+    pub approval: ApprovalConfig,
+    #[serde(default)]
+    pub workspace: WorkspaceConfig,
+    #[serde(default)]
+    pub commands: CommandConfig,
+    #[serde(default)]
+    pub custom_tools: Vec<CustomToolConfig>,
+    #[serde(default)]
+    pub tool_profiles: ToolProfilesConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub mcp: McpConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +45,21 @@ pub struct ProviderConfig {
 pub struct AgentConfig {
     pub max_turns: u32,
     pub timeout_seconds: u64,
+    #[serde(default)]
+    pub compaction: CompactionStrategy,
+    #[serde(default)]
+    pub delegation: DelegationConfig,
+    #[serde(default)]
+    pub review: ReviewConfig,
+    #[serde(default)]
+    pub budgets: BudgetConfig,
+    /// Largest tool output, in bytes, added to the conversation
+    #[serde(default = "default_max_tool_output_bytes")]
+    pub max_tool_output_bytes: usize,
+}
+
+fn default_max_tool_output_bytes() -> usize {
+    DEFAULT_MAX_OUTPUT_BYTES
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,6 +104,11 @@ impl Default for Config {
             agent: AgentConfig {
                 max_turns: 10,
                 timeout_seconds: 600,
+                compaction: CompactionStrategy::default(),
+                delegation: DelegationConfig::default(),
+                review: ReviewConfig::default(),
+                budgets: BudgetConfig::default(),
+                max_tool_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
             },
             repository: RepositoryConfig {
                 ignore_patterns: vec!["target".to_string(), ".git".to_string()],
@@ -70,6 +116,13 @@ impl Default for Config {
             documentation: DocumentationConfig {
                 output_dir: "docs".to_string(),
             },
+            approval: ApprovalConfig::default(),
+            workspace: WorkspaceConfig::default(),
+            commands: CommandConfig::default(),
+            custom_tools: Vec::new(),
+            tool_profiles: ToolProfilesConfig::default(),
+            audit: AuditConfig::default(),
+            mcp: McpConfig::default(),
         }
     }
 }
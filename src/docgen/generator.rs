@@ -1,17 +1,49 @@
 use super::diataxis::DocCategory;
 use super::templates::TemplateRegistry;
 use crate::agent::core::Agent;
+use crate::agent::review::{ReviewReport, Reviewer};
 use crate::error::Result;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
 
 pub struct DocGenerator {
     agent: Arc<Agent>,
     templates: Arc<TemplateRegistry>,
+    reviewer: Option<Reviewer>,
+    reviews: Mutex<Vec<ReviewReport>>,
+    cancel: CancellationToken,
 }
 
 impl DocGenerator {
     pub fn new(agent: Arc<Agent>, templates: Arc<TemplateRegistry>) -> Self {
-        Self { agent, templates }
+        Self {
+            agent,
+            templates,
+            reviewer: None,
+            reviews: Mutex::new(Vec::new()),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Stop generating or revising once `cancel` fires
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Review each rendered document against its Diataxis category and revise
+    /// it until it meets the rubric threshold
+    pub fn with_reviewer(mut self, reviewer: Reviewer) -> Self {
+        self.reviewer = Some(reviewer);
+        self
+    }
+
+    /// Review rounds for the documents generated so far
+    pub fn reviews(&self) -> Vec<ReviewReport> {
+        self.reviews
+            .lock()
+            .map(|reviews| reviews.clone())
+            .unwrap_or_default()
     }
 
     pub async fn generate(
@@ -24,12 +56,60 @@ impl DocGenerator {
         let prompt = self.build_prompt(category, topic, context);
 
         // 2. Call agent
-        let content_json = self.agent.run(&prompt).await?;
+        let content_json = self.agent.run_with_cancel(&prompt, &self.cancel).await?;
+
+        // 3. Parse and render
+        let document = self.render_response(category, &content_json)?;
+
+        // 4. Critique and revise, if enabled
+        match &self.reviewer {
+            Some(reviewer) => self.review(reviewer, category, &prompt, document).await,
+            None => Ok(document),
+        }
+    }
+
+    async fn review(
+        &self,
+        reviewer: &Reviewer,
+        category: DocCategory,
+        prompt: &str,
+        mut document: String,
+    ) -> Result<String> {
+        let mut report = ReviewReport::new(prompt);
+        loop {
+            let scores = match reviewer.score(prompt, &document, Some(category)).await {
+                Ok(scores) => scores,
+                Err(e) => {
+                    tracing::warn!("Review failed, keeping document unreviewed: {}", e);
+                    break;
+                }
+            };
+            tracing::info!("Review round {}: {}", report.rounds.len() + 1, scores);
+
+            report.passed = reviewer.passes(&scores);
+            report.rounds.push(scores);
+            if report.passed || report.revisions() >= reviewer.config().max_revisions {
+                break;
+            }
+
+            let revision = format!(
+                "{}\nReturn the complete revised JSON object only.",
+                Reviewer::revision_prompt(&report.rounds[report.rounds.len() - 1])
+            );
+            let content_json = self.agent.run_with_cancel(&revision, &self.cancel).await?;
+            document = self.render_response(category, &content_json)?;
+        }
+
+        if let Ok(mut reviews) = self.reviews.lock() {
+            reviews.push(report);
+        }
+        Ok(document)
+    }
 
-        // 3. Clean up potential markdown code blocks from response
-        let clean_json = self.clean_json_response(&content_json);
+    fn render_response(&self, category: DocCategory, response: &str) -> Result<String> {
+        // Clean up potential markdown code blocks from response
+        let clean_json = self.clean_json_response(response);
 
-        // 4. Parse JSON content
         let data: serde_json::Value = serde_json::from_str(&clean_json).map_err(|e| {
             crate::error::DocGenError::Generation(format!(
                 "Failed to parse agent response as JSON: {}. Response: {}",
@@ -37,7 +117,6 @@ impl DocGenerator {
             ))
         })?;
 
-        // 5. Render template
         self.templates.render(category, &data)
     }
 
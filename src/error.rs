@@ -1,3 +1,5 @@
+use crate::agent::budget::BudgetKind;
+use crate::tools::workspace::PathViolation;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, XzardgzError>;
@@ -21,6 +23,27 @@ pub enum XzardgzError {
 
     #[error("Documentation generation error: {0}")]
     DocGen(#[from] DocGenError),
+
+    #[error("Session error: {0}")]
+    Session(#[from] SessionError),
+
+    #[error("Command error: {0}")]
+    Command(#[from] CommandError),
+
+    #[error("MCP error: {0}")]
+    Mcp(#[from] McpError),
+
+    #[error("Cancelled")]
+    Cancelled,
+
+    #[error("Budget exceeded: {used} of {limit} {kind}")]
+    BudgetExceeded {
+        kind: BudgetKind,
+        used: u64,
+        limit: u64,
+        /// Last answer the agent gave before it was stopped
+        partial: Option<String>,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -49,6 +72,14 @@ pub enum WorkflowError {
     Parse(String),
     #[error("Execution failed: {0}")]
     Execution(String),
+    #[error("Interrupted at step {0}")]
+    Interrupted(String),
+    #[error("Budget exceeded at step {step}: {reason}")]
+    BudgetExceeded {
+        step: String,
+        reason: String,
+        partial: Option<String>,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -57,6 +88,10 @@ pub enum RepositoryError {
     Git(String),
     #[error("Scan failed: {0}")]
     Scan(String),
+    #[error("Could not parse Rust source: {0}")]
+    Parse(String),
+    #[error("Invalid Cargo manifest: {0}")]
+    Manifest(String),
 }
 
 #[derive(Debug, Error)]
@@ -68,3 +103,59 @@ pub enum DocGenError {
     #[error("IO error: {0}")]
     Io(String),
 }
+
+#[derive(Debug, Error)]
+pub enum SessionError {
+    #[error("Session not found: {0}")]
+    NotFound(String),
+    #[error("Invalid session id: {0:?}")]
+    InvalidId(String),
+    #[error("Storage error: {0}")]
+    Storage(String),
+    #[error("Serialization error: {0}")]
+    Serialization(String),
+}
+
+#[derive(Debug, Error)]
+pub enum CommandError {
+    #[error("Empty command")]
+    Empty,
+    #[error("{0} is not in the allowed programs")]
+    NotAllowed(String),
+    #[error("{0} is denied by the command policy")]
+    Denied(String),
+    #[error("{0}")]
+    Syntax(String),
+    #[error("{0}")]
+    Path(#[from] PathViolation),
+    #[error("{0} is not a directory")]
+    NotADirectory(String),
+    #[error("Failed to start {program}: {reason}")]
+    Spawn { program: String, reason: String },
+}
+
+#[derive(Debug, Error)]
+pub enum McpError {
+    #[error("Failed to start MCP server {server}: {reason}")]
+    Spawn { server: String, reason: String },
+    #[error("MCP server {0} closed the connection")]
+    Closed(String),
+    #[error("The connection to MCP server {0} was interrupted mid-message and cannot be reused")]
+    Broken(String),
+    #[error("MCP server {server} did not answer {method} within {seconds}s")]
+    Timeout {
+        server: String,
+        method: String,
+        seconds: u64,
+    },
+    #[error("MCP server {server} returned error {code}: {message}")]
+    Remote {
+        server: String,
+        code: i64,
+        message: String,
+    },
+    #[error("Invalid MCP message: {0}")]
+    Protocol(String),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -4,6 +4,7 @@ pub mod commands;
 pub mod config;
 pub mod docgen;
 pub mod error;
+pub mod mcp;
 pub mod providers;
 pub mod repository;
 pub mod telemetry;
@@ -1,16 +1,75 @@
 use clap::Parser;
-use xzardgz::cli::{AuthCommands, Cli, Commands};
+use xzardgz::cli::{AuthCommands, Cli, Commands, McpCommands, PlanCommands, TranscriptCommands};
 use xzardgz::commands;
 use xzardgz::error::XzardgzError;
 
 #[tokio::main]
 async fn main() -> Result<(), XzardgzError> {
-    xzardgz::telemetry::init_logging("info")?;
     let cli = Cli::parse();
+    // stdout carries the protocol when serving MCP
+    if matches!(cli.command, Some(Commands::Mcp { .. })) {
+        xzardgz::telemetry::init_stderr_logging("info")?;
+    } else {
+        xzardgz::telemetry::init_logging("info")?;
+    }
 
     match cli.command {
-        Some(Commands::Run { plan }) => commands::run::execute(plan).await,
-        Some(Commands::Chat { message }) => commands::chat::run(message).await,
+        Some(Commands::Run {
+            plan,
+            resume,
+            transcript,
+        }) => commands::run::execute(plan, resume, transcript, cli.dry_run, cli.tools).await,
+        Some(Commands::Chat {
+            message,
+            resume,
+            fork,
+            from_transcript,
+            transcript,
+            list,
+            export,
+            format,
+        }) => {
+            if list {
+                commands::chat::list_sessions()
+            } else if let Some(id) = export {
+                commands::chat::export_session(&id, format)
+            } else {
+                commands::chat::run(
+                    message,
+                    resume,
+                    fork,
+                    from_transcript,
+                    transcript,
+                    cli.dry_run,
+                    cli.tools,
+                )
+                .await
+            }
+        }
+        Some(Commands::Transcript { command }) => match command {
+            TranscriptCommands::Show { path, format } => commands::transcript::show(&path, format),
+            TranscriptCommands::Replay {
+                path,
+                provider,
+                model,
+                output,
+            } => commands::transcript::replay(&path, provider, model, output).await,
+        },
+        Some(Commands::Plan { command }) => match command {
+            PlanCommands::New {
+                goal,
+                repository,
+                output,
+                review,
+                max_repairs,
+            } => {
+                commands::plan::new(goal, repository, output, review, max_repairs, cli.dry_run)
+                    .await
+            }
+        },
+        Some(Commands::Mcp { command }) => match command {
+            McpCommands::Serve => commands::mcp::serve(cli.dry_run, cli.tools).await,
+        },
         Some(Commands::Auth { command }) => match command {
             AuthCommands::Login => commands::auth::login().await,
         },
@@ -20,7 +79,18 @@ async fn main() -> Result<(), XzardgzError> {
             topic,
             output,
             overwrite,
-        }) => commands::generate::execute(repository, category, topic, output, overwrite).await,
+        }) => {
+            commands::generate::execute(
+                repository,
+                category,
+                topic,
+                output,
+                overwrite,
+                cli.dry_run,
+                cli.tools,
+            )
+            .await
+        }
         None => {
             println!("No command specified. Use --help for usage.");
             Ok(())
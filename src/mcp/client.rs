@@ -0,0 +1,276 @@
+use crate::error::{McpError, XzardgzError};
+use crate::mcp::McpServerConfig;
+use crate::mcp::protocol::{
+    CallToolResult, METHOD_NOT_FOUND, McpTool, Message, PROTOCOL_VERSION, read_message,
+    write_message,
+};
+use crate::providers::types::Tool;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use serde_json::{Value, json};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::Mutex;
+
+/// A connection to an MCP server running as a child process, speaking
+/// newline-delimited JSON-RPC over its stdin and stdout
+pub struct McpClient {
+    name: String,
+    timeout: Duration,
+    next_id: AtomicU64,
+    connection: Mutex<Connection>,
+}
+
+struct Connection {
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    /// Set while a request is in flight. Still set afterwards if it timed out
+    /// or was cancelled, which can stop partway through writing or reading a
+    /// line, so the stream is not in a state the next request can use.
+    broken: bool,
+    // Held so the server is killed when the client is dropped
+    _child: Child,
+}
+
+impl McpClient {
+    /// Start the server and complete the MCP handshake
+    pub async fn connect(config: &McpServerConfig) -> Result<Self, McpError> {
+        let spawn_error = |reason: String| McpError::Spawn {
+            server: config.name.clone(),
+            reason,
+        };
+        let mut child = Command::new(&config.command)
+            .args(&config.args)
+            .envs(&config.env)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true)
+            .spawn()
+            .map_err(|e| spawn_error(e.to_string()))?;
+
+        let stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| spawn_error("no stdin".into()))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| spawn_error("no stdout".into()))?;
+        // Servers log to stderr; keep it out of the terminal
+        if let Some(stderr) = child.stderr.take() {
+            let server = config.name.clone();
+            tokio::spawn(async move {
+                let mut lines = BufReader::new(stderr).lines();
+                while let Ok(Some(line)) = lines.next_line().await {
+                    tracing::debug!("MCP server {}: {}", server, line);
+                }
+            });
+        }
+
+        let client = Self {
+            name: config.name.clone(),
+            timeout: Duration::from_secs(config.timeout_seconds),
+            next_id: AtomicU64::new(1),
+            connection: Mutex::new(Connection {
+                stdin,
+                stdout: BufReader::new(stdout),
+                broken: false,
+                _child: child,
+            }),
+        };
+
+        let info = client
+            .request(
+                "initialize",
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {},
+                    "clientInfo": {"name": "xzardgz", "version": env!("CARGO_PKG_VERSION")}
+                }),
+            )
+            .await?;
+        tracing::info!(
+            "Connected to MCP server {} ({} {}, protocol {})",
+            client.name,
+            info["serverInfo"]["name"].as_str().unwrap_or("unknown"),
+            info["serverInfo"]["version"].as_str().unwrap_or(""),
+            info["protocolVersion"].as_str().unwrap_or("unknown")
+        );
+        let mut connection = client.connection.lock().await;
+        write_message(
+            &mut connection.stdin,
+            &Message::notification("notifications/initialized"),
+        )
+        .await?;
+        drop(connection);
+
+        Ok(client)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Every tool the server offers, following pagination
+    pub async fn list_tools(&self) -> Result<Vec<McpTool>, McpError> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => json!({ "cursor": cursor }),
+                None => json!({}),
+            };
+            let mut page = self.request("tools/list", params).await?;
+            let listed: Vec<McpTool> = serde_json::from_value(page["tools"].take())
+                .map_err(|e| McpError::Protocol(format!("tools/list: {}", e)))?;
+            tools.extend(listed);
+            cursor = page["nextCursor"].as_str().map(String::from);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    pub async fn call_tool(
+        &self,
+        name: &str,
+        arguments: Value,
+    ) -> Result<CallToolResult, McpError> {
+        let result = self
+            .request(
+                "tools/call",
+                json!({ "name": name, "arguments": arguments }),
+            )
+            .await?;
+        serde_json::from_value(result).map_err(|e| McpError::Protocol(format!("tools/call: {}", e)))
+    }
+
+    /// Send a request and wait for its response. Calls are serialized; while
+    /// waiting, pings from the server are answered and other messages skipped.
+    /// After a timeout or cancellation every later request fails.
+    async fn request(&self, method: &str, params: Value) -> Result<Value, McpError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut connection = self.connection.lock().await;
+        if connection.broken {
+            return Err(McpError::Broken(self.name.clone()));
+        }
+        connection.broken = true;
+        write_message(&mut connection.stdin, &Message::request(id, method, params)).await?;
+
+        let wait = async {
+            loop {
+                let message = read_message(&mut connection.stdout)
+                    .await?
+                    .ok_or_else(|| McpError::Closed(self.name.clone()))?;
+                match (message.id, message.method) {
+                    (Some(request_id), Some(request)) => {
+                        let reply = if request == "ping" {
+                            Message::response(request_id, json!({}))
+                        } else {
+                            Message::error_response(
+                                request_id,
+                                METHOD_NOT_FOUND,
+                                format!("Unsupported request: {}", request),
+                            )
+                        };
+                        write_message(&mut connection.stdin, &reply).await?;
+                    }
+                    (Some(response_id), None) if response_id == id => {
+                        if let Some(error) = message.error {
+                            return Err(McpError::Remote {
+                                server: self.name.clone(),
+                                code: error.code,
+                                message: error.message,
+                            });
+                        }
+                        return Ok(message.result.unwrap_or(Value::Null));
+                    }
+                    // Notifications and late answers to requests that timed out
+                    _ => {}
+                }
+            }
+        };
+
+        let result = tokio::time::timeout(self.timeout, wait).await;
+        match result {
+            Ok(result) => {
+                // Whole messages were read, so the stream is at a line boundary
+                // unless reading or writing itself failed
+                if !matches!(result, Err(McpError::Io(_))) {
+                    connection.broken = false;
+                }
+                result
+            }
+            Err(_) => Err(McpError::Timeout {
+                server: self.name.clone(),
+                method: method.to_string(),
+                seconds: self.timeout.as_secs(),
+            }),
+        }
+    }
+}
+
+/// Forwards calls for one remote tool to its server
+pub struct McpToolExecutor {
+    client: Arc<McpClient>,
+    tool: String,
+    mutating: bool,
+}
+
+impl McpToolExecutor {
+    pub fn new(client: Arc<McpClient>, tool: &McpTool) -> Self {
+        let read_only = tool
+            .annotations
+            .as_ref()
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false);
+        Self {
+            client,
+            tool: tool.name.clone(),
+            mutating: !read_only,
+        }
+    }
+
+    /// The tool as offered to the model, named `<server>__<tool>` so tools
+    /// from different servers cannot collide with each other or built-ins
+    pub fn definition(server: &str, tool: &McpTool) -> Tool {
+        let name = format!("{}__{}", server, tool.name)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        Tool {
+            name,
+            description: tool
+                .description
+                .clone()
+                .unwrap_or_else(|| tool.name.clone()),
+            parameters: tool.input_schema.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for McpToolExecutor {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        match self.client.call_tool(&self.tool, params).await {
+            Ok(result) if result.is_error => Ok(ToolResult::failure(result.text())),
+            Ok(result) => Ok(ToolResult::success(result.text())),
+            Err(e) => Ok(ToolResult::failure(e.to_string())),
+        }
+    }
+
+    fn is_mutating(&self) -> bool {
+        self.mutating
+    }
+}
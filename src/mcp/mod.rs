@@ -0,0 +1,32 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub mod client;
+pub mod protocol;
+pub mod server;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct McpConfig {
+    /// Servers whose tools are offered to the agent
+    #[serde(default)]
+    pub servers: Vec<McpServerConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpServerConfig {
+    /// Prefix for the server's tools, as in `<name>__<tool>`
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Variables set for the server on top of the inherited environment
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Longest wait for any one response
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+}
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
@@ -0,0 +1,205 @@
+use crate::error::McpError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+/// MCP revision this implementation speaks
+pub const PROTOCOL_VERSION: &str = "2025-06-18";
+
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// A JSON-RPC 2.0 request, notification or response. Requests carry an `id`
+/// and a `method`, notifications only a `method`, responses an `id` and
+/// either a `result` or an `error`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub method: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub params: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Message {
+    pub fn request(id: u64, method: &str, params: Value) -> Self {
+        Self {
+            id: Some(Value::from(id)),
+            method: Some(method.to_string()),
+            params: Some(params),
+            ..Self::empty()
+        }
+    }
+
+    pub fn notification(method: &str) -> Self {
+        Self {
+            method: Some(method.to_string()),
+            ..Self::empty()
+        }
+    }
+
+    pub fn response(id: Value, result: Value) -> Self {
+        Self {
+            id: Some(id),
+            result: Some(result),
+            ..Self::empty()
+        }
+    }
+
+    pub fn error_response(id: Value, code: i64, message: impl Into<String>) -> Self {
+        Self {
+            id: Some(id),
+            error: Some(RpcError {
+                code,
+                message: message.into(),
+                data: None,
+            }),
+            ..Self::empty()
+        }
+    }
+
+    fn empty() -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            id: None,
+            method: None,
+            params: None,
+            result: None,
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// A tool as listed by `tools/list`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub input_schema: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub annotations: Option<ToolAnnotations>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolAnnotations {
+    /// The tool does not change its environment; unset means it might
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub read_only_hint: Option<bool>,
+}
+
+/// The result of `tools/call`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallToolResult {
+    #[serde(default)]
+    pub content: Vec<Content>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structured_content: Option<Value>,
+    #[serde(default)]
+    pub is_error: bool,
+}
+
+impl CallToolResult {
+    /// The text parts of the result, with placeholders for anything else
+    pub fn text(&self) -> String {
+        if self.content.is_empty() {
+            return self
+                .structured_content
+                .as_ref()
+                .map(Value::to_string)
+                .unwrap_or_default();
+        }
+        let parts: Vec<String> = self
+            .content
+            .iter()
+            .map(|content| match content {
+                Content::Text { text } => text.clone(),
+                Content::Image { mime_type } => format!("[image: {}]", mime_type),
+                Content::Audio { mime_type } => format!("[audio: {}]", mime_type),
+                Content::Resource { resource } => resource
+                    .get("text")
+                    .and_then(Value::as_str)
+                    .map(String::from)
+                    .unwrap_or_else(|| format!("[resource: {}]", resource["uri"])),
+                Content::ResourceLink { uri } => format!("[resource: {}]", uri),
+                Content::Other => "[unsupported content]".to_string(),
+            })
+            .collect();
+        parts.join("\n")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Content {
+    Text {
+        text: String,
+    },
+    Image {
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Audio {
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
+    Resource {
+        resource: Value,
+    },
+    ResourceLink {
+        uri: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// Write one message as a line of JSON
+pub async fn write_message<W: AsyncWrite + Unpin>(
+    writer: &mut W,
+    message: &Message,
+) -> Result<(), McpError> {
+    let mut line = serde_json::to_vec(message).map_err(|e| McpError::Protocol(e.to_string()))?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Read the next line of JSON, skipping blank lines; `None` at end of input.
+/// The error for a malformed line still consumes it.
+pub async fn read_message<R: AsyncBufRead + Unpin>(
+    reader: &mut R,
+) -> Result<Option<Message>, McpError> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        if !line.trim().is_empty() {
+            break;
+        }
+    }
+    serde_json::from_str(&line)
+        .map(Some)
+        .map_err(|e| McpError::Protocol(e.to_string()))
+}
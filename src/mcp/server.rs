@@ -0,0 +1,166 @@
+use crate::error::McpError;
+use crate::mcp::protocol::{
+    CallToolResult, Content, INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND, McpTool, Message,
+    PARSE_ERROR, PROTOCOL_VERSION, ToolAnnotations, read_message, write_message,
+};
+use crate::providers::types::{FunctionCall, ToolCall};
+use crate::tools::audit::AuditLog;
+use crate::tools::executor::ToolExecutionDispatcher;
+use crate::tools::registry::ToolRegistry;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncWrite};
+
+/// Offers the tools of a registry to MCP clients. Calls go through a
+/// dispatcher without an approver, since the client asks its own user.
+pub struct McpServer {
+    registry: Arc<ToolRegistry>,
+    dispatcher: ToolExecutionDispatcher,
+}
+
+impl McpServer {
+    pub fn new(registry: ToolRegistry) -> Self {
+        let registry = Arc::new(registry);
+        Self {
+            dispatcher: ToolExecutionDispatcher::new(registry.clone()),
+            registry,
+        }
+    }
+
+    /// Cut every tool result to at most `bytes`
+    pub fn with_output_limit(mut self, bytes: usize) -> Self {
+        self.dispatcher = self.dispatcher.with_output_limit(bytes);
+        self
+    }
+
+    /// Record every tool call in `log`
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.dispatcher = self.dispatcher.with_audit_log(log);
+        self
+    }
+
+    /// Have mutating tools report what they would do instead of running
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dispatcher = self.dispatcher.with_dry_run(dry_run);
+        self
+    }
+
+    /// Answer newline-delimited JSON-RPC messages until the input ends
+    pub async fn serve<R, W>(&self, mut reader: R, mut writer: W) -> Result<(), McpError>
+    where
+        R: AsyncBufRead + Unpin,
+        W: AsyncWrite + Unpin,
+    {
+        loop {
+            let reply = match read_message(&mut reader).await {
+                Ok(Some(message)) => self.handle(message).await,
+                Ok(None) => return Ok(()),
+                Err(McpError::Protocol(e)) => {
+                    Some(Message::error_response(Value::Null, PARSE_ERROR, e))
+                }
+                Err(e) => return Err(e),
+            };
+            if let Some(reply) = reply {
+                write_message(&mut writer, &reply).await?;
+            }
+        }
+    }
+
+    /// The reply to one message; notifications and responses get none
+    pub async fn handle(&self, message: Message) -> Option<Message> {
+        let (id, method) = match (message.id, message.method) {
+            (Some(id), Some(method)) => (id, method),
+            (None, Some(method)) => {
+                tracing::debug!("MCP notification: {}", method);
+                return None;
+            }
+            (Some(id), None) if message.result.is_none() && message.error.is_none() => {
+                return Some(Message::error_response(
+                    id,
+                    INVALID_REQUEST,
+                    "Request has no method",
+                ));
+            }
+            _ => return None,
+        };
+        let params = message.params.unwrap_or(Value::Null);
+
+        match method.as_str() {
+            "initialize" => Some(Message::response(
+                id,
+                json!({
+                    "protocolVersion": PROTOCOL_VERSION,
+                    "capabilities": {"tools": {"listChanged": false}},
+                    "serverInfo": {"name": "xzardgz", "version": env!("CARGO_PKG_VERSION")}
+                }),
+            )),
+            "ping" => Some(Message::response(id, json!({}))),
+            "tools/list" => Some(Message::response(id, json!({ "tools": self.tools() }))),
+            "tools/call" => Some(self.call(id, params).await),
+            _ => Some(Message::error_response(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Unknown method: {}", method),
+            )),
+        }
+    }
+
+    fn tools(&self) -> Vec<McpTool> {
+        let mut tools: Vec<McpTool> = self
+            .registry
+            .list_tools()
+            .into_iter()
+            .map(|tool| {
+                let read_only = self
+                    .registry
+                    .get_executor(&tool.name)
+                    .is_some_and(|executor| !executor.is_mutating());
+                McpTool {
+                    name: tool.name,
+                    description: Some(tool.description),
+                    input_schema: tool.parameters,
+                    annotations: Some(ToolAnnotations {
+                        read_only_hint: Some(read_only),
+                    }),
+                }
+            })
+            .collect();
+        tools.sort_by(|a, b| a.name.cmp(&b.name));
+        tools
+    }
+
+    async fn call(&self, id: Value, params: Value) -> Message {
+        let Some(name) = params["name"].as_str() else {
+            return Message::error_response(id, INVALID_PARAMS, "Missing tool name");
+        };
+        if self.registry.get_tool(name).is_none() {
+            return Message::error_response(id, INVALID_PARAMS, format!("Unknown tool: {}", name));
+        }
+        let arguments = match &params["arguments"] {
+            Value::Null => json!({}),
+            arguments => arguments.clone(),
+        };
+        let call = ToolCall {
+            id: format!("mcp-{}", id),
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
+        };
+
+        // Tool failures are results the client's model should see, not protocol errors
+        let (text, is_error) = match self.dispatcher.execute(&call).await {
+            Ok(result) => match result.error {
+                Some(error) => (error, true),
+                None => (result.output, false),
+            },
+            Err(e) => (e.to_string(), true),
+        };
+        let result = CallToolResult {
+            content: vec![Content::Text { text }],
+            structured_content: None,
+            is_error,
+        };
+        Message::response(id, serde_json::to_value(result).unwrap_or_default())
+    }
+}
@@ -0,0 +1,539 @@
+use crate::error::RepositoryError;
+use proc_macro2::{Span, TokenStream, TokenTree};
+use std::path::{Component, Path};
+use std::str::FromStr;
+use syn::spanned::Spanned;
+use syn::{
+    AttrStyle, Attribute, Expr, ExprLit, Fields, ImplItem, Item, Lit, Meta, TraitItem, Type,
+    Visibility,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolKind {
+    Function,
+    /// A function in an impl block or trait
+    Method,
+    Struct,
+    Enum,
+    Union,
+    Trait,
+    Impl,
+    TypeAlias,
+    Const,
+    Static,
+    Module,
+    Macro,
+}
+
+/// An item declared in a Rust source file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Symbol {
+    /// Item name. Items in impl blocks and traits are `Type::name`; impl
+    /// blocks are named after their self type.
+    pub name: String,
+    pub kind: SymbolKind,
+    /// Module the item is declared in, e.g. `crate::tools::schema`
+    pub module: String,
+    /// The declaration on one line, without body
+    pub signature: String,
+    /// Source of the declaration: the whole item for types and constants,
+    /// the signature for functions, traits, impls and modules
+    pub definition: String,
+    pub docs: Option<String>,
+    /// 1-based line of the declaration, after its attributes
+    pub line: usize,
+    /// Declared `pub`, or part of a public trait or of a trait impl
+    pub public: bool,
+    /// Signature of the impl block or trait the item belongs to
+    pub parent: Option<String>,
+}
+
+/// Where an identifier occurs in a source file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference {
+    /// 1-based line
+    pub line: usize,
+    /// 1-based column, in characters
+    pub column: usize,
+}
+
+/// The items declared in `source`, a Rust file of module `module`, in
+/// source order. Inline modules are descended into.
+pub fn parse_symbols(source: &str, module: &str) -> Result<Vec<Symbol>, RepositoryError> {
+    let symbols = match syn::parse_file(source) {
+        Ok(file) => {
+            let mut extractor = Extractor {
+                source,
+                symbols: Vec::new(),
+            };
+            extractor.items(&file.items, module);
+            Ok(extractor.symbols)
+        }
+        Err(e) => Err(parse_error(e.span(), e)),
+    };
+    // Parsed spans keep the source alive for the thread; nothing refers to it any more
+    proc_macro2::extra::invalidate_current_thread_spans();
+    symbols
+}
+
+/// Every occurrence of the identifier `name` in `source`, outside comments
+/// and string literals. Identifiers are matched by name only; no paths or
+/// types are resolved.
+pub fn find_references(source: &str, name: &str) -> Result<Vec<Reference>, RepositoryError> {
+    let references = match TokenStream::from_str(source) {
+        Ok(tokens) => {
+            let mut references = Vec::new();
+            collect_references(tokens, name.trim_start_matches("r#"), &mut references);
+            Ok(references)
+        }
+        Err(e) => Err(parse_error(e.span(), e)),
+    };
+    proc_macro2::extra::invalidate_current_thread_spans();
+    references
+}
+
+/// Module path of a file from its path relative to the crate, e.g.
+/// `src/tools/mod.rs` is `crate::tools`. Files outside `src` are named
+/// after their path.
+pub fn module_path(relative: &Path) -> String {
+    let parts: Vec<&str> = relative
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => part.to_str(),
+            _ => None,
+        })
+        .collect();
+    let parts = match parts.iter().rposition(|part| *part == "src") {
+        Some(src) => &parts[src + 1..],
+        None => &parts[..],
+    };
+
+    let mut module = String::from("crate");
+    for (index, part) in parts.iter().enumerate() {
+        let part = part.strip_suffix(".rs").unwrap_or(part);
+        let last = index + 1 == parts.len();
+        if last && (part == "mod" || (index == 0 && (part == "lib" || part == "main"))) {
+            continue;
+        }
+        module.push_str("::");
+        module.push_str(part);
+    }
+    module
+}
+
+fn parse_error(span: Span, error: impl std::fmt::Display) -> RepositoryError {
+    RepositoryError::Parse(format!("line {}: {}", span.start().line, error))
+}
+
+fn collect_references(tokens: TokenStream, name: &str, references: &mut Vec<Reference>) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) => {
+                let text = ident.to_string();
+                if text.trim_start_matches("r#") == name {
+                    let start = ident.span().start();
+                    references.push(Reference {
+                        line: start.line,
+                        column: start.column + 1,
+                    });
+                }
+            }
+            TokenTree::Group(group) => collect_references(group.stream(), name, references),
+            _ => {}
+        }
+    }
+}
+
+struct Extractor<'a> {
+    source: &'a str,
+    symbols: Vec<Symbol>,
+}
+
+/// Where a declaration's header ends
+enum Header {
+    /// Before this token, usually the opening brace of the body
+    Before(Span),
+    /// With the item itself
+    Whole,
+}
+
+/// Everything about an item that `Extractor::push` needs
+struct Declaration<'a> {
+    name: String,
+    kind: SymbolKind,
+    attrs: &'a [Attribute],
+    span: Span,
+    header: Header,
+    /// Show the whole item as the definition rather than just its header
+    whole_definition: bool,
+    public: bool,
+    parent: Option<String>,
+}
+
+impl<'a> Extractor<'a> {
+    fn items(&mut self, items: &[Item], module: &str) {
+        for item in items {
+            self.item(item, module);
+        }
+    }
+
+    fn item(&mut self, item: &Item, module: &str) {
+        let declaration = match item {
+            Item::Fn(item) => Declaration {
+                name: item.sig.ident.to_string(),
+                kind: SymbolKind::Function,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.block.brace_token.span.open()),
+                whole_definition: false,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Struct(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::Struct,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: match &item.fields {
+                    Fields::Named(fields) => Header::Before(fields.brace_token.span.open()),
+                    _ => Header::Whole,
+                },
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Enum(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::Enum,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.brace_token.span.open()),
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Union(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::Union,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.fields.brace_token.span.open()),
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Type(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::TypeAlias,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Whole,
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Const(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::Const,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.eq_token.span),
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Static(item) => Declaration {
+                name: item.ident.to_string(),
+                kind: SymbolKind::Static,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.eq_token.span),
+                whole_definition: true,
+                public: is_public(&item.vis),
+                parent: None,
+            },
+            Item::Trait(item) => return self.trait_item(item, module),
+            Item::Impl(item) => return self.impl_item(item, module),
+            Item::Mod(item) => {
+                let name = item.ident.to_string();
+                self.push(
+                    module,
+                    Declaration {
+                        name: name.clone(),
+                        kind: SymbolKind::Module,
+                        attrs: &item.attrs,
+                        span: item.span(),
+                        header: match &item.content {
+                            Some((brace, _)) => Header::Before(brace.span.open()),
+                            None => Header::Whole,
+                        },
+                        whole_definition: false,
+                        public: is_public(&item.vis),
+                        parent: None,
+                    },
+                );
+                if let Some((_, items)) = &item.content {
+                    self.items(items, &format!("{}::{}", module, name));
+                }
+                return;
+            }
+            Item::Macro(item) => {
+                let Some(ident) = &item.ident else {
+                    return;
+                };
+                Declaration {
+                    name: ident.to_string(),
+                    kind: SymbolKind::Macro,
+                    attrs: &item.attrs,
+                    span: item.span(),
+                    header: Header::Before(item.mac.delimiter.span().open()),
+                    whole_definition: false,
+                    public: item
+                        .attrs
+                        .iter()
+                        .any(|attr| attr.path().is_ident("macro_export")),
+                    parent: None,
+                }
+            }
+            _ => return,
+        };
+        self.push(module, declaration);
+    }
+
+    fn trait_item(&mut self, item: &syn::ItemTrait, module: &str) {
+        let public = is_public(&item.vis);
+        let name = item.ident.to_string();
+        let parent = self.push(
+            module,
+            Declaration {
+                name: name.clone(),
+                kind: SymbolKind::Trait,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.brace_token.span.open()),
+                whole_definition: false,
+                public,
+                parent: None,
+            },
+        );
+
+        for member in &item.items {
+            let (ident, kind, attrs, header) = match member {
+                TraitItem::Fn(f) => (
+                    &f.sig.ident,
+                    SymbolKind::Method,
+                    &f.attrs,
+                    f.default.as_ref().map_or(Header::Whole, |block| {
+                        Header::Before(block.brace_token.span.open())
+                    }),
+                ),
+                TraitItem::Const(c) => (&c.ident, SymbolKind::Const, &c.attrs, Header::Whole),
+                TraitItem::Type(t) => (&t.ident, SymbolKind::TypeAlias, &t.attrs, Header::Whole),
+                _ => continue,
+            };
+            self.push(
+                module,
+                Declaration {
+                    name: format!("{}::{}", name, ident),
+                    kind,
+                    attrs,
+                    span: member.span(),
+                    header,
+                    whole_definition: kind != SymbolKind::Method,
+                    public,
+                    parent: Some(parent.clone()),
+                },
+            );
+        }
+    }
+
+    fn impl_item(&mut self, item: &syn::ItemImpl, module: &str) {
+        let type_name = match item.self_ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string()),
+            _ => None,
+        }
+        .unwrap_or_else(|| one_line(self.text(item.self_ty.span())));
+        let trait_impl = item.trait_.is_some();
+        let index = self.symbols.len();
+        let parent = self.push(
+            module,
+            Declaration {
+                name: type_name.clone(),
+                kind: SymbolKind::Impl,
+                attrs: &item.attrs,
+                span: item.span(),
+                header: Header::Before(item.brace_token.span.open()),
+                whole_definition: false,
+                public: trait_impl,
+                parent: None,
+            },
+        );
+
+        for member in &item.items {
+            let (ident, kind, attrs, vis, header) = match member {
+                ImplItem::Fn(f) => (
+                    &f.sig.ident,
+                    SymbolKind::Method,
+                    &f.attrs,
+                    &f.vis,
+                    Header::Before(f.block.brace_token.span.open()),
+                ),
+                ImplItem::Const(c) => (
+                    &c.ident,
+                    SymbolKind::Const,
+                    &c.attrs,
+                    &c.vis,
+                    Header::Before(c.eq_token.span),
+                ),
+                ImplItem::Type(t) => (
+                    &t.ident,
+                    SymbolKind::TypeAlias,
+                    &t.attrs,
+                    &t.vis,
+                    Header::Whole,
+                ),
+                _ => continue,
+            };
+            self.push(
+                module,
+                Declaration {
+                    name: format!("{}::{}", type_name, ident),
+                    kind,
+                    attrs,
+                    span: member.span(),
+                    header,
+                    whole_definition: kind != SymbolKind::Method,
+                    public: trait_impl || is_public(vis),
+                    parent: Some(parent.clone()),
+                },
+            );
+        }
+
+        // An inherent impl is public API when anything in it is
+        let any_public = self.symbols[index + 1..].iter().any(|symbol| symbol.public);
+        self.symbols[index].public |= any_public;
+    }
+
+    /// Record `declaration` and return its signature
+    fn push(&mut self, module: &str, declaration: Declaration) -> String {
+        let span = declaration.span.byte_range();
+        let start = self.declaration_start(declaration.attrs, span.start);
+        let header_end = match declaration.header {
+            Header::Before(token) => token.byte_range().start,
+            Header::Whole => span.end,
+        };
+        let end = if declaration.whole_definition {
+            span.end
+        } else {
+            header_end
+        };
+
+        let signature = one_line(&self.source[start..header_end]);
+        let signature = signature.trim_end_matches(';').trim_end().to_string();
+        self.symbols.push(Symbol {
+            name: declaration.name,
+            kind: declaration.kind,
+            module: module.to_string(),
+            signature: signature.clone(),
+            definition: self.dedent(start, end),
+            docs: docs(declaration.attrs),
+            line: self.source[..start].matches('\n').count() + 1,
+            public: declaration.public,
+            parent: declaration.parent,
+        });
+        signature
+    }
+
+    /// Byte offset of the first token after the outer attributes, skipping
+    /// plain comments
+    fn declaration_start(&self, attrs: &[Attribute], item_start: usize) -> usize {
+        let mut start = attrs
+            .iter()
+            .filter(|attr| matches!(attr.style, AttrStyle::Outer))
+            .map(|attr| attr.span().byte_range().end)
+            .max()
+            .unwrap_or(item_start)
+            .max(item_start);
+        loop {
+            let rest = &self.source[start..];
+            let trimmed = rest.trim_start();
+            start += rest.len() - trimmed.len();
+            if !trimmed.starts_with("//") {
+                return start;
+            }
+            start += trimmed
+                .find('\n')
+                .map_or(trimmed.len(), |newline| newline + 1);
+        }
+    }
+
+    /// Source between `start` and `end`, with the indentation of the first
+    /// line removed from the others
+    fn dedent(&self, start: usize, end: usize) -> String {
+        let line_start = self.source[..start].rfind('\n').map_or(0, |n| n + 1);
+        let indent = start - line_start;
+        let text = self.source[start..end].trim_end();
+        let mut lines = text.lines();
+        let mut dedented: Vec<&str> = lines.next().into_iter().collect();
+        for line in lines {
+            let spaces = line.len() - line.trim_start_matches(' ').len();
+            dedented.push(&line[spaces.min(indent)..]);
+        }
+        dedented.join("\n")
+    }
+
+    fn text(&self, span: Span) -> &str {
+        &self.source[span.byte_range()]
+    }
+}
+
+fn is_public(vis: &Visibility) -> bool {
+    matches!(vis, Visibility::Public(_))
+}
+
+/// The text of the `///` and `/** */` comments in `attrs`
+fn docs(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| matches!(attr.style, AttrStyle::Outer) && attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(doc) => match &doc.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => Some(text.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .flat_map(|text| {
+            text.split('\n')
+                .map(|line| {
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    let docs = lines.join("\n").trim().to_string();
+    (!docs.is_empty()).then_some(docs)
+}
+
+/// `text` with whitespace runs collapsed, as a multi-line signature reads
+/// when written on one line
+fn one_line(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace(", )", ")")
+        .replace("( ", "(")
+        .replace(" )", ")")
+        .replace("< ", "<")
+        .replace(", >", ">")
+}
@@ -0,0 +1,607 @@
+use crate::error::RepositoryError;
+use globset::Glob;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::{Table, Value};
+
+/// The packages of a Cargo project, as declared in its manifests and
+/// resolved by its lock file
+#[derive(Debug, Clone, Serialize)]
+pub struct CargoProject {
+    /// Member directories of `[workspace]`, relative to the project root;
+    /// empty for a single package
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub workspace_members: Vec<String>,
+    pub packages: Vec<CargoPackage>,
+    /// Whether a `Cargo.lock` was found, and so `resolved` versions are known
+    pub locked: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CargoPackage {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edition: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rust_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// Path of the package's `Cargo.toml`, relative to the project root
+    pub manifest: String,
+    pub targets: Vec<CargoTarget>,
+    /// Features and what they enable, including the implicit features of
+    /// optional dependencies
+    pub features: BTreeMap<String, Vec<String>>,
+    pub dependencies: Vec<CargoDependency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum TargetKind {
+    Lib,
+    Bin,
+    Example,
+    Test,
+    Bench,
+    /// The build script
+    CustomBuild,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CargoTarget {
+    pub kind: TargetKind,
+    pub name: String,
+    /// Source file, relative to the project root
+    pub path: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub required_features: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DependencyKind {
+    Normal,
+    Dev,
+    Build,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CargoDependency {
+    /// Name the dependency is used under
+    pub name: String,
+    /// The package's real name, when the dependency is renamed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    pub kind: DependencyKind,
+    /// Version requirement, e.g. `1.0.228`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requirement: Option<String>,
+    /// Version chosen by `Cargo.lock`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub git: Option<String>,
+    #[serde(skip_serializing_if = "is_false")]
+    pub optional: bool,
+    #[serde(skip_serializing_if = "is_true")]
+    pub default_features: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub features: Vec<String>,
+    /// Platform the dependency is limited to, e.g. `cfg(windows)`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_true(value: &bool) -> bool {
+    *value
+}
+
+/// Dependencies as listed in `[[package]]` entries of `Cargo.lock`
+struct LockPackage {
+    name: String,
+    version: String,
+    dependencies: Vec<String>,
+}
+
+/// What members inherit from the root manifest's `[workspace]`
+#[derive(Default)]
+struct Inherited {
+    package: Table,
+    dependencies: Table,
+}
+
+impl CargoProject {
+    /// Read the project whose `Cargo.toml` (and `Cargo.lock`, if any) is in
+    /// `root`. Workspace members are read too.
+    pub fn load(root: &Path) -> Result<Self, RepositoryError> {
+        let manifest = read_manifest(&root.join("Cargo.toml"))?;
+        let lock = read_lock(&root.join("Cargo.lock"))?;
+
+        let workspace = manifest.get("workspace").and_then(Value::as_table);
+        let inherited = workspace.map_or_else(Inherited::default, |workspace| Inherited {
+            package: table(workspace, "package"),
+            dependencies: table(workspace, "dependencies"),
+        });
+        let workspace_members = match workspace {
+            Some(workspace) => members(
+                root,
+                &strings(workspace.get("members")),
+                &strings(workspace.get("exclude")),
+            )?,
+            None => Vec::new(),
+        };
+
+        let mut packages = Vec::new();
+        if manifest.contains_key("package") {
+            packages.push(package(
+                root,
+                Path::new(""),
+                &manifest,
+                &inherited,
+                lock.as_deref(),
+            )?);
+        }
+        for member in &workspace_members {
+            let dir = Path::new(member);
+            if dir.as_os_str().is_empty() || dir == Path::new(".") {
+                continue;
+            }
+            let manifest = read_manifest(&root.join(dir).join("Cargo.toml"))?;
+            if manifest.contains_key("package") {
+                packages.push(package(root, dir, &manifest, &inherited, lock.as_deref())?);
+            }
+        }
+        if packages.is_empty() {
+            return Err(RepositoryError::Manifest(format!(
+                "{} declares no package or workspace members",
+                root.join("Cargo.toml").display()
+            )));
+        }
+
+        Ok(Self {
+            workspace_members,
+            packages,
+            locked: lock.is_some(),
+        })
+    }
+}
+
+fn read_manifest(path: &Path) -> Result<Table, RepositoryError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| RepositoryError::Manifest(format!("{}: {}", path.display(), e)))?;
+    text.parse::<Table>()
+        .map_err(|e| RepositoryError::Manifest(format!("{}: {}", path.display(), e)))
+}
+
+fn read_lock(path: &Path) -> Result<Option<Vec<LockPackage>>, RepositoryError> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let lock = read_manifest(path)?;
+    let packages = lock
+        .get("package")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_table)
+        .filter_map(|entry| {
+            Some(LockPackage {
+                name: entry.get("name")?.as_str()?.to_string(),
+                version: entry.get("version")?.as_str()?.to_string(),
+                dependencies: strings(entry.get("dependencies")),
+            })
+        })
+        .collect();
+    Ok(Some(packages))
+}
+
+fn table(parent: &Table, key: &str) -> Table {
+    parent
+        .get(key)
+        .and_then(Value::as_table)
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn strings(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+        .map(str::to_string)
+        .collect()
+}
+
+/// Expand the `members` globs of `[workspace]` into directories with a
+/// `Cargo.toml`, sorted
+fn members(
+    root: &Path,
+    patterns: &[String],
+    exclude: &[String],
+) -> Result<Vec<String>, RepositoryError> {
+    let mut members = Vec::new();
+    for pattern in patterns {
+        let mut dirs = vec![PathBuf::new()];
+        for part in pattern.trim_end_matches('/').split('/') {
+            if !part.contains(['*', '?', '[']) {
+                dirs = dirs.into_iter().map(|dir| dir.join(part)).collect();
+                continue;
+            }
+            let matcher = Glob::new(part)
+                .map_err(|e| RepositoryError::Manifest(format!("member {}: {}", pattern, e)))?
+                .compile_matcher();
+            let mut matched = Vec::new();
+            for dir in dirs {
+                let Ok(entries) = fs::read_dir(root.join(&dir)) else {
+                    continue;
+                };
+                for entry in entries.flatten() {
+                    if entry.path().is_dir() && matcher.is_match(entry.file_name()) {
+                        matched.push(dir.join(entry.file_name()));
+                    }
+                }
+            }
+            dirs = matched;
+        }
+        members.extend(
+            dirs.into_iter()
+                .filter(|dir| root.join(dir).join("Cargo.toml").is_file())
+                .map(|dir| dir.to_string_lossy().replace('\\', "/")),
+        );
+    }
+    members.retain(|member| !exclude.contains(member));
+    members.sort();
+    members.dedup();
+    Ok(members)
+}
+
+fn package(
+    root: &Path,
+    dir: &Path,
+    manifest: &Table,
+    inherited: &Inherited,
+    lock: Option<&[LockPackage]>,
+) -> Result<CargoPackage, RepositoryError> {
+    let section = table(manifest, "package");
+    // `version.workspace = true` and the like take the value from the root manifest
+    let field = |key: &str| match section.get(key) {
+        Some(Value::Table(value)) if value.get("workspace") == Some(&Value::Boolean(true)) => {
+            inherited
+                .package
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        }
+        Some(value) => value.as_str().map(str::to_string),
+        None => None,
+    };
+    let name = field("name").ok_or_else(|| {
+        RepositoryError::Manifest(format!(
+            "{} has no package name",
+            relative(dir, "Cargo.toml")
+        ))
+    })?;
+    let version = field("version");
+
+    let mut dependencies = Vec::new();
+    for (key, kind) in [
+        ("dependencies", DependencyKind::Normal),
+        ("dev-dependencies", DependencyKind::Dev),
+        ("build-dependencies", DependencyKind::Build),
+    ] {
+        for (dep, value) in table(manifest, key) {
+            dependencies.push(dependency(dep, &value, kind, None, inherited));
+        }
+        for (target, platform) in table(manifest, "target") {
+            let Some(platform) = platform.as_table() else {
+                continue;
+            };
+            for (dep, value) in table(platform, key) {
+                dependencies.push(dependency(dep, &value, kind, Some(&target), inherited));
+            }
+        }
+    }
+    if let Some(lock) = lock {
+        for dependency in &mut dependencies {
+            let package = dependency.package.as_deref().unwrap_or(&dependency.name);
+            dependency.resolved = resolve(
+                lock,
+                &name,
+                version.as_deref(),
+                package,
+                dependency.requirement.as_deref(),
+            );
+        }
+    }
+
+    let mut features: BTreeMap<String, Vec<String>> = table(manifest, "features")
+        .iter()
+        .map(|(feature, enables)| (feature.clone(), strings(Some(enables))))
+        .collect();
+    // Optional dependencies are features of their own unless a feature names them as `dep:`
+    let explicit = features
+        .values()
+        .flatten()
+        .any(|enabled| enabled.starts_with("dep:"));
+    if !explicit {
+        for dependency in dependencies.iter().filter(|dependency| dependency.optional) {
+            features
+                .entry(dependency.name.clone())
+                .or_insert_with(|| vec![format!("dep:{}", dependency.name)]);
+        }
+    }
+
+    Ok(CargoPackage {
+        targets: targets(root, dir, manifest, &name),
+        edition: field("edition"),
+        rust_version: field("rust-version"),
+        description: field("description"),
+        license: field("license"),
+        repository: field("repository"),
+        manifest: relative(dir, "Cargo.toml"),
+        name,
+        version,
+        features,
+        dependencies,
+    })
+}
+
+fn dependency(
+    name: String,
+    value: &Value,
+    kind: DependencyKind,
+    target: Option<&str>,
+    inherited: &Inherited,
+) -> CargoDependency {
+    let mut dependency = CargoDependency {
+        name,
+        package: None,
+        kind,
+        requirement: None,
+        resolved: None,
+        path: None,
+        git: None,
+        optional: false,
+        default_features: true,
+        features: Vec::new(),
+        target: target.map(str::to_string),
+    };
+    let Some(spec) = value.as_table() else {
+        dependency.requirement = value.as_str().map(str::to_string);
+        return dependency;
+    };
+
+    // `foo.workspace = true` starts from `[workspace.dependencies]`; features add up
+    if spec.get("workspace") == Some(&Value::Boolean(true))
+        && let Some(base) = inherited.dependencies.get(&dependency.name)
+    {
+        let target = dependency.target.take();
+        dependency = self::dependency(dependency.name, base, kind, None, &Inherited::default());
+        dependency.target = target;
+    }
+    let text = |key: &str| spec.get(key).and_then(Value::as_str).map(str::to_string);
+    if let Some(requirement) = text("version") {
+        dependency.requirement = Some(requirement);
+    }
+    if let Some(package) = text("package") {
+        dependency.package = Some(package);
+    }
+    if let Some(path) = text("path") {
+        dependency.path = Some(path);
+    }
+    if let Some(git) = text("git") {
+        dependency.git = Some(git);
+    }
+    if let Some(optional) = spec.get("optional").and_then(Value::as_bool) {
+        dependency.optional = optional;
+    }
+    if let Some(default_features) = spec
+        .get("default-features")
+        .or_else(|| spec.get("default_features"))
+        .and_then(Value::as_bool)
+    {
+        dependency.default_features = default_features;
+    }
+    dependency.features.extend(strings(spec.get("features")));
+    dependency
+}
+
+/// The version of `dependency` that `Cargo.lock` chose for `package`
+fn resolve(
+    lock: &[LockPackage],
+    package: &str,
+    version: Option<&str>,
+    dependency: &str,
+    requirement: Option<&str>,
+) -> Option<String> {
+    let candidates: Vec<&LockPackage> = lock
+        .iter()
+        .filter(|entry| entry.name == dependency)
+        .collect();
+    if let [only] = candidates.as_slice() {
+        return Some(only.version.clone());
+    }
+    // With several versions locked, the dependent's entry names the ones it
+    // uses, and the requirement tells them apart
+    let entry = lock
+        .iter()
+        .find(|entry| entry.name == package && version.is_none_or(|v| entry.version == v))?;
+    entry
+        .dependencies
+        .iter()
+        .filter_map(|listed| {
+            let mut words = listed.split_whitespace();
+            (words.next() == Some(dependency)).then(|| words.next())?
+        })
+        .find(|locked| requirement.is_none_or(|requirement| compatible(requirement, locked)))
+        .map(str::to_string)
+}
+
+/// Whether `version` can satisfy `requirement`, judged by the components
+/// Cargo's default (caret), `~` and `=` requirements keep fixed
+fn compatible(requirement: &str, version: &str) -> bool {
+    let requirement = requirement.trim();
+    let (fixed, required) = if let Some(rest) = requirement.strip_prefix('=') {
+        (usize::MAX, rest)
+    } else if let Some(rest) = requirement.strip_prefix('~') {
+        (2, rest)
+    } else if requirement.starts_with(['>', '<', '*']) || requirement.contains(',') {
+        return true;
+    } else {
+        (0, requirement.trim_start_matches('^'))
+    };
+    let numbers = |text: &str| -> Vec<u64> {
+        text.split(['-', '+'])
+            .next()
+            .unwrap_or_default()
+            .split('.')
+            .map_while(|part| part.trim().parse().ok())
+            .collect()
+    };
+    let required = numbers(required);
+    let version = numbers(version);
+    // A caret requirement fixes everything up to the first non-zero component
+    let fixed = if fixed == 0 {
+        required
+            .iter()
+            .position(|&number| number != 0)
+            .map_or(required.len(), |first| first + 1)
+    } else {
+        fixed.min(required.len())
+    };
+    required
+        .iter()
+        .zip(&version)
+        .take(fixed)
+        .all(|(required, version)| required == version)
+}
+
+fn relative(dir: &Path, path: impl AsRef<Path>) -> String {
+    dir.join(path).to_string_lossy().replace('\\', "/")
+}
+
+/// Targets declared in the manifest, plus those Cargo discovers from the
+/// standard layout unless `autobins` and friends turn that off
+fn targets(root: &Path, dir: &Path, manifest: &Table, package: &str) -> Vec<CargoTarget> {
+    let section = table(manifest, "package");
+    let auto = |key: &str| section.get(key).and_then(Value::as_bool).unwrap_or(true);
+    let exists = |path: &str| root.join(dir).join(path).is_file();
+    let mut targets = Vec::new();
+
+    let lib = manifest.get("lib").and_then(Value::as_table);
+    if lib.is_some() || exists("src/lib.rs") {
+        let lib = lib.cloned().unwrap_or_default();
+        targets.push(CargoTarget {
+            kind: TargetKind::Lib,
+            name: lib
+                .get("name")
+                .and_then(Value::as_str)
+                .map_or_else(|| package.replace('-', "_"), str::to_string),
+            path: relative(
+                dir,
+                lib.get("path")
+                    .and_then(Value::as_str)
+                    .unwrap_or("src/lib.rs"),
+            ),
+            required_features: Vec::new(),
+        });
+    }
+
+    for (key, kind, auto_key, auto_dir) in [
+        ("bin", TargetKind::Bin, "autobins", "src/bin"),
+        ("example", TargetKind::Example, "autoexamples", "examples"),
+        ("test", TargetKind::Test, "autotests", "tests"),
+        ("bench", TargetKind::Bench, "autobenches", "benches"),
+    ] {
+        let mut found: Vec<CargoTarget> = manifest
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_table)
+            .filter_map(|declared| {
+                let name = declared.get("name")?.as_str()?.to_string();
+                let path = match declared.get("path").and_then(Value::as_str) {
+                    Some(path) => path.to_string(),
+                    None if kind == TargetKind::Bin && name == package => "src/main.rs".to_string(),
+                    None => format!("{}/{}.rs", auto_dir, name),
+                };
+                Some(CargoTarget {
+                    kind,
+                    path: relative(dir, path),
+                    required_features: strings(declared.get("required-features")),
+                    name,
+                })
+            })
+            .collect();
+
+        if auto(auto_key) {
+            let mut discovered = Vec::new();
+            if kind == TargetKind::Bin && exists("src/main.rs") {
+                discovered.push((package.to_string(), "src/main.rs".to_string()));
+            }
+            if let Ok(entries) = fs::read_dir(root.join(dir).join(auto_dir)) {
+                for entry in entries.flatten() {
+                    let file_name = entry.file_name().to_string_lossy().to_string();
+                    let path = entry.path();
+                    if let Some(stem) = file_name.strip_suffix(".rs")
+                        && path.is_file()
+                    {
+                        discovered.push((stem.to_string(), format!("{}/{}", auto_dir, file_name)));
+                    } else if path.join("main.rs").is_file() {
+                        discovered.push((
+                            file_name.clone(),
+                            format!("{}/{}/main.rs", auto_dir, file_name),
+                        ));
+                    }
+                }
+            }
+            discovered.sort();
+            for (name, path) in discovered {
+                let path = relative(dir, path);
+                if !found
+                    .iter()
+                    .any(|target| target.name == name || target.path == path)
+                {
+                    found.push(CargoTarget {
+                        kind,
+                        name,
+                        path,
+                        required_features: Vec::new(),
+                    });
+                }
+            }
+        }
+        targets.extend(found);
+    }
+
+    let build = match section.get("build") {
+        Some(Value::String(path)) => Some(path.clone()),
+        Some(Value::Boolean(false)) => None,
+        _ => exists("build.rs").then(|| "build.rs".to_string()),
+    };
+    if let Some(path) = build {
+        targets.push(CargoTarget {
+            kind: TargetKind::CustomBuild,
+            name: "build-script-build".to_string(),
+            path: relative(dir, path),
+            required_features: Vec::new(),
+        });
+    }
+    targets
+}
@@ -1,38 +1,388 @@
 use crate::error::RepositoryError;
-use git2::{Repository, StatusOptions};
+use chrono::{DateTime, Utc};
+use git2::{
+    BlameOptions, Commit, Diff, DiffFormat, DiffOptions, Repository, Sort, Status, StatusOptions,
+    Tree,
+};
 use std::path::Path;
 
 pub struct GitRepository {
     repo: Repository,
 }
 
+/// Which two states of the repository `GitRepository::diff` compares
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum DiffTarget {
+    /// Unstaged changes: index to working tree
+    #[default]
+    WorkingTree,
+    /// Staged changes: HEAD to index
+    Staged,
+    /// A revision to the working tree, or to another revision
+    Revisions { from: String, to: Option<String> },
+}
+
+/// Patch text for one file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// Path relative to the repository root
+    pub path: String,
+    pub patch: String,
+}
+
+/// A changed or untracked file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusEntry {
+    /// Path relative to the repository root
+    pub path: String,
+    /// Staged and unstaged change as in `git status --short`, e.g. `M ` or `??`
+    pub code: String,
+}
+
+/// The `git status --short` letters for one file
+fn status_code(status: Status) -> String {
+    if status.is_conflicted() {
+        return "UU".to_string();
+    }
+    if status.is_wt_new() {
+        return "??".to_string();
+    }
+    let staged = if status.is_index_new() {
+        'A'
+    } else if status.is_index_modified() {
+        'M'
+    } else if status.is_index_deleted() {
+        'D'
+    } else if status.is_index_renamed() {
+        'R'
+    } else if status.is_index_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    let unstaged = if status.is_wt_modified() {
+        'M'
+    } else if status.is_wt_deleted() {
+        'D'
+    } else if status.is_wt_renamed() {
+        'R'
+    } else if status.is_wt_typechange() {
+        'T'
+    } else {
+        ' '
+    };
+    format!("{}{}", staged, unstaged)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommitInfo {
+    pub id: String,
+    pub author: String,
+    pub email: String,
+    pub time: DateTime<Utc>,
+    pub message: String,
+}
+
+impl CommitInfo {
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(7)]
+    }
+
+    /// First line of the message
+    pub fn summary(&self) -> &str {
+        self.message.lines().next().unwrap_or_default()
+    }
+
+    fn from_commit(commit: &Commit) -> Self {
+        let author = commit.author();
+        Self {
+            id: commit.id().to_string(),
+            author: author.name().unwrap_or("unknown").to_string(),
+            email: author.email().unwrap_or_default().to_string(),
+            time: timestamp(author.when().seconds()),
+            message: commit.message().unwrap_or_default().trim_end().to_string(),
+        }
+    }
+}
+
+/// Last change to one line of a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlameLine {
+    pub line: usize,
+    pub commit: String,
+    pub author: String,
+    pub time: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BranchInfo {
+    pub name: String,
+    pub is_remote: bool,
+    pub is_head: bool,
+    pub commit: Option<CommitInfo>,
+    pub upstream: Option<String>,
+}
+
+fn git_error(e: git2::Error) -> RepositoryError {
+    RepositoryError::Git(e.message().to_string())
+}
+
+fn timestamp(seconds: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(seconds, 0).unwrap_or_default()
+}
+
 impl GitRepository {
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, RepositoryError> {
         let repo = Repository::open(path).map_err(|e| RepositoryError::Git(e.to_string()))?;
         Ok(Self { repo })
     }
 
-    pub fn get_status(&self) -> Result<String, RepositoryError> {
-        let mut opts = StatusOptions::new();
-        opts.include_untracked(true);
+    /// The repository containing `path`, which may be any directory inside it
+    pub fn discover<P: AsRef<Path>>(path: P) -> Result<Self, RepositoryError> {
+        let repo = Repository::discover(path).map_err(git_error)?;
+        Ok(Self { repo })
+    }
+
+    /// Root of the working tree, or `None` for a bare repository
+    pub fn workdir(&self) -> Option<&Path> {
+        self.repo.workdir()
+    }
 
-        let statuses = self
+    /// Per-file patches between two states, optionally limited to `paths`
+    pub fn diff(
+        &self,
+        target: &DiffTarget,
+        paths: &[String],
+    ) -> Result<Vec<FileDiff>, RepositoryError> {
+        let mut opts = DiffOptions::new();
+        for path in paths {
+            opts.pathspec(path);
+        }
+        let diff = match target {
+            DiffTarget::WorkingTree => self.repo.diff_index_to_workdir(None, Some(&mut opts)),
+            DiffTarget::Staged => {
+                let head = self.head_tree()?;
+                self.repo
+                    .diff_tree_to_index(head.as_ref(), None, Some(&mut opts))
+            }
+            DiffTarget::Revisions { from, to: None } => {
+                let from = self.tree(from)?;
+                self.repo
+                    .diff_tree_to_workdir_with_index(Some(&from), Some(&mut opts))
+            }
+            DiffTarget::Revisions { from, to: Some(to) } => {
+                let (from, to) = (self.tree(from)?, self.tree(to)?);
+                self.repo
+                    .diff_tree_to_tree(Some(&from), Some(&to), Some(&mut opts))
+            }
+        }
+        .map_err(git_error)?;
+        Self::patches(&diff)
+    }
+
+    /// Up to `limit` commits reachable from `rev` (default: HEAD), newest
+    /// first, optionally only those that changed `path`
+    pub fn log(
+        &self,
+        rev: Option<&str>,
+        path: Option<&str>,
+        limit: usize,
+    ) -> Result<Vec<CommitInfo>, RepositoryError> {
+        let mut walk = self.repo.revwalk().map_err(git_error)?;
+        walk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+            .map_err(git_error)?;
+        match rev {
+            Some(rev) => walk.push(self.commit(rev)?.id()),
+            None => walk.push_head(),
+        }
+        .map_err(git_error)?;
+
+        let mut commits = Vec::new();
+        for oid in walk {
+            if commits.len() == limit {
+                break;
+            }
+            let commit = self
+                .repo
+                .find_commit(oid.map_err(git_error)?)
+                .map_err(git_error)?;
+            if let Some(path) = path
+                && Self::patches(&self.commit_diff(&commit, &[path.to_string()])?)?.is_empty()
+            {
+                continue;
+            }
+            commits.push(CommitInfo::from_commit(&commit));
+        }
+        Ok(commits)
+    }
+
+    /// A commit and its changes against its first parent
+    pub fn show(&self, rev: &str) -> Result<(CommitInfo, Vec<FileDiff>), RepositoryError> {
+        let commit = self.commit(rev)?;
+        let diff = self.commit_diff(&commit, &[])?;
+        Ok((CommitInfo::from_commit(&commit), Self::patches(&diff)?))
+    }
+
+    /// Who last changed each line of `path` as committed at HEAD, optionally
+    /// limited to lines `start..=end` (1-based)
+    pub fn blame(
+        &self,
+        path: &Path,
+        start: Option<usize>,
+        end: Option<usize>,
+    ) -> Result<Vec<BlameLine>, RepositoryError> {
+        let mut opts = BlameOptions::new();
+        if let Some(start) = start {
+            opts.min_line(start);
+        }
+        if let Some(end) = end {
+            opts.max_line(end);
+        }
+        let blame = self
             .repo
-            .statuses(Some(&mut opts))
-            .map_err(|e| RepositoryError::Git(e.to_string()))?;
+            .blame_file(path, Some(&mut opts))
+            .map_err(git_error)?;
+
+        let tree = self
+            .head_tree()?
+            .ok_or_else(|| RepositoryError::Git("Repository has no commits".to_string()))?;
+        let blob = tree
+            .get_path(path)
+            .and_then(|entry| entry.to_object(&self.repo))
+            .and_then(|object| object.peel_to_blob())
+            .map_err(git_error)?;
+        let content = String::from_utf8_lossy(blob.content());
+        let lines: Vec<&str> = content.lines().collect();
 
-        let mut output = String::new();
-        for entry in statuses.iter() {
-            let path = entry.path().unwrap_or("unknown");
-            let status = entry.status();
-            output.push_str(&format!("{:?} {}\n", status, path));
+        let mut blamed = Vec::new();
+        for hunk in blame.iter() {
+            let signature = hunk.final_signature();
+            let first = hunk.final_start_line();
+            for line in first..first + hunk.lines_in_hunk() {
+                blamed.push(BlameLine {
+                    line,
+                    commit: hunk.final_commit_id().to_string()[..7].to_string(),
+                    author: signature.name().unwrap_or("unknown").to_string(),
+                    time: timestamp(signature.when().seconds()),
+                    text: lines.get(line - 1).copied().unwrap_or_default().to_string(),
+                });
+            }
         }
+        Ok(blamed)
+    }
 
-        if output.is_empty() {
-            Ok("Clean".to_string())
-        } else {
-            Ok(output)
+    /// Local and remote-tracking branches
+    pub fn branches(&self) -> Result<Vec<BranchInfo>, RepositoryError> {
+        let mut branches = Vec::new();
+        for branch in self.repo.branches(None).map_err(git_error)? {
+            let (branch, kind) = branch.map_err(git_error)?;
+            let Some(name) = branch.name().map_err(git_error)? else {
+                continue;
+            };
+            let upstream = branch
+                .upstream()
+                .ok()
+                .and_then(|upstream| upstream.name().ok().flatten().map(String::from));
+            branches.push(BranchInfo {
+                name: name.to_string(),
+                is_remote: kind == git2::BranchType::Remote,
+                is_head: branch.is_head(),
+                commit: branch
+                    .get()
+                    .peel_to_commit()
+                    .ok()
+                    .map(|commit| CommitInfo::from_commit(&commit)),
+                upstream,
+            });
         }
+        Ok(branches)
+    }
+
+    fn commit(&self, rev: &str) -> Result<Commit<'_>, RepositoryError> {
+        self.repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_commit())
+            .map_err(git_error)
+    }
+
+    fn tree(&self, rev: &str) -> Result<Tree<'_>, RepositoryError> {
+        self.repo
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .map_err(git_error)
+    }
+
+    /// HEAD's tree, or `None` before the first commit
+    fn head_tree(&self) -> Result<Option<Tree<'_>>, RepositoryError> {
+        match self.repo.head() {
+            Ok(head) => head.peel_to_tree().map(Some).map_err(git_error),
+            Err(e) if e.code() == git2::ErrorCode::UnbornBranch => Ok(None),
+            Err(e) => Err(git_error(e)),
+        }
+    }
+
+    /// Changes a commit made on top of its first parent
+    fn commit_diff(&self, commit: &Commit, paths: &[String]) -> Result<Diff<'_>, RepositoryError> {
+        let mut opts = DiffOptions::new();
+        for path in paths {
+            opts.pathspec(path);
+        }
+        let tree = commit.tree().map_err(git_error)?;
+        let parent = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().map_err(git_error)?),
+            Err(_) => None,
+        };
+        self.repo
+            .diff_tree_to_tree(parent.as_ref(), Some(&tree), Some(&mut opts))
+            .map_err(git_error)
+    }
+
+    fn patches(diff: &Diff) -> Result<Vec<FileDiff>, RepositoryError> {
+        let mut files: Vec<FileDiff> = Vec::new();
+        diff.print(DiffFormat::Patch, |delta, _hunk, line| {
+            let path = delta
+                .new_file()
+                .path()
+                .or_else(|| delta.old_file().path())
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            if files.last().is_none_or(|file| file.path != path) {
+                files.push(FileDiff {
+                    path,
+                    patch: String::new(),
+                });
+            }
+            if let Some(file) = files.last_mut() {
+                if matches!(line.origin(), '+' | '-' | ' ') {
+                    file.patch.push(line.origin());
+                }
+                file.patch
+                    .push_str(&String::from_utf8_lossy(line.content()));
+            }
+            true
+        })
+        .map_err(git_error)?;
+        Ok(files)
+    }
+
+    /// Changed and untracked files, optionally limited to `paths`. Files in
+    /// untracked directories are listed one by one.
+    pub fn get_status(&self, paths: &[String]) -> Result<Vec<StatusEntry>, RepositoryError> {
+        let mut opts = StatusOptions::new();
+        opts.include_untracked(true).recurse_untracked_dirs(true);
+        for path in paths {
+            opts.pathspec(path);
+        }
+
+        let statuses = self.repo.statuses(Some(&mut opts)).map_err(git_error)?;
+        Ok(statuses
+            .iter()
+            .map(|entry| StatusEntry {
+                path: entry.path().unwrap_or("unknown").to_string(),
+                code: status_code(entry.status()),
+            })
+            .collect())
     }
 
     pub fn get_diff(&self) -> Result<String, RepositoryError> {
@@ -1,2 +1,4 @@
+pub mod analyzer;
+pub mod cargo;
 pub mod git;
 pub mod scanner;
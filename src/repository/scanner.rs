@@ -5,6 +5,14 @@ use std::path::{Path, PathBuf};
 pub struct RepositoryScanner {
     root: PathBuf,
     ignore_patterns: Vec<String>,
+    max_depth: Option<usize>,
+}
+
+/// A file or directory found by `RepositoryScanner::entries`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub is_dir: bool,
 }
 
 impl RepositoryScanner {
@@ -12,11 +20,29 @@ impl RepositoryScanner {
         Self {
             root: root.as_ref().to_path_buf(),
             ignore_patterns,
+            max_depth: None,
         }
     }
 
+    /// Only descend `depth` levels below the root (1 = direct children)
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
     pub fn scan(&self) -> Result<Vec<PathBuf>, RepositoryError> {
+        Ok(self
+            .entries()?
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path)
+            .collect())
+    }
+
+    /// Files and directories below the root, excluding the root itself
+    pub fn entries(&self) -> Result<Vec<ScanEntry>, RepositoryError> {
         let mut builder = WalkBuilder::new(&self.root);
+        builder.max_depth(self.max_depth);
         builder.hidden(false); // Don't ignore hidden files by default, let ignore patterns handle it
         builder.git_ignore(true); // Respect .gitignore
 
@@ -52,19 +78,30 @@ impl RepositoryScanner {
         builder.overrides(overrides);
 
         let walker = builder.build();
-        let mut files = Vec::new();
+        let mut entries = Vec::new();
 
         for result in walker {
             match result {
                 Ok(entry) => {
-                    if entry.file_type().is_some_and(|ft| ft.is_file()) {
-                        files.push(entry.path().to_path_buf());
+                    if entry.depth() == 0 {
+                        continue;
+                    }
+                    match entry.file_type() {
+                        Some(ft) if ft.is_file() => entries.push(ScanEntry {
+                            path: entry.path().to_path_buf(),
+                            is_dir: false,
+                        }),
+                        Some(ft) if ft.is_dir() => entries.push(ScanEntry {
+                            path: entry.path().to_path_buf(),
+                            is_dir: true,
+                        }),
+                        _ => {}
                     }
                 }
                 Err(err) => return Err(RepositoryError::Scan(err.to_string())),
             }
         }
 
-        Ok(files)
+        Ok(entries)
     }
 }
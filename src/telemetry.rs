@@ -2,13 +2,27 @@ use crate::error::Result;
 use tracing_subscriber::{EnvFilter, fmt};
 
 pub fn init_logging(level: &str) -> Result<()> {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level));
+    fmt()
+        .with_env_filter(filter(level))
+        .with_target(false)
+        .with_file(true)
+        .with_line_number(true)
+        .init();
+    Ok(())
+}
 
+/// Like `init_logging`, but keeps stdout free for command output
+pub fn init_stderr_logging(level: &str) -> Result<()> {
     fmt()
-        .with_env_filter(filter)
+        .with_env_filter(filter(level))
         .with_target(false)
         .with_file(true)
         .with_line_number(true)
+        .with_writer(std::io::stderr)
         .init();
     Ok(())
 }
+
+fn filter(level: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(level))
+}
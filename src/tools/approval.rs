@@ -0,0 +1,208 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::{Mutex, mpsc};
+
+/// A mutating tool call waiting for a decision
+#[derive(Debug, Clone)]
+pub struct ApprovalRequest {
+    pub tool: String,
+    pub arguments: Value,
+    /// Human-readable description of the change, e.g. a diff
+    pub preview: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    Approved { policy: String },
+    Denied { policy: String, reason: String },
+}
+
+impl ApprovalDecision {
+    pub fn is_approved(&self) -> bool {
+        matches!(self, ApprovalDecision::Approved { .. })
+    }
+
+    pub fn policy(&self) -> &str {
+        match self {
+            ApprovalDecision::Approved { policy } | ApprovalDecision::Denied { policy, .. } => {
+                policy
+            }
+        }
+    }
+}
+
+/// Decides whether a mutating tool call may run
+#[async_trait]
+pub trait Approver: Send + Sync {
+    async fn approve(&self, request: &ApprovalRequest) -> ApprovalDecision;
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalMode {
+    /// Ask on the terminal; denies when there is no terminal or `CI` is set
+    #[default]
+    Prompt,
+    /// Approve everything not on the deny list
+    Approve,
+    /// Deny everything not on the approve list
+    Deny,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApprovalConfig {
+    #[serde(default)]
+    pub mode: ApprovalMode,
+    /// Tools that never need confirmation
+    #[serde(default)]
+    pub auto_approve: Vec<String>,
+    /// Tools that are always refused
+    #[serde(default)]
+    pub auto_deny: Vec<String>,
+}
+
+/// Approves every request
+pub struct AutoApprover;
+
+#[async_trait]
+impl Approver for AutoApprover {
+    async fn approve(&self, _request: &ApprovalRequest) -> ApprovalDecision {
+        ApprovalDecision::Approved {
+            policy: "auto_approve".to_string(),
+        }
+    }
+}
+
+/// Denies every request, for unattended runs
+pub struct DenyApprover;
+
+#[async_trait]
+impl Approver for DenyApprover {
+    async fn approve(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        ApprovalDecision::Denied {
+            policy: "deny".to_string(),
+            reason: format!("{} requires approval and none is available", request.tool),
+        }
+    }
+}
+
+/// Asks the user on stdin: `y` approves, `n` denies, `d` shows the preview
+pub struct InteractiveApprover;
+
+#[async_trait]
+impl Approver for InteractiveApprover {
+    async fn approve(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        prompt(request).await
+    }
+}
+
+/// The next line typed on stdin, or `None` at end of input. One thread reads
+/// stdin for the whole process, so a prompt dropped while waiting, such as the
+/// approval of a cancelled turn, leaves the next line to the chat prompt.
+pub async fn read_line() -> io::Result<Option<String>> {
+    static LINES: OnceLock<Mutex<mpsc::UnboundedReceiver<io::Result<String>>>> = OnceLock::new();
+    let lines = LINES.get_or_init(|| {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        std::thread::spawn(move || {
+            loop {
+                let mut line = String::new();
+                match io::stdin().lock().read_line(&mut line) {
+                    Ok(0) => break,
+                    Ok(_) => {
+                        if sender.send(Ok(line)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = sender.send(Err(e));
+                        break;
+                    }
+                }
+            }
+        });
+        Mutex::new(receiver)
+    });
+    lines.lock().await.recv().await.transpose()
+}
+
+async fn prompt(request: &ApprovalRequest) -> ApprovalDecision {
+    let denied = |reason: &str| ApprovalDecision::Denied {
+        policy: "prompt".to_string(),
+        reason: reason.to_string(),
+    };
+
+    println!("Agent wants to run {} {}", request.tool, request.arguments);
+    loop {
+        print!("Allow? [y]es / [n]o / [d]iff: ");
+        if io::stdout().flush().is_err() {
+            return denied("Could not prompt for approval");
+        }
+
+        let answer = match read_line().await {
+            Ok(Some(answer)) => answer,
+            Ok(None) | Err(_) => return denied("No answer given"),
+        };
+
+        match answer.trim().to_lowercase().as_str() {
+            "y" | "yes" => {
+                return ApprovalDecision::Approved {
+                    policy: "prompt".to_string(),
+                };
+            }
+            "n" | "no" => return denied("Denied by user"),
+            "d" | "diff" => match &request.preview {
+                Some(preview) => println!("{}", preview),
+                None => println!("No preview available for {}", request.tool),
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Applies the config allow/deny lists before falling back to the mode's approver
+pub struct PolicyApprover {
+    auto_approve: Vec<String>,
+    auto_deny: Vec<String>,
+    fallback: Arc<dyn Approver>,
+}
+
+impl PolicyApprover {
+    pub fn new(config: &ApprovalConfig) -> Self {
+        let interactive = io::stdin().is_terminal() && std::env::var_os("CI").is_none();
+        let fallback: Arc<dyn Approver> = match config.mode {
+            ApprovalMode::Prompt if interactive => Arc::new(InteractiveApprover),
+            ApprovalMode::Prompt | ApprovalMode::Deny => Arc::new(DenyApprover),
+            ApprovalMode::Approve => Arc::new(AutoApprover),
+        };
+        Self::with_fallback(config, fallback)
+    }
+
+    pub fn with_fallback(config: &ApprovalConfig, fallback: Arc<dyn Approver>) -> Self {
+        Self {
+            auto_approve: config.auto_approve.clone(),
+            auto_deny: config.auto_deny.clone(),
+            fallback,
+        }
+    }
+}
+
+#[async_trait]
+impl Approver for PolicyApprover {
+    async fn approve(&self, request: &ApprovalRequest) -> ApprovalDecision {
+        if self.auto_deny.contains(&request.tool) {
+            return ApprovalDecision::Denied {
+                policy: "auto_deny".to_string(),
+                reason: format!("{} is on the auto_deny list", request.tool),
+            };
+        }
+        if self.auto_approve.contains(&request.tool) {
+            return ApprovalDecision::Approved {
+                policy: "auto_approve".to_string(),
+            };
+        }
+        self.fallback.approve(request).await
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    /// Write a log of every tool call for each command run
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Where logs go; defaults to the `audit` directory next to saved sessions
+    #[serde(default)]
+    pub dir: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+        }
+    }
+}
+
+/// How a tool call ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+    /// Refused by the approval policy
+    Denied,
+    /// Arguments did not match the tool's schema
+    Invalid,
+    /// A mutating tool skipped because of `--dry-run`
+    DryRun,
+}
+
+/// One line of the audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub call_id: String,
+    pub tool: String,
+    pub arguments: Value,
+    pub outcome: AuditOutcome,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u64,
+    /// Policy that approved or denied the call; unset for tools that need no approval
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<String>,
+}
+
+/// Append-only JSONL log of tool calls, one file per run
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Open `path` for appending, creating it and its directory
+    pub fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    /// A new log for one run of `command` (e.g. `chat`), or `None` when
+    /// auditing is disabled
+    pub fn for_run(config: &AuditConfig, command: &str) -> io::Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let dir = match &config.dir {
+            Some(dir) => PathBuf::from(dir),
+            None => ProjectDirs::from("", "", "xzardgz")
+                .ok_or_else(|| io::Error::other("Could not determine data directory"))?
+                .data_dir()
+                .join("audit"),
+        };
+        let name = format!(
+            "{}-{}-{}.jsonl",
+            Utc::now().format("%Y%m%dT%H%M%S%.3f"),
+            command,
+            std::process::id()
+        );
+        Self::create(dir.join(name)).map(Some)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append `entry`. A log that cannot be written is reported, but does not
+    /// stop the tool call.
+    pub fn record(&self, entry: &AuditEntry) {
+        let written = serde_json::to_string(entry)
+            .map_err(io::Error::other)
+            .and_then(|line| {
+                let mut file = self
+                    .file
+                    .lock()
+                    .map_err(|_| io::Error::other("audit log lock poisoned"))?;
+                writeln!(file, "{}", line)
+            });
+        if let Err(e) = written {
+            tracing::warn!("Failed to write audit log {:?}: {}", self.path, e);
+        }
+    }
+
+    /// Every entry written so far
+    pub fn entries(&self) -> io::Result<Vec<AuditEntry>> {
+        fs::read_to_string(&self.path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(io::Error::other))
+            .collect()
+    }
+}
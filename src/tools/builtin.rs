@@ -0,0 +1,140 @@
+use crate::docgen::templates::TemplateRegistry;
+use crate::error::XzardgzError;
+use crate::tools::code_ops::{
+    CargoMetadataTool, FindDefinitionTool, FindReferencesTool, ListSymbolsTool,
+};
+use crate::tools::command_ops::{CommandRunner, RunCommandTool};
+use crate::tools::doc_ops::{
+    ListDocsTool, ReadDocTool, RenderTemplateTool, SearchDocsTool, WriteDocTool,
+};
+use crate::tools::edit_ops::{ApplyPatchTool, EditFileTool, InsertLinesTool};
+use crate::tools::file_ops::{ReadFileTool, WriteFileTool};
+use crate::tools::git_ops::{
+    GitBlameTool, GitBranchesTool, GitDiffTool, GitLogTool, GitShowTool, GitStatusTool,
+};
+use crate::tools::registry::ToolRegistry;
+use crate::tools::search_ops::{GlobFilesTool, GrepTool, ListDirectoryTool};
+use crate::tools::workspace::Workspace;
+use std::sync::Arc;
+
+/// The file, edit, git, search, code, command and documentation tools, all bound
+/// to `workspace`
+pub fn workspace_tools(
+    workspace: &Arc<Workspace>,
+    commands: &Arc<CommandRunner>,
+    ignore_patterns: &[String],
+) -> Result<ToolRegistry, XzardgzError> {
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        EditFileTool::definition(),
+        Arc::new(EditFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        InsertLinesTool::definition(),
+        Arc::new(InsertLinesTool::new(workspace.clone())),
+    );
+    registry.register(
+        ApplyPatchTool::definition(),
+        Arc::new(ApplyPatchTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitStatusTool::definition(),
+        Arc::new(GitStatusTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitDiffTool::definition(),
+        Arc::new(GitDiffTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitLogTool::definition(),
+        Arc::new(GitLogTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitShowTool::definition(),
+        Arc::new(GitShowTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBlameTool::definition(),
+        Arc::new(GitBlameTool::new(workspace.clone())),
+    );
+    registry.register(
+        GitBranchesTool::definition(),
+        Arc::new(GitBranchesTool::new(workspace.clone())),
+    );
+    registry.register(
+        RunCommandTool::definition(),
+        Arc::new(RunCommandTool::new(commands.clone())),
+    );
+    registry.register(
+        ListDirectoryTool::definition(),
+        Arc::new(ListDirectoryTool::new(
+            workspace.clone(),
+            ignore_patterns.to_vec(),
+        )),
+    );
+    registry.register(
+        GlobFilesTool::definition(),
+        Arc::new(GlobFilesTool::new(
+            workspace.clone(),
+            ignore_patterns.to_vec(),
+        )),
+    );
+    registry.register(
+        GrepTool::definition(),
+        Arc::new(GrepTool::new(workspace.clone(), ignore_patterns.to_vec())),
+    );
+    registry.register(
+        ListSymbolsTool::definition(),
+        Arc::new(ListSymbolsTool::new(
+            workspace.clone(),
+            ignore_patterns.to_vec(),
+        )),
+    );
+    registry.register(
+        FindDefinitionTool::definition(),
+        Arc::new(FindDefinitionTool::new(
+            workspace.clone(),
+            ignore_patterns.to_vec(),
+        )),
+    );
+    registry.register(
+        FindReferencesTool::definition(),
+        Arc::new(FindReferencesTool::new(
+            workspace.clone(),
+            ignore_patterns.to_vec(),
+        )),
+    );
+    registry.register(
+        CargoMetadataTool::definition(),
+        Arc::new(CargoMetadataTool::new(workspace.clone())),
+    );
+    registry.register(
+        ListDocsTool::definition(),
+        Arc::new(ListDocsTool::new(workspace.clone())),
+    );
+    registry.register(
+        ReadDocTool::definition(),
+        Arc::new(ReadDocTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteDocTool::definition(),
+        Arc::new(WriteDocTool::new(workspace.clone())),
+    );
+    registry.register(
+        RenderTemplateTool::definition(),
+        Arc::new(RenderTemplateTool::new(Arc::new(TemplateRegistry::new()?))),
+    );
+    registry.register(
+        SearchDocsTool::definition(),
+        Arc::new(SearchDocsTool::new(workspace.clone())),
+    );
+    Ok(registry)
+}
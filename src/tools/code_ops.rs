@@ -0,0 +1,404 @@
+use crate::error::XzardgzError;
+use crate::providers::types::Tool;
+use crate::repository::analyzer::{self, Symbol, SymbolKind};
+use crate::repository::cargo::CargoProject;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::search_ops::{elide, walk};
+use crate::tools::workspace::Workspace;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Output lines listed before the rest are elided
+const MAX_LISTED_LINES: usize = 400;
+const MAX_DEFINITIONS: usize = 20;
+const DEFAULT_REFERENCES: u64 = 100;
+const MAX_REFERENCES: u64 = 500;
+
+/// The `.rs` files at or below `path`, found the way `RepositoryScanner` does
+fn rust_files(
+    workspace: &Workspace,
+    ignore_patterns: &[String],
+    path: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let resolved = workspace
+        .resolve_read(path)
+        .map_err(|violation| violation.to_string())?;
+    if resolved.is_file() {
+        if resolved
+            .extension()
+            .is_none_or(|extension| extension != "rs")
+        {
+            return Err(format!("Not a Rust source file: {}", path));
+        }
+        return Ok(vec![resolved]);
+    }
+    Ok(walk(workspace, ignore_patterns, path, None)?
+        .into_iter()
+        .filter(|entry| !entry.is_dir && entry.path.extension().is_some_and(|e| e == "rs"))
+        .map(|entry| entry.path)
+        .collect())
+}
+
+/// The symbols of `file`, or why it could not be read
+fn file_symbols(workspace: &Workspace, file: &Path) -> Result<Vec<Symbol>, String> {
+    let relative = workspace.relative(file);
+    let source = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", relative, e))?;
+    analyzer::parse_symbols(&source, &analyzer::module_path(Path::new(&relative)))
+        .map_err(|e| format!("{}: {}", relative, e))
+}
+
+/// The first paragraph of a doc comment, on one line
+fn summary(docs: &str) -> String {
+    docs.split("\n\n")
+        .next()
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListSymbolsArgs {
+    /// Rust file or directory, relative to the workspace root (default: the root)
+    pub path: Option<String>,
+    /// Also list items that are not `pub` (default: false)
+    #[serde(default)]
+    pub include_private: bool,
+}
+
+pub struct ListSymbolsTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+impl ListSymbolsTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "list_symbols".to_string(),
+            description: "List the functions, types, traits, impl blocks and modules declared \
+                in Rust files, with their signatures, line numbers and the first paragraph \
+                of their doc comments. Only public items are listed unless include_private is set."
+                .to_string(),
+            parameters: schema_for::<ListSymbolsArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ListSymbolsTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: ListSymbolsArgs = parse_args(params)?;
+        let path = args.path.as_deref().unwrap_or(".");
+        let files = match rust_files(&self.workspace, &self.ignore_patterns, path) {
+            Ok(files) => files,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let mut lines = Vec::new();
+        for file in files {
+            let symbols = match file_symbols(&self.workspace, &file) {
+                Ok(symbols) => symbols,
+                Err(e) => {
+                    lines.push(format!("{} (skipped)", e));
+                    continue;
+                }
+            };
+            let shown: Vec<&Symbol> = symbols
+                .iter()
+                .filter(|symbol| args.include_private || symbol.public)
+                .collect();
+            let Some(first) = shown.first() else {
+                continue;
+            };
+            lines.push(format!(
+                "{} ({})",
+                self.workspace.relative(&file),
+                first.module
+            ));
+            for symbol in shown {
+                let indent = if symbol.parent.is_some() {
+                    "    "
+                } else {
+                    "  "
+                };
+                lines.push(format!("{}{}: {}", indent, symbol.line, symbol.signature));
+                if let Some(docs) = &symbol.docs {
+                    lines.push(format!("{}    {}", indent, summary(docs)));
+                }
+            }
+        }
+
+        if lines.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No Rust items found in {}",
+                path
+            )));
+        }
+        Ok(ToolResult::success(elide(lines, MAX_LISTED_LINES)))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindDefinitionArgs {
+    /// Item name, optionally qualified: `validate`, `Workspace::resolve_read` or
+    /// `crate::tools::schema::validate`
+    pub name: String,
+    /// Rust file or directory to search, relative to the workspace root (default: the root)
+    pub path: Option<String>,
+}
+
+pub struct FindDefinitionTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+impl FindDefinitionTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "find_definition".to_string(),
+            description: "Find where a Rust item is declared and show its doc comment and \
+                declaration: the full definition of types and constants, the signature of \
+                functions and traits."
+                .to_string(),
+            parameters: schema_for::<FindDefinitionArgs>(),
+        }
+    }
+}
+
+/// Whether `name` names `symbol`, by its own name or any suffix of its path
+fn names(symbol: &Symbol, name: &str) -> bool {
+    let path = format!("{}::{}", symbol.module, symbol.name);
+    path == name || path.ends_with(&format!("::{}", name))
+}
+
+#[async_trait]
+impl ToolExecutor for FindDefinitionTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: FindDefinitionArgs = parse_args(params)?;
+        let name = args.name.trim();
+        let path = args.path.as_deref().unwrap_or(".");
+        let files = match rust_files(&self.workspace, &self.ignore_patterns, path) {
+            Ok(files) => files,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let mut found = Vec::new();
+        for file in files {
+            // Files that do not parse cannot hold the definition in a usable form
+            let Ok(symbols) = file_symbols(&self.workspace, &file) else {
+                continue;
+            };
+            for symbol in symbols {
+                // An impl block is not a definition of its type
+                if symbol.kind != SymbolKind::Impl && names(&symbol, name) {
+                    found.push((self.workspace.relative(&file), symbol));
+                }
+            }
+        }
+
+        if found.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No definition of {} found",
+                name
+            )));
+        }
+        let total = found.len();
+        let mut blocks: Vec<String> = found
+            .into_iter()
+            .take(MAX_DEFINITIONS)
+            .map(|(file, symbol)| {
+                let mut block = match &symbol.parent {
+                    Some(parent) => format!(
+                        "{}:{} ({}, in {})\n",
+                        file, symbol.line, symbol.module, parent
+                    ),
+                    None => format!("{}:{} ({})\n", file, symbol.line, symbol.module),
+                };
+                for line in symbol.docs.iter().flat_map(|docs| docs.lines()) {
+                    if line.is_empty() {
+                        block.push_str("///\n");
+                    } else {
+                        block.push_str(&format!("/// {}\n", line));
+                    }
+                }
+                block.push_str(&symbol.definition);
+                block
+            })
+            .collect();
+        if total > MAX_DEFINITIONS {
+            blocks.push(format!("... and {} more", total - MAX_DEFINITIONS));
+        }
+        Ok(ToolResult::success(blocks.join("\n\n")))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FindReferencesArgs {
+    /// Identifier to look for; for a path such as `Workspace::resolve_read` only
+    /// the last segment is matched
+    pub name: String,
+    /// Rust file or directory to search, relative to the workspace root (default: the root)
+    pub path: Option<String>,
+    /// Maximum references to return (default: 100, max: 500)
+    pub max_results: Option<u64>,
+}
+
+pub struct FindReferencesTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+impl FindReferencesTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "find_references".to_string(),
+            description: "Find where an identifier is used in Rust code, skipping comments and \
+                strings. Matches are by name only, shown as path:line:column: text."
+                .to_string(),
+            parameters: schema_for::<FindReferencesArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for FindReferencesTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: FindReferencesArgs = parse_args(params)?;
+        let name = args.name.trim();
+        let ident = name.rsplit("::").next().unwrap_or(name);
+        let max_results = args
+            .max_results
+            .unwrap_or(DEFAULT_REFERENCES)
+            .clamp(1, MAX_REFERENCES) as usize;
+        let path = args.path.as_deref().unwrap_or(".");
+        let files = match rust_files(&self.workspace, &self.ignore_patterns, path) {
+            Ok(files) => files,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let mut lines = Vec::new();
+        let mut found = 0;
+        'files: for file in files {
+            let Ok(source) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            let relative = self.workspace.relative(&file);
+            let references = match analyzer::find_references(&source, ident) {
+                Ok(references) => references,
+                Err(e) => {
+                    lines.push(format!("{}: {} (skipped)", relative, e));
+                    continue;
+                }
+            };
+            let text: Vec<&str> = source.lines().collect();
+            for reference in references {
+                if found == max_results {
+                    lines.push(format!("(stopped after {} references)", max_results));
+                    break 'files;
+                }
+                found += 1;
+                lines.push(format!(
+                    "{}:{}:{}: {}",
+                    relative,
+                    reference.line,
+                    reference.column,
+                    text.get(reference.line - 1).map_or("", |line| line.trim())
+                ));
+            }
+        }
+
+        if found == 0 {
+            lines.push(format!("No references to {}", ident));
+        }
+        Ok(ToolResult::success(lines.join("\n")))
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct CargoMetadataArgs {
+    /// Directory holding Cargo.toml, relative to the workspace root (default: the root)
+    pub path: Option<String>,
+    /// Only report the workspace member with this name
+    pub package: Option<String>,
+}
+
+pub struct CargoMetadataTool {
+    workspace: Arc<Workspace>,
+}
+
+impl CargoMetadataTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "cargo_metadata".to_string(),
+            description: "Describe a Cargo project from its Cargo.toml files and Cargo.lock: \
+                workspace members, and for each package its version, edition, targets \
+                (library, binaries, examples, tests, benches), features and dependencies \
+                with requirements and locked versions, as JSON."
+                .to_string(),
+            parameters: schema_for::<CargoMetadataArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for CargoMetadataTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: CargoMetadataArgs = parse_args(params)?;
+        let path = args.path.as_deref().unwrap_or(".");
+        let dir = match self.workspace.resolve_read(path) {
+            Ok(dir) => dir,
+            Err(violation) => return Ok(ToolResult::failure(violation.to_string())),
+        };
+        let manifest = dir.join("Cargo.toml");
+        if !manifest.is_file() || !self.workspace.is_readable(&manifest) {
+            return Ok(ToolResult::failure(format!(
+                "No readable Cargo.toml in {}",
+                path
+            )));
+        }
+
+        let mut project = match CargoProject::load(&dir) {
+            Ok(project) => project,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+        if let Some(name) = &args.package {
+            project.packages.retain(|package| &package.name == name);
+            if project.packages.is_empty() {
+                return Ok(ToolResult::failure(format!("No package named {}", name)));
+            }
+        }
+        Ok(ToolResult::success(
+            serde_json::to_string_pretty(&project).unwrap_or_default(),
+        ))
+    }
+}
@@ -0,0 +1,761 @@
+use crate::error::{CommandError, XzardgzError};
+use crate::providers::types::Tool;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::process::{Child, Command};
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// How long to keep reading output after the command's processes were killed
+const PIPE_GRACE: Duration = Duration::from_secs(1);
+
+/// Characters that only mean something to a shell
+const SHELL_SYNTAX: &[char] = &['|', '&', ';', '<', '>', '(', ')', '$', '`', '\n'];
+
+/// Characters that start a shell operator such as `&&` or `2>`
+const SHELL_OPERATORS: &[char] = &['|', '&', ';', '<', '>', '(', ')', '\n'];
+
+/// Shell words that precede a command without running anything themselves
+const RESERVED_WORDS: &[&str] = &[
+    "!", "{", "}", "if", "then", "else", "elif", "fi", "while", "until", "do", "done", "esac",
+    "in", "coproc",
+];
+
+/// Programs that run the command in their arguments
+const WRAPPERS: &[&str] = &["env", "exec", "command", "nohup", "xargs", "builtin"];
+
+/// Options that make a program run other programs or change files, which the
+/// command policy could not check
+const UNCHECKED_OPTIONS: &[(&str, &[&str])] = &[
+    (
+        "find",
+        &[
+            "-exec", "-execdir", "-ok", "-okdir", "-delete", "-fls", "-fprint", "-fprint0",
+            "-fprintf",
+        ],
+    ),
+    ("rg", &["--pre", "--pre-glob"]),
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandConfig {
+    /// Programs that may run; empty allows any program not denied
+    #[serde(default = "default_allow")]
+    pub allow: Vec<String>,
+    /// Programs that may never run, even through a shell
+    #[serde(default = "default_deny")]
+    pub deny: Vec<String>,
+    /// Let commands run through `sh -c`, with pipes, redirects and variables
+    #[serde(default)]
+    pub allow_shell: bool,
+    /// Longest a command may run; the tool may ask for less
+    #[serde(default = "default_timeout_seconds")]
+    pub timeout_seconds: u64,
+    /// Bytes kept from each of stdout and stderr
+    #[serde(default = "default_max_output_bytes")]
+    pub max_output_bytes: usize,
+    /// Environment variables passed through; every other variable is removed
+    #[serde(default = "default_env")]
+    pub env: Vec<String>,
+}
+
+fn default_allow() -> Vec<String> {
+    ["cargo", "git", "ls", "wc", "echo", "pwd"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_deny() -> Vec<String> {
+    [
+        "sudo", "su", "rm", "dd", "mkfs", "shutdown", "reboot", "curl", "wget", "ssh", "scp",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+fn default_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_max_output_bytes() -> usize {
+    32 * 1024
+}
+
+fn default_env() -> Vec<String> {
+    [
+        "PATH",
+        "HOME",
+        "USER",
+        "LANG",
+        "LC_ALL",
+        "TERM",
+        "TMPDIR",
+        "CARGO_HOME",
+        "RUSTUP_HOME",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+impl Default for CommandConfig {
+    fn default() -> Self {
+        Self {
+            allow: default_allow(),
+            deny: default_deny(),
+            allow_shell: false,
+            timeout_seconds: default_timeout_seconds(),
+            max_output_bytes: default_max_output_bytes(),
+            env: default_env(),
+        }
+    }
+}
+
+/// A program and its arguments, checked against the policy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandSpec {
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CommandSpec {
+    /// The command as it would be typed, for logs and approval prompts
+    pub fn display(&self) -> String {
+        std::iter::once(self.program.as_str())
+            .chain(self.args.iter().map(String::as_str))
+            .map(|word| {
+                if word.is_empty() || word.contains(char::is_whitespace) {
+                    format!("'{}'", word)
+                } else {
+                    word.to_string()
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// What a finished (or timed out) command produced
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandOutput {
+    /// `None` when the command was killed, e.g. after timing out
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub timed_out: bool,
+    /// Whether stdout or stderr went over `max_output_bytes`, or was cut
+    /// off because processes the command started outlived the timeout
+    pub truncated: bool,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.exit_code == Some(0) && !self.timed_out
+    }
+}
+
+/// Runs commands in the workspace under a `CommandConfig`. Shared by the
+/// `run_command` tool and `execute_command` workflow steps.
+pub struct CommandRunner {
+    workspace: Arc<Workspace>,
+    config: CommandConfig,
+}
+
+impl CommandRunner {
+    pub fn new(workspace: Arc<Workspace>, config: CommandConfig) -> Self {
+        Self { workspace, config }
+    }
+
+    pub fn config(&self) -> &CommandConfig {
+        &self.config
+    }
+
+    /// Split `command` into words, honouring quotes. With `shell`, the whole
+    /// line is instead handed to `sh -c` and every program in it is checked,
+    /// including those run through wrappers such as `env`; redirects to or
+    /// from files are refused.
+    pub fn parse(&self, command: &str, shell: bool) -> Result<CommandSpec, CommandError> {
+        if shell {
+            if !self.config.allow_shell {
+                return Err(CommandError::Syntax(
+                    "Shell commands are disabled (commands.allow_shell)".to_string(),
+                ));
+            }
+            if command.contains('`') || command.contains("$(") {
+                return Err(CommandError::Syntax(
+                    "Command substitution is not allowed".to_string(),
+                ));
+            }
+            let mut commands = Vec::new();
+            let mut words = Vec::new();
+            let mut tokens = tokenize(command, true)?.into_iter();
+            while let Some(token) = tokens.next() {
+                match token {
+                    Token::Word(word) => words.push(word),
+                    Token::Operator(operator) if operator.contains(['<', '>']) => {
+                        let Some(Token::Word(target)) = tokens.next() else {
+                            return Err(CommandError::Syntax(format!(
+                                "`{}` needs a target",
+                                operator
+                            )));
+                        };
+                        check_redirect(&operator, &target)?;
+                    }
+                    Token::Operator(_) => commands.push(std::mem::take(&mut words)),
+                }
+            }
+            commands.push(words);
+            if commands.iter().all(Vec::is_empty) {
+                return Err(CommandError::Empty);
+            }
+            for words in &commands {
+                self.check_shell_command(words)?;
+            }
+            return Ok(CommandSpec {
+                program: "sh".to_string(),
+                args: vec!["-c".to_string(), command.to_string()],
+            });
+        }
+
+        let mut words = tokenize(command, false)?
+            .into_iter()
+            .filter_map(|token| match token {
+                Token::Word(word) => Some(word),
+                Token::Operator(_) => None,
+            });
+        let program = words.next().ok_or(CommandError::Empty)?;
+        self.spec(program, words.collect())
+    }
+
+    /// A program with arguments passed exactly as given
+    pub fn spec(&self, program: String, args: Vec<String>) -> Result<CommandSpec, CommandError> {
+        self.check_program(&program)?;
+        check_options(&program, args.iter().map(String::as_str))?;
+        Ok(CommandSpec { program, args })
+    }
+
+    /// Check every program one command of a shell line would run, looking
+    /// past variable assignments, reserved words and wrappers like `env`
+    fn check_shell_command(&self, words: &[String]) -> Result<(), CommandError> {
+        let mut wrapper = None;
+        let mut words = words.iter().map(String::as_str);
+        while let Some(word) = words.next() {
+            if is_assignment(word) {
+                continue;
+            }
+            if let Some(wrapper) = wrapper
+                && word.starts_with('-')
+            {
+                return Err(CommandError::Syntax(format!(
+                    "Options of `{}` are not allowed in shell commands",
+                    wrapper
+                )));
+            }
+            match word {
+                // The rest is a word list or a test, not a command
+                "for" | "case" | "select" | "[[" => return Ok(()),
+                "function" => {
+                    words.next();
+                }
+                "time" => wrapper = Some(word),
+                "eval" | "source" | "." => {
+                    return Err(CommandError::Syntax(format!(
+                        "`{}` runs code that cannot be checked",
+                        word
+                    )));
+                }
+                word if RESERVED_WORDS.contains(&word) => {}
+                program => {
+                    if program != "[" && program.contains(['$', '*', '?', '[', '{', '}']) {
+                        return Err(CommandError::Syntax(format!(
+                            "Programs in shell commands must be named literally, not `{}`",
+                            program
+                        )));
+                    }
+                    self.check_program(program)?;
+                    if !WRAPPERS.contains(&program_name(program)) {
+                        return check_options(program, words);
+                    }
+                    wrapper = Some(program);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_program(&self, program: &str) -> Result<(), CommandError> {
+        let name = program_name(program);
+        if self.config.deny.iter().any(|denied| denied == name) {
+            return Err(CommandError::Denied(name.to_string()));
+        }
+        if !self.config.allow.is_empty() && !self.config.allow.iter().any(|allowed| allowed == name)
+        {
+            return Err(CommandError::NotAllowed(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Directory to run in: the workspace root, or `cwd` inside it
+    pub fn working_dir(&self, cwd: Option<&str>) -> Result<PathBuf, CommandError> {
+        let Some(cwd) = cwd else {
+            return Ok(self.workspace.root().to_path_buf());
+        };
+        let dir = self.workspace.resolve_read(cwd)?;
+        if !dir.is_dir() {
+            return Err(CommandError::NotADirectory(cwd.to_string()));
+        }
+        Ok(dir)
+    }
+
+    /// Run `spec` in `dir` with a scrubbed environment, killing it after
+    /// `timeout` (never longer than the configured limit)
+    pub async fn run(
+        &self,
+        spec: &CommandSpec,
+        dir: &Path,
+        timeout: Option<Duration>,
+    ) -> Result<CommandOutput, CommandError> {
+        self.run_with_input(spec, dir, timeout, None).await
+    }
+
+    /// Like `run`, but writes `input` to the command's stdin instead of
+    /// leaving it closed
+    pub async fn run_with_input(
+        &self,
+        spec: &CommandSpec,
+        dir: &Path,
+        timeout: Option<Duration>,
+        input: Option<&str>,
+    ) -> Result<CommandOutput, CommandError> {
+        let limit = Duration::from_secs(self.config.timeout_seconds);
+        let timeout = timeout.map_or(limit, |timeout| timeout.min(limit));
+
+        let mut command = Command::new(&spec.program);
+        command
+            .args(&spec.args)
+            .current_dir(dir)
+            .env_clear()
+            .envs(
+                self.config
+                    .env
+                    .iter()
+                    .filter_map(|name| std::env::var(name).ok().map(|value| (name, value))),
+            )
+            .stdin(if input.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        // Its own process group, so a timeout also reaches whatever it started
+        #[cfg(unix)]
+        command.process_group(0);
+        let mut child = command.spawn().map_err(|e| CommandError::Spawn {
+            program: spec.program.clone(),
+            reason: e.to_string(),
+        })?;
+        // Kept, since `id()` is gone once the command has been waited for
+        let pid = child.id();
+
+        // Written from a task so a command that never reads cannot block us
+        if let (Some(mut stdin), Some(input)) = (child.stdin.take(), input) {
+            let input = input.as_bytes().to_vec();
+            tokio::spawn(async move {
+                let _ = stdin.write_all(&input).await;
+            });
+        }
+
+        let max = self.config.max_output_bytes;
+        let stop = CancellationToken::new();
+        let stdout = tokio::spawn(read_capped(child.stdout.take(), max, stop.clone()));
+        let stderr = tokio::spawn(read_capped(child.stderr.take(), max, stop.clone()));
+
+        let deadline = Instant::now() + timeout;
+        let (exit_code, timed_out) = match tokio::time::timeout_at(deadline, child.wait()).await {
+            Ok(Ok(status)) => (status.code(), false),
+            Ok(Err(e)) => {
+                return Err(CommandError::Spawn {
+                    program: spec.program.clone(),
+                    reason: e.to_string(),
+                });
+            }
+            Err(_) => {
+                kill_process_group(&mut child, pid).await;
+                (None, true)
+            }
+        };
+
+        // Processes the command left running, e.g. `sleep 100 &`, keep the
+        // pipes open; they get until the deadline, then are killed too
+        let mut readers = Box::pin(async { tokio::join!(stdout, stderr) });
+        let wait = deadline.saturating_duration_since(Instant::now());
+        let mut cut_off = false;
+        let (stdout, stderr) = match tokio::time::timeout(wait.max(PIPE_GRACE), &mut readers).await
+        {
+            Ok(output) => output,
+            Err(_) => {
+                kill_process_group(&mut child, pid).await;
+                cut_off = true;
+                tokio::select! {
+                    output = &mut readers => output,
+                    // Something outside the group still holds the pipes
+                    _ = tokio::time::sleep(PIPE_GRACE) => {
+                        stop.cancel();
+                        readers.await
+                    }
+                }
+            }
+        };
+        let (stdout, stdout_truncated) = stdout.unwrap_or_default();
+        let (stderr, stderr_truncated) = stderr.unwrap_or_default();
+        Ok(CommandOutput {
+            exit_code,
+            stdout,
+            stderr,
+            timed_out,
+            truncated: stdout_truncated || stderr_truncated || cut_off,
+        })
+    }
+}
+
+/// Kill `child` and, on Unix, every process left in its group `pid`
+async fn kill_process_group(child: &mut Child, pid: Option<u32>) {
+    #[cfg(unix)]
+    if let Some(pid) = pid.and_then(|pid| i32::try_from(pid).ok()) {
+        // SAFETY: kill(2) takes no pointers; a negative pid names the group
+        // that `process_group(0)` created for the child
+        unsafe {
+            libc::kill(-pid, libc::SIGKILL);
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = pid;
+    let _ = child.kill().await;
+}
+
+/// Read a pipe to the end, or until `stop`, keeping the first `max` bytes
+async fn read_capped<R: AsyncRead + Unpin>(
+    pipe: Option<R>,
+    max: usize,
+    stop: CancellationToken,
+) -> (String, bool) {
+    let Some(mut pipe) = pipe else {
+        return (String::new(), false);
+    };
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let read = tokio::select! {
+            read = pipe.read(&mut buffer) => read,
+            _ = stop.cancelled() => break,
+        };
+        match read {
+            Ok(0) | Err(_) => break,
+            Ok(read) => {
+                let room = max.saturating_sub(kept.len());
+                kept.extend_from_slice(&buffer[..read.min(room)]);
+                truncated |= read > room;
+            }
+        }
+    }
+    (String::from_utf8_lossy(&kept).into_owned(), truncated)
+}
+
+/// A word, or in a shell line an unquoted operator such as `|`, `&&` or `2>`
+#[derive(Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Operator(String),
+}
+
+/// Split a command line into tokens. Single quotes are literal, double quotes
+/// allow `\"` and `\\`, and a backslash outside quotes escapes the next
+/// character. Unless `in_shell`, unquoted shell syntax is refused rather than
+/// passed on literally, so only words come back.
+fn tokenize(line: &str, in_shell: bool) -> Result<Vec<Token>, CommandError> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated('\'')),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                quoted = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(unterminated('"')),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(unterminated('"')),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                quoted = true;
+                if let Some(c) = chars.next() {
+                    word.push(c);
+                }
+            }
+            c if in_shell && SHELL_OPERATORS.contains(&c) => {
+                // The digits of `2>` name a file descriptor, not a word
+                let mut operator = String::new();
+                if matches!(c, '<' | '>')
+                    && in_word
+                    && !quoted
+                    && word.chars().all(|c| c.is_ascii_digit())
+                {
+                    operator = std::mem::take(&mut word);
+                    in_word = false;
+                }
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                    quoted = false;
+                }
+                operator.push(c);
+                let mut take = |next: &[char]| match chars.peek() {
+                    Some(peeked) if next.contains(peeked) => {
+                        operator.push(*peeked);
+                        chars.next();
+                        true
+                    }
+                    _ => false,
+                };
+                match c {
+                    '|' => {
+                        take(&['|', '&']);
+                    }
+                    '&' => {
+                        if take(&['>']) {
+                            take(&['>']);
+                        } else {
+                            take(&['&']);
+                        }
+                    }
+                    ';' => {
+                        take(&[';', '&']);
+                    }
+                    '<' => {
+                        if take(&['<']) {
+                            take(&['<', '-']);
+                        } else {
+                            take(&['>', '&']);
+                        }
+                    }
+                    '>' => {
+                        take(&['>', '|', '&']);
+                    }
+                    _ => {}
+                }
+                tokens.push(Token::Operator(operator));
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            c if SHELL_SYNTAX.contains(&c) && !in_shell => {
+                return Err(CommandError::Syntax(format!(
+                    "`{}` is shell syntax; quote it, or run the command with shell: true \
+                     (requires commands.allow_shell)",
+                    c
+                )));
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+    Ok(tokens)
+}
+
+/// Only descriptor duplication like `2>&1`, here-strings and `/dev/null` are
+/// allowed, since redirects would otherwise bypass the workspace policy
+fn check_redirect(operator: &str, target: &str) -> Result<(), CommandError> {
+    if operator.ends_with("<<<") || target == "/dev/null" {
+        return Ok(());
+    }
+    if operator.ends_with("<<") || operator.ends_with("<<-") {
+        return Err(CommandError::Syntax(
+            "Here-documents are not allowed in shell commands".to_string(),
+        ));
+    }
+    let duplicates =
+        operator.ends_with('&') && (target == "-" || target.chars().all(|c| c.is_ascii_digit()));
+    if duplicates {
+        return Ok(());
+    }
+    Err(CommandError::Syntax(format!(
+        "Redirects to or from files (`{}{}`) are not allowed in shell commands; \
+         use the file tools instead",
+        operator, target
+    )))
+}
+
+/// Refuse the options in `UNCHECKED_OPTIONS`, given alone or as `--opt=value`
+fn check_options<'a>(
+    program: &str,
+    args: impl IntoIterator<Item = &'a str>,
+) -> Result<(), CommandError> {
+    let name = program_name(program);
+    let Some((_, options)) = UNCHECKED_OPTIONS
+        .iter()
+        .find(|(program, _)| *program == name)
+    else {
+        return Ok(());
+    };
+    for arg in args {
+        let option = arg.split_once('=').map_or(arg, |(option, _)| option);
+        if options.contains(&option) {
+            return Err(CommandError::Syntax(format!(
+                "`{}` of `{}` is not allowed, since it runs programs or changes files \
+                 outside the command policy",
+                option, name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// `NAME=value`, which the shell takes as an assignment rather than a program
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        let name = name.strip_suffix('+').unwrap_or(name);
+        name.chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+/// `/bin/rm` is still `rm`
+fn program_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(program)
+}
+
+fn unterminated(quote: char) -> CommandError {
+    CommandError::Syntax(format!("Unterminated {} quote", quote))
+}
+
+pub struct RunCommandTool {
+    runner: Arc<CommandRunner>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RunCommandArgs {
+    /// Command line, e.g. cargo test --lib; or only the program when args is given
+    pub command: String,
+    /// Arguments passed exactly as given, without any parsing
+    pub args: Option<Vec<String>>,
+    /// Directory to run in, relative to the workspace root
+    pub cwd: Option<String>,
+    /// Stop the command after this long (capped by the configuration)
+    pub timeout_seconds: Option<u64>,
+    /// Run through sh -c to use pipes and redirects, if allowed (default: false)
+    #[serde(default)]
+    pub shell: bool,
+}
+
+impl RunCommandTool {
+    pub fn new(runner: Arc<CommandRunner>) -> Self {
+        Self { runner }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "run_command".to_string(),
+            description: "Run a program in the workspace and return its exit code, stdout and \
+                stderr as JSON. Commands are not run through a shell unless shell is set."
+                .to_string(),
+            parameters: schema_for::<RunCommandArgs>(),
+        }
+    }
+
+    fn spec(&self, args: &RunCommandArgs) -> Result<CommandSpec, CommandError> {
+        match &args.args {
+            Some(list) => self.runner.spec(args.command.clone(), list.clone()),
+            None => self.runner.parse(&args.command, args.shell),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for RunCommandTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: RunCommandArgs = parse_args(params)?;
+        let spec = match self.spec(&args) {
+            Ok(spec) => spec,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+        let dir = match self.runner.working_dir(args.cwd.as_deref()) {
+            Ok(dir) => dir,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+        let timeout = args.timeout_seconds.map(Duration::from_secs);
+
+        tracing::info!("Running command: {}", spec.display());
+        match self.runner.run(&spec, &dir, timeout).await {
+            Ok(output) => Ok(ToolResult::success(
+                serde_json::to_string_pretty(&output).unwrap_or_default(),
+            )),
+            Err(e) => Ok(ToolResult::failure(e.to_string())),
+        }
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: RunCommandArgs = parse_args(params.clone()).ok()?;
+        let spec = self.spec(&args).ok()?;
+        let cwd = args.cwd.as_deref().unwrap_or(".");
+        Some(format!("$ {}  (in {})", spec.display(), cwd))
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::{ConfigError, XzardgzError};
+use crate::providers::types::Tool;
+use crate::tools::command_ops::{CommandRunner, CommandSpec};
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use handlebars::Handlebars;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A project-specific tool declared in `config.yaml` that runs a command
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomToolConfig {
+    pub name: String,
+    pub description: String,
+    /// JSON schema for the arguments, checked before every call
+    #[serde(default = "default_parameters")]
+    pub parameters: Value,
+    /// Program and argument templates; `{{name}}` is replaced by an argument
+    pub command: Vec<String>,
+    /// How arguments reach the command besides the templates
+    #[serde(default)]
+    pub input: ArgumentInput,
+    /// Whether calls change anything and so need approval
+    #[serde(default = "default_mutating")]
+    pub mutating: bool,
+    /// Directory to run in, relative to the workspace root
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Longest a call may run; never more than `commands.timeout_seconds`
+    #[serde(default)]
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentInput {
+    /// Only through the command templates; stdin is closed
+    #[default]
+    Argv,
+    /// Also as a JSON object on stdin
+    Stdin,
+}
+
+fn default_parameters() -> Value {
+    json!({"type": "object", "properties": {}})
+}
+
+fn default_mutating() -> bool {
+    true
+}
+
+/// Runs a `CustomToolConfig` through the command runner, so the workspace,
+/// environment, timeout and output limits of `commands` apply. The program
+/// is fixed by the config and is not checked against `commands.allow`.
+pub struct CustomTool {
+    config: CustomToolConfig,
+    runner: Arc<CommandRunner>,
+    templates: Handlebars<'static>,
+}
+
+impl CustomTool {
+    pub fn new(config: CustomToolConfig, runner: Arc<CommandRunner>) -> Result<Self, ConfigError> {
+        let invalid = |reason: String| {
+            ConfigError::Validation(format!("custom tool {}: {}", config.name, reason))
+        };
+        if config.name.is_empty()
+            || !config
+                .name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(invalid(
+                "names may only use letters, digits, '_' and '-'".to_string(),
+            ));
+        }
+        match config.command.first() {
+            None => return Err(invalid("command is empty".to_string())),
+            Some(program) if program.contains("{{") => {
+                return Err(invalid("the program cannot be templated".to_string()));
+            }
+            Some(_) => {}
+        }
+        if config.parameters["type"] != "object" {
+            return Err(invalid("parameters must be an object schema".to_string()));
+        }
+
+        let mut templates = Handlebars::new();
+        templates.register_escape_fn(handlebars::no_escape);
+        for (index, word) in config.command.iter().enumerate().skip(1) {
+            templates
+                .register_template_string(&index.to_string(), word)
+                .map_err(|e| invalid(format!("command word {:?}: {}", word, e)))?;
+        }
+
+        Ok(Self {
+            config,
+            runner,
+            templates,
+        })
+    }
+
+    pub fn definition(&self) -> Tool {
+        Tool {
+            name: self.config.name.clone(),
+            description: self.config.description.clone(),
+            parameters: self.config.parameters.clone(),
+        }
+    }
+
+    /// Fill in the argument templates. A word that is just `{{name}}` for an
+    /// array argument becomes one word per item, and words that render empty
+    /// are dropped, so `{{#if fix}}--fix{{/if}}` works as a flag.
+    fn spec(&self, params: &Value) -> Result<CommandSpec, String> {
+        let mut args = Vec::new();
+        for (index, word) in self.config.command.iter().enumerate().skip(1) {
+            if let Some(Value::Array(items)) = whole_placeholder(word).map(|name| &params[name]) {
+                args.extend(items.iter().map(|item| match item {
+                    Value::String(text) => text.clone(),
+                    other => other.to_string(),
+                }));
+                continue;
+            }
+            let rendered = self
+                .templates
+                .render(&index.to_string(), params)
+                .map_err(|e| format!("Failed to fill in {:?}: {}", word, e))?;
+            if !rendered.is_empty() {
+                args.push(rendered);
+            }
+        }
+        Ok(CommandSpec {
+            program: self.config.command[0].clone(),
+            args,
+        })
+    }
+}
+
+/// The argument name if `word` is exactly one `{{name}}` placeholder
+fn whole_placeholder(word: &str) -> Option<&str> {
+    let name = word.strip_prefix("{{")?.strip_suffix("}}")?.trim();
+    name.chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        .then_some(name)
+}
+
+#[async_trait]
+impl ToolExecutor for CustomTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let spec = match self.spec(&params) {
+            Ok(spec) => spec,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let dir = match self.runner.working_dir(self.config.cwd.as_deref()) {
+            Ok(dir) => dir,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+        let input = match self.config.input {
+            ArgumentInput::Argv => None,
+            ArgumentInput::Stdin => Some(params.to_string()),
+        };
+        let timeout = self.config.timeout_seconds.map(Duration::from_secs);
+
+        tracing::info!("Running {}: {}", self.config.name, spec.display());
+        match self
+            .runner
+            .run_with_input(&spec, &dir, timeout, input.as_deref())
+            .await
+        {
+            Ok(output) => Ok(ToolResult::success(
+                serde_json::to_string_pretty(&output).unwrap_or_default(),
+            )),
+            Err(e) => Ok(ToolResult::failure(e.to_string())),
+        }
+    }
+
+    fn is_mutating(&self) -> bool {
+        self.config.mutating
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let spec = self.spec(params).ok()?;
+        let cwd = self.config.cwd.as_deref().unwrap_or(".");
+        let mut preview = format!("$ {}  (in {})", spec.display(), cwd);
+        if self.config.input == ArgumentInput::Stdin {
+            preview.push_str(&format!("\nstdin: {}", params));
+        }
+        Some(preview)
+    }
+}
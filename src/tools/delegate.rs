@@ -0,0 +1,243 @@
+use crate::agent::compaction::CompactionStrategy;
+use crate::agent::core::Agent;
+use crate::error::XzardgzError;
+use crate::providers::base::Provider;
+use crate::providers::types::Tool;
+use crate::tools::approval::Approver;
+use crate::tools::audit::AuditLog;
+use crate::tools::registry::ToolRegistry;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::{ToolContext, ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a focused sub-agent. Complete the task you are given \
+    and reply with only the result the caller needs.";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationConfig {
+    /// How many levels of sub-agents may be nested
+    pub max_depth: usize,
+    /// Total sub-agents one run (a chat turn or a workflow step) may start,
+    /// across all levels
+    pub max_delegations: usize,
+}
+
+impl Default for DelegationConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: 2,
+            max_delegations: 5,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct DelegateTaskArgs {
+    /// Complete description of the task, including any context the sub-agent needs
+    pub task: String,
+    /// Optional focused instructions for the sub-agent
+    pub system_prompt: Option<String>,
+    /// Names of the tools the sub-agent may use (default: all)
+    pub tools: Option<Vec<String>>,
+}
+
+/// Runs a task in a child `Agent` with its own context and returns only its answer
+pub struct DelegateTaskTool {
+    provider: Arc<dyn Provider>,
+    tools: ToolRegistry,
+    config: DelegationConfig,
+    depth: usize,
+    approver: Option<Arc<dyn Approver>>,
+    audit: Option<Arc<AuditLog>>,
+    dry_run: bool,
+    tool_output_limit: Option<usize>,
+    compaction: Option<CompactionStrategy>,
+}
+
+impl DelegateTaskTool {
+    /// `tools` are the tools sub-agents may be given
+    pub fn new(provider: Arc<dyn Provider>, tools: ToolRegistry, config: DelegationConfig) -> Self {
+        Self {
+            provider,
+            tools,
+            config,
+            depth: 0,
+            approver: None,
+            audit: None,
+            dry_run: false,
+            tool_output_limit: None,
+            compaction: None,
+        }
+    }
+
+    /// Sub-agents ask `approver` before running mutating tools
+    pub fn with_approver(mut self, approver: Arc<dyn Approver>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Sub-agents record their tool calls in `log`
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Sub-agents only report what mutating tools would do
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sub-agents cap each tool result at `bytes`, like their parent
+    pub fn with_tool_output_limit(mut self, bytes: usize) -> Self {
+        self.tool_output_limit = Some(bytes);
+        self
+    }
+
+    /// Sub-agents compact their context with `strategy`, like their parent
+    pub fn with_compaction(mut self, strategy: CompactionStrategy) -> Self {
+        self.compaction = Some(strategy);
+        self
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "delegate_task".to_string(),
+            description: "Hand a self-contained task to a sub-agent with a fresh context. \
+                Only the sub-agent's final answer is returned."
+                .to_string(),
+            parameters: schema_for::<DelegateTaskArgs>(),
+        }
+    }
+
+    /// Tools the calling run may hand out: all of them, or only those of the
+    /// step profile the run is limited to
+    fn available(&self, context: &ToolContext) -> ToolRegistry {
+        match &context.tools {
+            Some(scope) => {
+                let names: Vec<String> = scope.list_tools().into_iter().map(|t| t.name).collect();
+                self.tools.subset(&names)
+            }
+            None => self.tools.clone(),
+        }
+    }
+
+    /// Tools for the child: the requested subset of `available`, plus
+    /// delegation if depth allows
+    fn child_registry(
+        &self,
+        available: &ToolRegistry,
+        requested: Option<Vec<String>>,
+    ) -> Result<ToolRegistry, String> {
+        let mut registry = match requested {
+            Some(names) => {
+                let unknown: Vec<&String> = names
+                    .iter()
+                    .filter(|name| available.get_tool(name).is_none())
+                    .collect();
+                if !unknown.is_empty() {
+                    let mut available: Vec<String> =
+                        available.list_tools().into_iter().map(|t| t.name).collect();
+                    available.sort();
+                    return Err(format!(
+                        "Unknown tools {:?}. Available: {}",
+                        unknown,
+                        available.join(", ")
+                    ));
+                }
+                available.subset(&names)
+            }
+            None => available.clone(),
+        };
+
+        if self.depth + 1 < self.config.max_depth {
+            let nested = DelegateTaskTool {
+                provider: self.provider.clone(),
+                tools: available.clone(),
+                config: self.config.clone(),
+                depth: self.depth + 1,
+                approver: self.approver.clone(),
+                audit: self.audit.clone(),
+                dry_run: self.dry_run,
+                tool_output_limit: self.tool_output_limit,
+                compaction: self.compaction,
+            };
+            registry.register(DelegateTaskTool::definition(), Arc::new(nested));
+        }
+        Ok(registry)
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for DelegateTaskTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        self.execute_in(params, &ToolContext::default()).await
+    }
+
+    /// The sub-agent runs as part of the caller's run: it stops when the run is
+    /// cancelled and counts against the run's delegation limit and budgets
+    async fn execute_in(
+        &self,
+        params: Value,
+        context: &ToolContext,
+    ) -> Result<ToolResult, XzardgzError> {
+        let args: DelegateTaskArgs = parse_args(params)?;
+
+        if self.depth >= self.config.max_depth {
+            return Ok(ToolResult::failure(format!(
+                "Delegation depth limit ({}) reached; complete the task yourself",
+                self.config.max_depth
+            )));
+        }
+        let used = context.delegations.fetch_add(1, Ordering::SeqCst);
+        if used >= self.config.max_delegations {
+            return Ok(ToolResult::failure(format!(
+                "Delegation budget ({} sub-agents) exhausted; complete the task yourself",
+                self.config.max_delegations
+            )));
+        }
+
+        let registry = match self.child_registry(&self.available(context), args.tools) {
+            Ok(registry) => registry,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let system_prompt = args
+            .system_prompt
+            .unwrap_or_else(|| DEFAULT_SYSTEM_PROMPT.to_string());
+        let mut agent =
+            Agent::new(self.provider.clone(), system_prompt, registry).with_dry_run(self.dry_run);
+        if let Some(audit) = &self.audit {
+            agent = agent.with_audit_log(audit.clone());
+        }
+        if let Some(approver) = &self.approver {
+            agent = agent.with_approver(approver.clone());
+        }
+        for budget in &context.budgets {
+            agent = agent.with_shared_budget(budget.clone());
+        }
+        if let Some(bytes) = self.tool_output_limit {
+            agent = agent.with_tool_output_limit(bytes);
+        }
+        if let Some(strategy) = self.compaction {
+            agent = agent.with_compaction(strategy);
+        }
+
+        tracing::info!("Delegating task at depth {}: {}", self.depth + 1, args.task);
+        // The child's registry is already narrowed, so only the run is shared
+        let child_run = ToolContext {
+            tools: None,
+            ..context.clone()
+        };
+        match agent.run_in(&args.task, &child_run).await {
+            Ok(answer) => Ok(ToolResult::success(answer)),
+            Err(XzardgzError::Cancelled) => Err(XzardgzError::Cancelled),
+            Err(e) => Ok(ToolResult::failure(format!("Sub-agent failed: {}", e))),
+        }
+    }
+}
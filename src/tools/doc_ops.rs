@@ -0,0 +1,410 @@
+use crate::docgen::diataxis::DocCategory;
+use crate::docgen::templates::TemplateRegistry;
+use crate::docgen::writer::DocumentWriter;
+use crate::error::XzardgzError;
+use crate::providers::types::Tool;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const CATEGORIES: [DocCategory; 4] = [
+    DocCategory::Tutorial,
+    DocCategory::HowTo,
+    DocCategory::Explanation,
+    DocCategory::Reference,
+];
+const DEFAULT_SEARCH_RESULTS: u64 = 50;
+const MAX_SEARCH_RESULTS: u64 = 200;
+
+fn category_schema(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "string",
+        "enum": ["tutorial", "how-to", "explanation", "reference"]
+    })
+}
+
+/// Accept `how-to` as well as `how_to` and `HowTo`
+fn parse_category(name: &str) -> Result<DocCategory, String> {
+    let key = |name: &str| name.replace(['-', '_'], "").to_lowercase();
+    CATEGORIES
+        .into_iter()
+        .find(|category| key(category.as_str()) == key(name))
+        .ok_or_else(|| {
+            format!(
+                "Unknown category {}; use tutorial, how-to, explanation or reference",
+                name
+            )
+        })
+}
+
+/// Path of a document relative to the workspace root, e.g. `docs/how_to/deploy.md`
+fn doc_path(category: DocCategory, name: &str) -> Result<String, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(format!(
+            "Invalid document name {:?}; use a file name such as deploy.md",
+            name
+        ));
+    }
+    let mut file = name.to_string();
+    if !file.ends_with(".md") {
+        file.push_str(".md");
+    }
+    Ok(format!("{}/{}", category.directory(), file))
+}
+
+/// Markdown files in one category directory, sorted, as absolute paths
+fn category_docs(workspace: &Workspace, category: DocCategory) -> Vec<PathBuf> {
+    let Ok(dir) = workspace.resolve_read(category.directory()) else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut docs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "md"))
+        .filter(|path| workspace.is_readable(path))
+        .collect();
+    docs.sort();
+    docs
+}
+
+/// Categories named by an optional `category` parameter, or all of them
+fn selected_categories(category: Option<&str>) -> Result<Vec<DocCategory>, String> {
+    match category {
+        None => Ok(CATEGORIES.to_vec()),
+        Some(name) => parse_category(name).map(|category| vec![category]),
+    }
+}
+
+pub struct ListDocsTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDocsArgs {
+    /// Diataxis category
+    // Without `skip_serializing_if` the schema would mark it required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "category_schema")]
+    pub category: Option<String>,
+}
+
+impl ListDocsTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "list_docs".to_string(),
+            description: "List the documents in each Diataxis category with their titles"
+                .to_string(),
+            parameters: schema_for::<ListDocsArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ListDocsTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: ListDocsArgs = parse_args(params)?;
+        let categories = match selected_categories(args.category.as_deref()) {
+            Ok(categories) => categories,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let mut output = Vec::new();
+        for category in categories {
+            output.push(format!("{} ({}):", category, category.directory()));
+            let docs = category_docs(&self.workspace, category);
+            if docs.is_empty() {
+                output.push("  (none)".to_string());
+            }
+            for path in docs {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let title = fs::read_to_string(&path).ok().and_then(|content| {
+                    content
+                        .lines()
+                        .find_map(|line| line.strip_prefix("# ").map(|t| t.trim().to_string()))
+                });
+                match title {
+                    Some(title) => output.push(format!("  {} - {}", name, title)),
+                    None => output.push(format!("  {}", name)),
+                }
+            }
+        }
+        Ok(ToolResult::success(output.join("\n")))
+    }
+}
+
+pub struct ReadDocTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadDocArgs {
+    /// Diataxis category
+    #[schemars(schema_with = "category_schema")]
+    pub category: String,
+    /// File name as shown by list_docs, e.g. deploy.md
+    pub name: String,
+}
+
+impl ReadDocTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "read_doc".to_string(),
+            description: "Read a document from a Diataxis category".to_string(),
+            parameters: schema_for::<ReadDocArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ReadDocTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: ReadDocArgs = parse_args(params)?;
+        let path = match parse_category(&args.category)
+            .and_then(|category| doc_path(category, &args.name))
+        {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let resolved = match self.workspace.resolve_read(&path) {
+            Ok(resolved) => resolved,
+            Err(violation) => return Ok(ToolResult::failure(violation.to_string())),
+        };
+        match fs::read_to_string(resolved) {
+            Ok(content) => Ok(ToolResult::success(content)),
+            Err(_) => Ok(ToolResult::failure(format!("Document not found: {}", path))),
+        }
+    }
+}
+
+pub struct WriteDocTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WriteDocArgs {
+    /// Diataxis category
+    #[schemars(schema_with = "category_schema")]
+    pub category: String,
+    /// File name, e.g. deploy.md (.md is added if missing)
+    pub name: String,
+    /// Markdown content
+    pub content: String,
+    /// Replace an existing document (default: false)
+    #[serde(default)]
+    pub overwrite: bool,
+}
+
+impl WriteDocTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "write_doc".to_string(),
+            description: "Write a document into a Diataxis category directory. Existing \
+                documents are only replaced when overwrite is set."
+                .to_string(),
+            parameters: schema_for::<WriteDocArgs>(),
+        }
+    }
+
+    /// Category, relative path and file name, checked against the workspace
+    fn target(&self, args: &WriteDocArgs) -> Result<(DocCategory, String, PathBuf), String> {
+        let category = parse_category(&args.category)?;
+        let path = doc_path(category, &args.name)?;
+        let resolved = self
+            .workspace
+            .resolve_write(&path)
+            .map_err(|violation| violation.to_string())?;
+        Ok((category, path, resolved))
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for WriteDocTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: WriteDocArgs = parse_args(params)?;
+        let (category, path, resolved) = match self.target(&args) {
+            Ok(target) => target,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let overwrite = args.overwrite;
+        if overwrite && let Err(e) = self.workspace.backup(&resolved) {
+            return Ok(ToolResult::failure(format!(
+                "Failed to back up file: {}",
+                e
+            )));
+        }
+
+        let file_name = resolved.file_name().unwrap_or_default().to_string_lossy();
+        let writer = DocumentWriter::new(self.workspace.root().to_path_buf(), overwrite);
+        match writer.write(category, &file_name, &args.content).await {
+            Ok(_) => Ok(ToolResult::success(format!("Wrote {}", path))),
+            Err(e) => Ok(ToolResult::failure(format!(
+                "{}{}",
+                e,
+                if resolved.exists() && !overwrite {
+                    "; set overwrite to replace it"
+                } else {
+                    ""
+                }
+            ))),
+        }
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: WriteDocArgs = parse_args(params.clone()).ok()?;
+        let (_, _, resolved) = self.target(&args).ok()?;
+        let current = fs::read_to_string(resolved).unwrap_or_default();
+        Some(diffy::create_patch(&current, &args.content).to_string())
+    }
+}
+
+pub struct RenderTemplateTool {
+    templates: Arc<TemplateRegistry>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RenderTemplateArgs {
+    /// Diataxis category
+    #[schemars(schema_with = "category_schema")]
+    pub category: String,
+    /// Template fields
+    pub data: Map<String, Value>,
+}
+
+impl RenderTemplateTool {
+    pub fn new(templates: Arc<TemplateRegistry>) -> Self {
+        Self { templates }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "render_template".to_string(),
+            description: "Render the Markdown template for a Diataxis category without writing \
+                it. Fields: tutorial (title, introduction, prerequisites, steps, conclusion), \
+                how-to (title, problem, solution, steps, discussion), explanation (title, \
+                overview, concepts, architecture, design_decisions), reference (title, \
+                description, api, usage, examples). steps is a list of strings."
+                .to_string(),
+            parameters: schema_for::<RenderTemplateArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for RenderTemplateTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: RenderTemplateArgs = parse_args(params)?;
+        let category = match parse_category(&args.category) {
+            Ok(category) => category,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        match self.templates.render(category, &Value::Object(args.data)) {
+            Ok(rendered) => Ok(ToolResult::success(rendered)),
+            Err(e) => Ok(ToolResult::failure(e.to_string())),
+        }
+    }
+}
+
+pub struct SearchDocsTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchDocsArgs {
+    /// Text to look for
+    pub query: String,
+    /// Diataxis category
+    // Without `skip_serializing_if` the schema would mark it required
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(schema_with = "category_schema")]
+    pub category: Option<String>,
+    /// Maximum matching lines (default: 50, max: 200)
+    pub max_results: Option<u64>,
+}
+
+impl SearchDocsTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "search_docs".to_string(),
+            description: "Find lines in the documentation containing some text (ignoring case), \
+                as path:line: text"
+                .to_string(),
+            parameters: schema_for::<SearchDocsArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for SearchDocsTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: SearchDocsArgs = parse_args(params)?;
+        let query = &args.query;
+        let categories = match selected_categories(args.category.as_deref()) {
+            Ok(categories) => categories,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let max_results = args
+            .max_results
+            .unwrap_or(DEFAULT_SEARCH_RESULTS)
+            .clamp(1, MAX_SEARCH_RESULTS) as usize;
+
+        let needle = query.to_lowercase();
+        let mut matches = Vec::new();
+        'docs: for path in categories
+            .into_iter()
+            .flat_map(|category| category_docs(&self.workspace, category))
+        {
+            let Ok(content) = fs::read_to_string(&path) else {
+                continue;
+            };
+            let relative = self.workspace.relative(&path);
+            for (number, line) in content.lines().enumerate() {
+                if !line.to_lowercase().contains(&needle) {
+                    continue;
+                }
+                if matches.len() == max_results {
+                    matches.push(format!("(stopped after {} matches)", max_results));
+                    break 'docs;
+                }
+                matches.push(format!("{}:{}: {}", relative, number + 1, line));
+            }
+        }
+
+        if matches.is_empty() {
+            return Ok(ToolResult::success(format!(
+                "No documents mention {}",
+                query
+            )));
+        }
+        Ok(ToolResult::success(matches.join("\n")))
+    }
+}
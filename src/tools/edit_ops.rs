@@ -0,0 +1,302 @@
+use crate::error::XzardgzError;
+use crate::providers::types::Tool;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A change to one file, worked out before anything is written so the same
+/// edit can be previewed for approval and then applied
+struct Edit {
+    path: PathBuf,
+    before: String,
+    after: String,
+}
+
+impl Edit {
+    /// Read the file an edit tool targets; edits never create files
+    fn load(workspace: &Workspace, path_str: &str) -> Result<Self, String> {
+        let path = workspace
+            .resolve_write(path_str)
+            .map_err(|violation| violation.to_string())?;
+        if !path.is_file() {
+            return Err(format!("File not found: {}", path_str));
+        }
+        let before =
+            fs::read_to_string(&path).map_err(|e| format!("Failed to read file: {}", e))?;
+        Ok(Self {
+            path,
+            after: before.clone(),
+            before,
+        })
+    }
+
+    fn diff(&self) -> String {
+        diffy::create_patch(&self.before, &self.after).to_string()
+    }
+
+    fn apply(self, workspace: &Workspace, summary: String) -> ToolResult {
+        if let Err(e) = workspace.backup(&self.path) {
+            return ToolResult::failure(format!("Failed to back up file: {}", e));
+        }
+        match fs::write(&self.path, &self.after) {
+            Ok(_) => ToolResult::success(summary),
+            Err(e) => ToolResult::failure(format!("Failed to write file: {}", e)),
+        }
+    }
+}
+
+fn run(
+    workspace: &Workspace,
+    path: &str,
+    plan: impl FnOnce(Edit) -> Result<(Edit, String), String>,
+) -> ToolResult {
+    let edit = match Edit::load(workspace, path) {
+        Ok(edit) => edit,
+        Err(e) => return ToolResult::failure(e),
+    };
+    match plan(edit) {
+        Ok((edit, summary)) => edit.apply(workspace, summary),
+        Err(e) => ToolResult::failure(e),
+    }
+}
+
+fn preview(
+    workspace: &Workspace,
+    path: &str,
+    plan: impl FnOnce(Edit) -> Result<(Edit, String), String>,
+) -> Option<String> {
+    let edit = Edit::load(workspace, path).ok()?;
+    plan(edit).ok().map(|(edit, _)| edit.diff())
+}
+
+pub struct EditFileTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct EditFileArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// Text to replace; include surrounding lines to make it unique
+    pub old_text: String,
+    /// Replacement text
+    pub new_text: String,
+    /// Replace every occurrence (default: false)
+    #[serde(default)]
+    pub replace_all: bool,
+}
+
+impl EditFileTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "edit_file".to_string(),
+            description: "Replace exact text in a file. old_text must match the file exactly, \
+                including whitespace, and occur only once unless replace_all is set."
+                .to_string(),
+            parameters: schema_for::<EditFileArgs>(),
+        }
+    }
+
+    fn plan(args: &EditFileArgs, mut edit: Edit) -> Result<(Edit, String), String> {
+        let (path, old_text, new_text) = (&args.path, &args.old_text, &args.new_text);
+        if old_text.is_empty() {
+            return Err("old_text must not be empty".to_string());
+        }
+
+        let count = edit.before.matches(old_text.as_str()).count();
+        match count {
+            0 => {
+                return Err(format!(
+                    "old_text was not found in {}. It must match the file exactly, \
+                     including whitespace and indentation; read the file again and retry.",
+                    path
+                ));
+            }
+            1 => {}
+            _ if !args.replace_all => {
+                return Err(format!(
+                    "old_text occurs {} times in {}. Include more surrounding lines to make it \
+                     unique, or set replace_all.",
+                    count, path
+                ));
+            }
+            _ => {}
+        }
+
+        edit.after = edit.before.replace(old_text.as_str(), new_text);
+        let summary = format!("Replaced {} occurrence(s) in {}", count, path);
+        Ok((edit, summary))
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for EditFileTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: EditFileArgs = parse_args(params)?;
+        Ok(run(&self.workspace, &args.path, |edit| {
+            Self::plan(&args, edit)
+        }))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: EditFileArgs = parse_args(params.clone()).ok()?;
+        preview(&self.workspace, &args.path, |edit| Self::plan(&args, edit))
+    }
+}
+
+pub struct InsertLinesTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct InsertLinesArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// 1-based line to insert before; one past the last line appends
+    pub line: u64,
+    /// Lines to insert
+    pub content: String,
+}
+
+impl InsertLinesTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "insert_lines".to_string(),
+            description: "Insert lines into a file before a given line number".to_string(),
+            parameters: schema_for::<InsertLinesArgs>(),
+        }
+    }
+
+    fn plan(args: &InsertLinesArgs, mut edit: Edit) -> Result<(Edit, String), String> {
+        let (path, content, line) = (&args.path, &args.content, args.line as usize);
+
+        let mut lines: Vec<&str> = edit.before.split_inclusive('\n').collect();
+        if line == 0 || line > lines.len() + 1 {
+            return Err(format!(
+                "line {} is outside {}, which has {} lines; use 1 to {}",
+                line,
+                path,
+                lines.len(),
+                lines.len() + 1
+            ));
+        }
+
+        // Keep line structure intact when appending after a last line without a newline
+        let mut inserted = content.to_string();
+        if !inserted.ends_with('\n') {
+            inserted.push('\n');
+        }
+        let mut prefix = String::new();
+        if line == lines.len() + 1 && lines.last().is_some_and(|last| !last.ends_with('\n')) {
+            prefix.push('\n');
+        }
+        let inserted = format!("{}{}", prefix, inserted);
+        lines.insert(line - 1, &inserted);
+
+        edit.after = lines.concat();
+        let count = content.lines().count().max(1);
+        let summary = format!("Inserted {} line(s) at line {} of {}", count, line, path);
+        Ok((edit, summary))
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for InsertLinesTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: InsertLinesArgs = parse_args(params)?;
+        Ok(run(&self.workspace, &args.path, |edit| {
+            Self::plan(&args, edit)
+        }))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: InsertLinesArgs = parse_args(params.clone()).ok()?;
+        preview(&self.workspace, &args.path, |edit| Self::plan(&args, edit))
+    }
+}
+
+pub struct ApplyPatchTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ApplyPatchArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// Unified diff with @@ hunk headers for this file
+    pub patch: String,
+}
+
+impl ApplyPatchTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "apply_patch".to_string(),
+            description: "Apply a unified diff to one file. Context lines must match the file."
+                .to_string(),
+            parameters: schema_for::<ApplyPatchArgs>(),
+        }
+    }
+
+    fn plan(args: &ApplyPatchArgs, mut edit: Edit) -> Result<(Edit, String), String> {
+        let (path, text) = (&args.path, &args.patch);
+
+        let patch =
+            diffy::Patch::from_str(text).map_err(|e| format!("Invalid unified diff: {}", e))?;
+        edit.after = diffy::apply(&edit.before, &patch).map_err(|e| {
+            format!(
+                "Patch does not apply to {} ({}). Read the file again and make sure the \
+                 context and removed lines match it exactly.",
+                path, e
+            )
+        })?;
+
+        let summary = format!("Applied {} hunk(s) to {}", patch.hunks().len(), path);
+        Ok((edit, summary))
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ApplyPatchTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: ApplyPatchArgs = parse_args(params)?;
+        Ok(run(&self.workspace, &args.path, |edit| {
+            Self::plan(&args, edit)
+        }))
+    }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: ApplyPatchArgs = parse_args(params.clone()).ok()?;
+        preview(&self.workspace, &args.path, |edit| Self::plan(&args, edit))
+    }
+}
@@ -1,37 +1,255 @@
 use crate::error::XzardgzError;
 use crate::providers::types::ToolCall;
-use crate::tools::ToolResult;
+use crate::tools::approval::{ApprovalDecision, ApprovalRequest, Approver};
+use crate::tools::audit::{AuditEntry, AuditLog, AuditOutcome};
 use crate::tools::registry::ToolRegistry;
-use serde_json::Value;
-use std::sync::Arc;
+use crate::tools::schema::{self, SchemaViolation};
+use crate::tools::{ToolContext, ToolResult};
+use chrono::Utc;
+use serde_json::{Value, json};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Largest tool output, in bytes, added to the conversation by default
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Outcome of an approval check, kept for reporting
+#[derive(Debug, Clone)]
+pub struct ApprovalRecord {
+    pub call_id: String,
+    pub tool: String,
+    pub decision: ApprovalDecision,
+}
 
 pub struct ToolExecutionDispatcher {
     registry: Arc<ToolRegistry>,
+    approver: Option<Arc<dyn Approver>>,
+    approvals: Mutex<Vec<ApprovalRecord>>,
+    max_output_bytes: usize,
+    audit: Option<Arc<AuditLog>>,
+    dry_run: bool,
+}
+
+/// What the audit log needs to know beyond the result
+#[derive(Default)]
+struct CallNotes {
+    outcome: Option<AuditOutcome>,
+    approval: Option<String>,
 }
 
 impl ToolExecutionDispatcher {
     pub fn new(registry: Arc<ToolRegistry>) -> Self {
-        Self { registry }
+        Self {
+            registry,
+            approver: None,
+            approvals: Mutex::new(Vec::new()),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            audit: None,
+            dry_run: false,
+        }
+    }
+
+    /// Truncate every tool's output (and error) to at most `bytes`
+    pub fn with_output_limit(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Require `approver` to sign off on every mutating tool call
+    pub fn with_approver(mut self, approver: Arc<dyn Approver>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Append every call to `log`
+    pub fn with_audit_log(mut self, log: Arc<AuditLog>) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Report what mutating tools would do instead of running them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Approval decisions made so far
+    pub fn approvals(&self) -> Vec<ApprovalRecord> {
+        self.approvals
+            .lock()
+            .map(|approvals| approvals.clone())
+            .unwrap_or_default()
     }
 
     pub async fn execute(&self, tool_call: &ToolCall) -> Result<ToolResult, XzardgzError> {
+        self.execute_in(tool_call, &ToolContext::default()).await
+    }
+
+    /// Like `execute`, on behalf of the run described by `context`
+    pub async fn execute_in(
+        &self,
+        tool_call: &ToolCall,
+        context: &ToolContext,
+    ) -> Result<ToolResult, XzardgzError> {
+        let started = Instant::now();
+        let mut notes = CallNotes::default();
+        let result = self.dispatch(tool_call, context, &mut notes).await;
+
+        if let Some(log) = &self.audit {
+            let (output, error) = match &result {
+                Ok(result) => (Some(result.output.clone()), result.error.clone()),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            let outcome = notes.outcome.unwrap_or(if error.is_some() {
+                AuditOutcome::Failure
+            } else {
+                AuditOutcome::Success
+            });
+            let arguments = serde_json::from_str(&tool_call.function.arguments)
+                .unwrap_or_else(|_| Value::String(tool_call.function.arguments.clone()));
+            log.record(&AuditEntry {
+                timestamp: Utc::now(),
+                call_id: tool_call.id.clone(),
+                tool: tool_call.function.name.clone(),
+                arguments,
+                outcome,
+                output: output.filter(|output| !output.is_empty()),
+                error,
+                duration_ms: started.elapsed().as_millis() as u64,
+                approval: notes.approval,
+            });
+        }
+        result
+    }
+
+    async fn dispatch(
+        &self,
+        tool_call: &ToolCall,
+        context: &ToolContext,
+        notes: &mut CallNotes,
+    ) -> Result<ToolResult, XzardgzError> {
         let function = &tool_call.function;
-        let executor = self.registry.get_executor(&function.name).ok_or_else(|| {
-            XzardgzError::Workflow(crate::error::WorkflowError::Execution(format!(
-                "Tool not found: {}",
-                function.name
-            )))
-        })?;
-
-        // Parse arguments if they are a string (JSON), otherwise use as is?
-        // ToolCall.function.arguments is String (JSON).
-        let params: Value = serde_json::from_str(&function.arguments).map_err(|e| {
-            XzardgzError::Workflow(crate::error::WorkflowError::Execution(format!(
-                "Invalid tool arguments: {}",
-                e
-            )))
-        })?;
-
-        executor.execute(params).await
+        // Mistakes of the model go back to it as results, so it can correct them
+        let Some(executor) = self.registry.get_executor(&function.name) else {
+            let mut available: Vec<String> = self
+                .registry
+                .list_tools()
+                .into_iter()
+                .map(|tool| tool.name)
+                .collect();
+            available.sort();
+            let report = json!({
+                "error": "unknown_tool",
+                "tool": function.name,
+                "available": available,
+            });
+            notes.outcome = Some(AuditOutcome::Invalid);
+            return Ok(ToolResult::failure(report.to_string()));
+        };
+
+        let mut params: Value = match serde_json::from_str(&function.arguments) {
+            Ok(params) => params,
+            Err(e) => {
+                let violation = SchemaViolation {
+                    path: String::new(),
+                    message: format!("not valid JSON: {}", e),
+                };
+                let report = json!({
+                    "error": "invalid_arguments",
+                    "tool": function.name,
+                    "violations": [violation],
+                });
+                notes.outcome = Some(AuditOutcome::Invalid);
+                return Ok(ToolResult::failure(report.to_string()));
+            }
+        };
+
+        // Checked before approval, so nobody is asked to approve a call that cannot run
+        if let Some(tool) = self.registry.get_tool(&function.name)
+            && let Err(violations) = schema::validate(&tool.parameters, &mut params)
+        {
+            tracing::info!(
+                "Invalid arguments for {} ({}): {:?}",
+                function.name,
+                tool_call.id,
+                violations
+            );
+            let report = json!({
+                "error": "invalid_arguments",
+                "tool": function.name,
+                "violations": violations,
+            });
+            notes.outcome = Some(AuditOutcome::Invalid);
+            return Ok(ToolResult::failure(report.to_string()));
+        }
+
+        // Nothing changes, so there is nothing to approve either
+        if self.dry_run && executor.is_mutating() {
+            tracing::info!("Dry run: skipped {} ({})", function.name, tool_call.id);
+            notes.outcome = Some(AuditOutcome::DryRun);
+            let effect = match executor.preview(&params) {
+                Some(preview) => format!("It would have made this change:\n{}", preview),
+                None => format!("It would have run with arguments {}", params),
+            };
+            return Ok(ToolResult::success(format!(
+                "[dry run] {} was not run and nothing was changed; continue as if it \
+                 succeeded. {}",
+                function.name, effect
+            )));
+        }
+
+        if let Some(approver) = self.approver.as_ref().filter(|_| executor.is_mutating()) {
+            let request = ApprovalRequest {
+                tool: function.name.clone(),
+                preview: executor.preview(&params),
+                arguments: params.clone(),
+            };
+            let decision = approver.approve(&request).await;
+            tracing::info!(
+                "Approval for {} ({}): {:?}",
+                function.name,
+                tool_call.id,
+                decision
+            );
+            notes.approval = Some(decision.policy().to_string());
+            if let Ok(mut approvals) = self.approvals.lock() {
+                approvals.push(ApprovalRecord {
+                    call_id: tool_call.id.clone(),
+                    tool: function.name.clone(),
+                    decision: decision.clone(),
+                });
+            }
+
+            if let ApprovalDecision::Denied { policy, reason } = decision {
+                notes.outcome = Some(AuditOutcome::Denied);
+                return Ok(ToolResult::failure(format!(
+                    "Tool call denied ({}): {}. Do not retry it unchanged.",
+                    policy, reason
+                )));
+            }
+        }
+
+        let mut result = executor.execute_in(params, context).await?;
+        result.output = truncate(result.output, self.max_output_bytes);
+        result.error = result.error.map(|e| truncate(e, self.max_output_bytes));
+        Ok(result)
+    }
+}
+
+/// Cut `text` to at most `limit` bytes on a character boundary, with a marker
+fn truncate(mut text: String, limit: usize) -> String {
+    if text.len() <= limit {
+        return text;
+    }
+    let total = text.len();
+    let mut end = limit;
+    while !text.is_char_boundary(end) {
+        end -= 1;
     }
+    text.truncate(end);
+    text.push_str(&format!(
+        "\n[Output truncated: showing {} of {} bytes]",
+        end, total
+    ));
+    text
 }
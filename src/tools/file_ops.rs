@@ -1,75 +1,228 @@
 use crate::error::XzardgzError;
 use crate::providers::types::Tool;
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
 use crate::tools::{ToolExecutor, ToolResult};
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::Value;
-use serde_json::json;
 use std::fs;
-use std::path::Path;
+use std::sync::Arc;
 
-pub struct ReadFileTool;
+pub struct ReadFileTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ReadFileArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// 1-based line to start at (default: 1)
+    pub offset: Option<u64>,
+    /// Maximum number of lines to return (default: all)
+    pub limit: Option<u64>,
+    /// Maximum bytes to return (default and max: 32768)
+    pub max_bytes: Option<u64>,
+    /// Prefix each line with its number
+    #[serde(default)]
+    pub line_numbers: bool,
+}
 
 impl ReadFileTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
     pub fn definition() -> Tool {
         Tool {
             name: "read_file".to_string(),
-            description: "Read contents of a file".to_string(),
-            parameters: json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "Path to the file"
-                    }
-                },
-                "required": ["path"]
-            }),
+            description: "Read a text file, or a range of its lines. Long files are truncated; \
+                use offset to continue where the output stops."
+                .to_string(),
+            parameters: schema_for::<ReadFileArgs>(),
         }
     }
 }
 
+/// Default and largest `max_bytes` for one `read_file` call
+pub const MAX_READ_BYTES: usize = 32 * 1024;
+
+/// Largest file `read_file` loads; line ranges and `max_bytes` are applied
+/// after loading, so bigger files are refused up front
+pub const MAX_FILE_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Bytes inspected for NUL bytes when deciding whether a file is binary
+const BINARY_SNIFF_BYTES: usize = 8 * 1024;
+
+/// Decode file contents as text. UTF-8 and UTF-16 byte order marks are
+/// honoured; files with NUL bytes are treated as binary and refused, other
+/// invalid UTF-8 is decoded lossily with a note.
+fn decode(bytes: &[u8]) -> Result<(String, Option<&'static str>), String> {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return Ok((String::from_utf8_lossy(rest).into_owned(), None));
+    }
+    let utf16: Option<fn([u8; 2]) -> u16> = match bytes {
+        [0xFF, 0xFE, ..] => Some(u16::from_le_bytes),
+        [0xFE, 0xFF, ..] => Some(u16::from_be_bytes),
+        _ => None,
+    };
+    if let Some(from_bytes) = utf16 {
+        let units: Vec<u16> = bytes[2..]
+            .chunks_exact(2)
+            .map(|pair| from_bytes([pair[0], pair[1]]))
+            .collect();
+        return Ok((
+            String::from_utf16_lossy(&units),
+            Some("decoded from UTF-16"),
+        ));
+    }
+    if bytes.iter().take(BINARY_SNIFF_BYTES).any(|&b| b == 0) {
+        return Err(format!(
+            "Binary file ({} bytes); it cannot be read as text",
+            bytes.len()
+        ));
+    }
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(text) => Ok((text, None)),
+        Err(e) => Ok((
+            String::from_utf8_lossy(e.as_bytes()).into_owned(),
+            Some("not valid UTF-8; invalid bytes are shown as \u{FFFD}"),
+        )),
+    }
+}
+
 #[async_trait]
 impl ToolExecutor for ReadFileTool {
     async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
-        let path_str = params["path"].as_str().ok_or_else(|| {
-            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                "Missing path parameter".to_string(),
-            ))
-        })?;
+        let args: ReadFileArgs = parse_args(params)?;
+        let path_str = args.path.as_str();
+        let offset = args.offset.unwrap_or(1).max(1) as usize;
+        let limit = args.limit.map(|limit| limit as usize);
+        let max_bytes = args
+            .max_bytes
+            .map_or(MAX_READ_BYTES, |bytes| (bytes as usize).min(MAX_READ_BYTES));
+        let line_numbers = args.line_numbers;
 
-        let path = Path::new(path_str);
+        let path = match self.workspace.resolve_read(path_str) {
+            Ok(path) => path,
+            Err(violation) => return Ok(ToolResult::failure(violation.to_string())),
+        };
         if !path.exists() {
             return Ok(ToolResult::failure(format!("File not found: {}", path_str)));
         }
+        match fs::metadata(&path) {
+            Ok(metadata) if !metadata.is_file() => {
+                return Ok(ToolResult::failure(format!(
+                    "{} is not a regular file",
+                    path_str
+                )));
+            }
+            Ok(metadata) if metadata.len() > MAX_FILE_BYTES => {
+                return Ok(ToolResult::failure(format!(
+                    "{} is {} bytes, more than the {} bytes read_file loads; search it \
+                     with grep instead",
+                    path_str,
+                    metadata.len(),
+                    MAX_FILE_BYTES
+                )));
+            }
+            Ok(_) => {}
+            Err(e) => return Ok(ToolResult::failure(format!("Failed to read file: {}", e))),
+        }
+
+        let bytes = match fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => return Ok(ToolResult::failure(format!("Failed to read file: {}", e))),
+        };
+        let (text, note) = match decode(&bytes) {
+            Ok(decoded) => decoded,
+            Err(e) => return Ok(ToolResult::failure(format!("{}: {}", path_str, e))),
+        };
+
+        let lines: Vec<&str> = text.split_inclusive('\n').collect();
+        let total = lines.len();
+        let first = offset - 1;
+        if first > 0 && first >= total {
+            return Ok(ToolResult::failure(format!(
+                "offset {} is past the end of {}, which has {} lines",
+                offset, path_str, total
+            )));
+        }
+        let last = limit.map_or(total, |limit| (first + limit).min(total));
 
-        match fs::read_to_string(path) {
-            Ok(content) => Ok(ToolResult::success(content)),
-            Err(e) => Ok(ToolResult::failure(format!("Failed to read file: {}", e))),
+        let mut output = String::new();
+        let mut shown = first;
+        let mut truncated = false;
+        for (index, line) in lines.iter().enumerate().take(last).skip(first) {
+            let line = if line_numbers {
+                format!("{:>6}\t{}", index + 1, line)
+            } else {
+                line.to_string()
+            };
+            if output.len() + line.len() > max_bytes {
+                truncated = true;
+                // A single line longer than the cap is shown in part
+                if shown == first {
+                    let mut end = max_bytes;
+                    while !line.is_char_boundary(end) {
+                        end -= 1;
+                    }
+                    output.push_str(&line[..end]);
+                    shown = index + 1;
+                }
+                break;
+            }
+            output.push_str(&line);
+            shown = index + 1;
+        }
+
+        let mut notes = Vec::new();
+        if let Some(note) = note {
+            notes.push(note.to_string());
+        }
+        if first > 0 || shown < total {
+            let mut range = format!("lines {}-{} of {}", offset, shown, total);
+            if truncated {
+                range.push_str(&format!(", truncated at {} bytes", max_bytes));
+            }
+            if shown < total {
+                range.push_str(&format!("; use offset={} to read more", shown + 1));
+            }
+            notes.push(range);
         }
+        if !notes.is_empty() {
+            if !output.is_empty() && !output.ends_with('\n') {
+                output.push('\n');
+            }
+            output.push_str(&format!("[{}]", notes.join("; ")));
+        }
+        Ok(ToolResult::success(output))
     }
 }
 
-pub struct WriteFileTool;
+pub struct WriteFileTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct WriteFileArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// Content to write
+    pub content: String,
+}
 
 impl WriteFileTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
     pub fn definition() -> Tool {
         Tool {
             name: "write_file".to_string(),
             description: "Write content to a file".to_string(),
-            parameters: json!({
-                "type": "object",
-                "properties": {
-                    "path": {
-                        "type": "string",
-                        "description": "Path to the file"
-                    },
-                    "content": {
-                        "type": "string",
-                        "description": "Content to write"
-                    }
-                },
-                "required": ["path", "content"]
-            }),
+            parameters: schema_for::<WriteFileArgs>(),
         }
     }
 }
@@ -77,18 +230,20 @@ impl WriteFileTool {
 #[async_trait]
 impl ToolExecutor for WriteFileTool {
     async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
-        let path_str = params["path"].as_str().ok_or_else(|| {
-            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                "Missing path parameter".to_string(),
-            ))
-        })?;
-        let content = params["content"].as_str().ok_or_else(|| {
-            XzardgzError::Workflow(crate::error::WorkflowError::Execution(
-                "Missing content parameter".to_string(),
-            ))
-        })?;
-
-        match fs::write(path_str, content) {
+        let args: WriteFileArgs = parse_args(params)?;
+        let (path_str, content) = (args.path.as_str(), args.content.as_str());
+
+        let path = match self.workspace.resolve_write(path_str) {
+            Ok(path) => path,
+            Err(violation) => return Ok(ToolResult::failure(violation.to_string())),
+        };
+        if let Err(e) = self.workspace.backup(&path) {
+            return Ok(ToolResult::failure(format!(
+                "Failed to back up file: {}",
+                e
+            )));
+        }
+        match fs::write(path, content) {
             Ok(_) => Ok(ToolResult::success(format!(
                 "Successfully wrote to {}",
                 path_str
@@ -96,4 +251,15 @@ impl ToolExecutor for WriteFileTool {
             Err(e) => Ok(ToolResult::failure(format!("Failed to write file: {}", e))),
         }
     }
+
+    fn is_mutating(&self) -> bool {
+        true
+    }
+
+    fn preview(&self, params: &Value) -> Option<String> {
+        let args: WriteFileArgs = parse_args(params.clone()).ok()?;
+        let path = self.workspace.resolve_write(&args.path).ok()?;
+        let current = fs::read_to_string(path).unwrap_or_default();
+        Some(diffy::create_patch(&current, &args.content).to_string())
+    }
 }
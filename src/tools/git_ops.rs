@@ -1,18 +1,32 @@
 use crate::error::XzardgzError;
 use crate::providers::types::Tool;
+use crate::repository::git::{CommitInfo, DiffTarget, FileDiff, GitRepository, StatusEntry};
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
 use crate::tools::{ToolExecutor, ToolResult};
 use async_trait::async_trait;
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::Value;
 use serde_json::json;
-use std::process::Command;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-pub struct GitStatusTool;
+pub struct GitStatusTool {
+    workspace: Arc<Workspace>,
+}
 
 impl GitStatusTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
     pub fn definition() -> Tool {
         Tool {
             name: "git_status".to_string(),
-            description: "Get git status".to_string(),
+            description: "List changed and untracked files in the workspace, one per line with \
+                the staged and unstaged change as in git status --short"
+                .to_string(),
             parameters: json!({
                 "type": "object",
                 "properties": {},
@@ -25,14 +39,472 @@ impl GitStatusTool {
 #[async_trait]
 impl ToolExecutor for GitStatusTool {
     async fn execute(&self, _params: Value) -> Result<ToolResult, XzardgzError> {
-        let output = Command::new("git").arg("status").output().map_err(|e| {
-            XzardgzError::Repository(crate::error::RepositoryError::Git(e.to_string()))
-        })?;
+        let scope = match open(&self.workspace) {
+            Ok(scope) => scope,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        match scope.repo.get_status(&scope.pathspecs(&[])) {
+            Ok(entries) => Ok(ToolResult::success(render_status(
+                &self.workspace,
+                &scope,
+                entries,
+            ))),
+            Err(e) => Ok(ToolResult::failure(e.to_string())),
+        }
+    }
+}
+
+const DEFAULT_LOG_LIMIT: u64 = 20;
+const MAX_LOG_LIMIT: u64 = 100;
+
+/// The repository containing the workspace, which may be a subdirectory of
+/// its working tree
+struct ScopedRepo {
+    repo: GitRepository,
+    /// Workspace root relative to the working tree, empty at the top
+    prefix: PathBuf,
+}
+
+fn open(workspace: &Workspace) -> Result<ScopedRepo, String> {
+    let repo = GitRepository::discover(workspace.root()).map_err(|e| e.to_string())?;
+    let workdir = repo
+        .workdir()
+        .ok_or_else(|| "The repository has no working tree".to_string())?
+        .canonicalize()
+        .map_err(|e| e.to_string())?;
+    let prefix = workspace
+        .root()
+        .strip_prefix(&workdir)
+        .map_err(|_| "The workspace is outside the repository's working tree".to_string())?
+        .to_path_buf();
+    Ok(ScopedRepo { repo, prefix })
+}
+
+impl ScopedRepo {
+    /// A workspace-relative path as git names it, relative to the working tree
+    fn repo_path(&self, relative: &str) -> String {
+        let parts: Vec<String> = self
+            .prefix
+            .join(relative)
+            .components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        parts.join("/")
+    }
+
+    /// Pathspecs for workspace-relative `paths`, or the whole workspace
+    fn pathspecs(&self, paths: &[String]) -> Vec<String> {
+        let specs: Vec<String> = if paths.is_empty() {
+            vec![self.repo_path("")]
+        } else {
+            paths.iter().map(|path| self.repo_path(path)).collect()
+        };
+        // An empty pathspec is the whole working tree, which is also the default
+        specs.into_iter().filter(|spec| !spec.is_empty()).collect()
+    }
+}
+
+/// A path argument checked against the workspace policy, relative to the root
+fn checked_path(workspace: &Workspace, path: &str) -> Result<String, String> {
+    let resolved = workspace
+        .resolve_read(path)
+        .map_err(|violation| violation.to_string())?;
+    Ok(workspace.relative(&resolved))
+}
 
-        if output.status.success() {
-            Ok(ToolResult::success(String::from_utf8_lossy(&output.stdout)))
+/// Strip `prefix` from the paths in a patch's `diff --git`, `---` and `+++` headers
+fn relative_patch(patch: &str, prefix: &str) -> String {
+    let mut output = String::with_capacity(patch.len());
+    let mut in_header = true;
+    for line in patch.split_inclusive('\n') {
+        in_header &= !line.starts_with("@@");
+        if in_header {
+            output.push_str(
+                &line
+                    .replace(&format!("a/{}/", prefix), "a/")
+                    .replace(&format!("b/{}/", prefix), "b/"),
+            );
         } else {
-            Ok(ToolResult::failure(String::from_utf8_lossy(&output.stderr)))
+            output.push_str(line);
+        }
+    }
+    output
+}
+
+/// Join patches with workspace-relative paths, leaving out files outside the
+/// workspace or denied by its policy
+fn render_patches(workspace: &Workspace, scope: &ScopedRepo, files: Vec<FileDiff>) -> String {
+    let prefix = scope.repo_path("");
+    let (shown, hidden): (Vec<FileDiff>, Vec<FileDiff>) = files.into_iter().partition(|file| {
+        Path::new(&file.path)
+            .strip_prefix(&scope.prefix)
+            .is_ok_and(|relative| workspace.is_readable(&workspace.root().join(relative)))
+    });
+    let mut output: String = shown
+        .into_iter()
+        .map(|file| match prefix.as_str() {
+            "" => file.patch,
+            prefix => relative_patch(&file.patch, prefix),
+        })
+        .collect();
+    if !hidden.is_empty() {
+        output.push_str(&format!(
+            "[{} file(s) hidden by the workspace policy]\n",
+            hidden.len()
+        ));
+    }
+    output
+}
+
+/// One `XY path` line per file with workspace-relative paths, leaving out
+/// files outside the workspace or denied by its policy
+fn render_status(workspace: &Workspace, scope: &ScopedRepo, entries: Vec<StatusEntry>) -> String {
+    let mut hidden = 0;
+    let mut output = String::new();
+    for entry in entries {
+        match Path::new(&entry.path).strip_prefix(&scope.prefix) {
+            Ok(relative) if workspace.is_readable(&workspace.root().join(relative)) => {
+                output.push_str(&format!("{} {}\n", entry.code, relative.display()));
+            }
+            _ => hidden += 1,
+        }
+    }
+    if output.is_empty() && hidden == 0 {
+        output.push_str("Clean\n");
+    }
+    if hidden > 0 {
+        output.push_str(&format!(
+            "[{} file(s) hidden by the workspace policy]\n",
+            hidden
+        ));
+    }
+    output
+}
+
+fn log_line(commit: &CommitInfo) -> String {
+    format!(
+        "{} {} {}: {}",
+        commit.short_id(),
+        commit.time.format("%Y-%m-%d"),
+        commit.author,
+        commit.summary()
+    )
+}
+
+pub struct GitDiffTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitDiffArgs {
+    /// Show staged instead of unstaged changes (default: false)
+    #[serde(default)]
+    pub staged: bool,
+    /// Revision to compare from, e.g. HEAD~3 or main
+    pub from: Option<String>,
+    /// Revision to compare to (default: the working tree)
+    pub to: Option<String>,
+    /// Only show changes to these paths
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl GitDiffTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "git_diff".to_string(),
+            description: "Show changes as a unified diff: unstaged changes by default, staged \
+                changes, or changes between revisions"
+                .to_string(),
+            parameters: schema_for::<GitDiffArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GitDiffTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GitDiffArgs = parse_args(params)?;
+        let target = match (args.from, args.to) {
+            (Some(from), to) => DiffTarget::Revisions { from, to },
+            (None, Some(_)) => {
+                return Ok(ToolResult::failure("to requires from"));
+            }
+            (None, None) if args.staged => DiffTarget::Staged,
+            (None, None) => DiffTarget::WorkingTree,
+        };
+        let mut paths = Vec::new();
+        for path in &args.paths {
+            match checked_path(&self.workspace, path) {
+                Ok(path) => paths.push(path),
+                Err(e) => return Ok(ToolResult::failure(e)),
+            }
+        }
+
+        let scope = match open(&self.workspace) {
+            Ok(scope) => scope,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let files = match scope.repo.diff(&target, &scope.pathspecs(&paths)) {
+            Ok(files) => files,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+        if files.is_empty() {
+            return Ok(ToolResult::success("No changes"));
+        }
+        Ok(ToolResult::success(render_patches(
+            &self.workspace,
+            &scope,
+            files,
+        )))
+    }
+}
+
+pub struct GitLogTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitLogArgs {
+    /// Revision to start from (default: HEAD)
+    pub rev: Option<String>,
+    /// Only commits that changed this path
+    pub path: Option<String>,
+    /// Maximum commits to list (default: 20, max: 100)
+    pub limit: Option<u64>,
+}
+
+impl GitLogTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "git_log".to_string(),
+            description: "List commits, newest first, as: id date author: summary".to_string(),
+            parameters: schema_for::<GitLogArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GitLogTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GitLogArgs = parse_args(params)?;
+        let limit = args
+            .limit
+            .unwrap_or(DEFAULT_LOG_LIMIT)
+            .clamp(1, MAX_LOG_LIMIT) as usize;
+        let path = match args.path.as_deref() {
+            Some(path) => match checked_path(&self.workspace, path) {
+                Ok(path) => Some(path),
+                Err(e) => return Ok(ToolResult::failure(e)),
+            },
+            None => None,
+        };
+
+        let commits = match open(&self.workspace).and_then(|scope| {
+            // Without a path, only commits that touched the workspace
+            let path = scope.repo_path(path.as_deref().unwrap_or_default());
+            let path = Some(path).filter(|path| !path.is_empty());
+            scope
+                .repo
+                .log(args.rev.as_deref(), path.as_deref(), limit)
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(commits) => commits,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        if commits.is_empty() {
+            return Ok(ToolResult::success("No commits"));
+        }
+        let lines: Vec<String> = commits.iter().map(log_line).collect();
+        Ok(ToolResult::success(lines.join("\n")))
+    }
+}
+
+pub struct GitShowTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitShowArgs {
+    /// Commit to show (default: HEAD)
+    pub rev: Option<String>,
+}
+
+impl GitShowTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "git_show".to_string(),
+            description: "Show a commit's message and changes".to_string(),
+            parameters: schema_for::<GitShowArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GitShowTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GitShowArgs = parse_args(params)?;
+        let rev = args.rev.as_deref().unwrap_or("HEAD");
+        let scope = match open(&self.workspace) {
+            Ok(scope) => scope,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let (commit, files) = match scope.repo.show(rev) {
+            Ok(shown) => shown,
+            Err(e) => return Ok(ToolResult::failure(e.to_string())),
+        };
+
+        let message: String = commit
+            .message
+            .lines()
+            .map(|line| format!("    {}\n", line))
+            .collect();
+        Ok(ToolResult::success(format!(
+            "commit {}\nAuthor: {} <{}>\nDate:   {}\n\n{}\n{}",
+            commit.id,
+            commit.author,
+            commit.email,
+            commit.time.to_rfc3339(),
+            message,
+            render_patches(&self.workspace, &scope, files)
+        )))
+    }
+}
+
+pub struct GitBlameTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitBlameArgs {
+    /// Path to the file, relative to the workspace root
+    pub path: String,
+    /// First line to blame (default: 1)
+    pub start_line: Option<u64>,
+    /// Last line to blame (default: the end of the file)
+    pub end_line: Option<u64>,
+}
+
+impl GitBlameTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "git_blame".to_string(),
+            description: "Show the commit and author that last changed each line of a file, \
+                as committed at HEAD"
+                .to_string(),
+            parameters: schema_for::<GitBlameArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GitBlameTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GitBlameArgs = parse_args(params)?;
+        let path = match checked_path(&self.workspace, &args.path) {
+            Ok(path) => path,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let start = args.start_line.map(|line| line as usize);
+        let end = args.end_line.map(|line| line as usize);
+
+        let lines = match open(&self.workspace).and_then(|scope| {
+            scope
+                .repo
+                .blame(Path::new(&scope.repo_path(&path)), start, end)
+                .map_err(|e| e.to_string())
+        }) {
+            Ok(lines) => lines,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let output: Vec<String> = lines
+            .iter()
+            .map(|line| {
+                format!(
+                    "{} ({} {} {:>5}) {}",
+                    line.commit,
+                    line.author,
+                    line.time.format("%Y-%m-%d"),
+                    line.line,
+                    line.text
+                )
+            })
+            .collect();
+        Ok(ToolResult::success(output.join("\n")))
+    }
+}
+
+pub struct GitBranchesTool {
+    workspace: Arc<Workspace>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GitBranchesArgs {
+    /// Include remote-tracking branches (default: false)
+    #[serde(default)]
+    pub remote: bool,
+}
+
+impl GitBranchesTool {
+    pub fn new(workspace: Arc<Workspace>) -> Self {
+        Self { workspace }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "git_branches".to_string(),
+            description: "List branches with their latest commit; * marks the current branch"
+                .to_string(),
+            parameters: schema_for::<GitBranchesArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GitBranchesTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let remote = parse_args::<GitBranchesArgs>(params)?.remote;
+        let branches = match open(&self.workspace)
+            .and_then(|scope| scope.repo.branches().map_err(|e| e.to_string()))
+        {
+            Ok(branches) => branches,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+
+        let lines: Vec<String> = branches
+            .iter()
+            .filter(|branch| remote || !branch.is_remote)
+            .map(|branch| {
+                let mut line =
+                    format!("{} {}", if branch.is_head { "*" } else { " " }, branch.name);
+                if let Some(commit) = &branch.commit {
+                    line.push_str(&format!(" {} {}", commit.short_id(), commit.summary()));
+                }
+                if let Some(upstream) = &branch.upstream {
+                    line.push_str(&format!(" [{}]", upstream));
+                }
+                line
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok(ToolResult::success("No branches"));
         }
+        Ok(ToolResult::success(lines.join("\n")))
     }
 }
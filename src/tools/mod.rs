@@ -1,11 +1,29 @@
+use crate::agent::budget::BudgetTracker;
 use crate::error::XzardgzError;
+use crate::tools::registry::ToolRegistry;
 use async_trait::async_trait;
 use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use tokio_util::sync::CancellationToken;
 
+pub mod approval;
+pub mod audit;
+pub mod builtin;
+pub mod code_ops;
+pub mod command_ops;
+pub mod custom_ops;
+pub mod delegate;
+pub mod doc_ops;
+pub mod edit_ops;
 pub mod executor;
 pub mod file_ops;
 pub mod git_ops;
+pub mod profile;
 pub mod registry;
+pub mod schema;
+pub mod search_ops;
+pub mod workspace;
 
 #[derive(Debug, Clone)]
 pub struct ToolResult {
@@ -29,7 +47,50 @@ impl ToolResult {
     }
 }
 
+/// The agent run a tool is called from
+#[derive(Clone, Default)]
+pub struct ToolContext {
+    /// Fires when the run is cancelled, e.g. by Ctrl-C
+    pub cancel: CancellationToken,
+    /// The tools the run may use, when a step profile narrows the agent's registry
+    pub tools: Option<ToolRegistry>,
+    /// Sub-agents started so far, counted across every level of delegation
+    pub delegations: Arc<AtomicUsize>,
+    /// Budgets of the calling turn, which sub-agents count their usage against
+    pub budgets: Vec<Arc<BudgetTracker>>,
+}
+
+impl ToolContext {
+    /// Context for a new top-level run
+    pub fn new(cancel: CancellationToken) -> Self {
+        Self {
+            cancel,
+            ..Self::default()
+        }
+    }
+}
+
 #[async_trait]
 pub trait ToolExecutor: Send + Sync {
     async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError>;
+
+    /// Like `execute`, for tools that depend on the run calling them, such as
+    /// `delegate_task`. Other tools ignore the context.
+    async fn execute_in(
+        &self,
+        params: Value,
+        _context: &ToolContext,
+    ) -> Result<ToolResult, XzardgzError> {
+        self.execute(params).await
+    }
+
+    /// Whether the tool changes files or other state, and so needs approval
+    fn is_mutating(&self) -> bool {
+        false
+    }
+
+    /// Description of what a call would change, shown when asking for approval
+    fn preview(&self, _params: &Value) -> Option<String> {
+        None
+    }
 }
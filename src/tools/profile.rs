@@ -0,0 +1,159 @@
+use crate::error::ConfigError;
+use crate::tools::registry::ToolRegistry;
+use globset::Glob;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+pub const READ_ONLY: &str = "read-only";
+pub const DOCS_WRITER: &str = "docs-writer";
+pub const FULL: &str = "full";
+
+/// Which tools a command or workflow step may use
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ToolProfile {
+    /// Include every tool that changes nothing, except `delegate_task`
+    #[serde(default)]
+    pub read_only_tools: bool,
+    /// Tools to include by name; `*` matches any characters, e.g. `git_*`
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Tools to leave out even when included above
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+impl ToolProfile {
+    /// The profile for `name` among `read-only`, `docs-writer` and `full`
+    pub fn builtin(name: &str) -> Option<Self> {
+        let allow = |names: &[&str]| names.iter().map(|name| name.to_string()).collect();
+        match name {
+            READ_ONLY => Some(Self {
+                read_only_tools: true,
+                ..Self::default()
+            }),
+            DOCS_WRITER => Some(Self {
+                read_only_tools: true,
+                allow: allow(&[
+                    "write_doc",
+                    "write_file",
+                    "edit_file",
+                    "insert_lines",
+                    "apply_patch",
+                ]),
+                deny: Vec::new(),
+            }),
+            FULL => Some(Self {
+                allow: allow(&["*"]),
+                ..Self::default()
+            }),
+            _ => None,
+        }
+    }
+
+    /// Whether the tool `name`, which changes files or other state if
+    /// `mutating`, belongs to the profile. Sub-agents can use every tool of
+    /// the run, so `delegate_task` has to be allowed by name.
+    pub fn allows(&self, name: &str, mutating: bool) -> bool {
+        let read_only = self.read_only_tools && !mutating && name != "delegate_task";
+        (read_only || matches(&self.allow, name)) && !matches(&self.deny, name)
+    }
+
+    /// The tools of `registry` that belong to the profile
+    pub fn apply(&self, registry: &ToolRegistry) -> ToolRegistry {
+        let names: Vec<String> = registry
+            .list_tools()
+            .into_iter()
+            .map(|tool| tool.name)
+            .filter(|name| {
+                let mutating = registry
+                    .get_executor(name)
+                    .is_some_and(|executor| executor.is_mutating());
+                self.allows(name, mutating)
+            })
+            .collect();
+        registry.subset(&names)
+    }
+
+    fn validate(&self, name: &str) -> Result<(), ConfigError> {
+        for pattern in self.allow.iter().chain(&self.deny) {
+            Glob::new(pattern)
+                .map_err(|e| ConfigError::Validation(format!("tool profile {}: {}", name, e)))?;
+        }
+        Ok(())
+    }
+}
+
+fn matches(patterns: &[String], name: &str) -> bool {
+    patterns
+        .iter()
+        .any(|pattern| Glob::new(pattern).is_ok_and(|glob| glob.compile_matcher().is_match(name)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolProfilesConfig {
+    /// Profile used when `--tools` is not given
+    #[serde(default = "default_profile")]
+    pub default: String,
+    /// Profiles by name, added to the built-in ones; a profile named like a
+    /// built-in one replaces it
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ToolProfile>,
+}
+
+fn default_profile() -> String {
+    FULL.to_string()
+}
+
+impl Default for ToolProfilesConfig {
+    fn default() -> Self {
+        Self {
+            default: default_profile(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl ToolProfilesConfig {
+    /// The profile called `name`
+    pub fn get(&self, name: &str) -> Result<ToolProfile, ConfigError> {
+        let profile = self
+            .profiles
+            .get(name)
+            .cloned()
+            .or_else(|| ToolProfile::builtin(name))
+            .ok_or_else(|| {
+                ConfigError::Validation(format!(
+                    "Unknown tool profile {}. Available: {}",
+                    name,
+                    self.names().join(", ")
+                ))
+            })?;
+        profile.validate(name)?;
+        Ok(profile)
+    }
+
+    /// These profiles with `name`, if given, as the default; for `--tools`
+    pub fn with_default(mut self, name: Option<String>) -> Self {
+        if let Some(name) = name {
+            self.default = name;
+        }
+        self
+    }
+
+    /// The profile commands use unless a workflow step picks another one
+    pub fn selected(&self) -> Result<ToolProfile, ConfigError> {
+        self.get(&self.default)
+    }
+
+    /// Names of the built-in and configured profiles, sorted
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = [READ_ONLY, DOCS_WRITER, FULL]
+            .into_iter()
+            .map(str::to_string)
+            .chain(self.profiles.keys().cloned())
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
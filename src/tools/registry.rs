@@ -1,8 +1,14 @@
+use crate::error::{ConfigError, McpError};
+use crate::mcp::McpServerConfig;
+use crate::mcp::client::{McpClient, McpToolExecutor};
 use crate::providers::types::Tool;
 use crate::tools::ToolExecutor;
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::custom_ops::{CustomTool, CustomToolConfig};
 use std::collections::HashMap;
 use std::sync::Arc;
 
+#[derive(Clone)]
 pub struct ToolRegistry {
     tools: HashMap<String, Tool>,
     executors: HashMap<String, Arc<dyn ToolExecutor>>,
@@ -32,6 +38,78 @@ impl ToolRegistry {
     pub fn list_tools(&self) -> Vec<Tool> {
         self.tools.values().cloned().collect()
     }
+
+    /// A registry holding only the named tools (unknown names are ignored)
+    pub fn subset(&self, names: &[String]) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        for name in names {
+            if let (Some(tool), Some(executor)) = (self.get_tool(name), self.get_executor(name)) {
+                registry.register(tool.clone(), executor);
+            }
+        }
+        registry
+    }
+
+    /// Register the command tools declared in `custom_tools`. They may not
+    /// replace a tool that is already registered.
+    pub fn register_custom_tools(
+        &mut self,
+        tools: &[CustomToolConfig],
+        runner: &Arc<CommandRunner>,
+    ) -> Result<(), ConfigError> {
+        for config in tools {
+            if self.tools.contains_key(&config.name) {
+                return Err(ConfigError::Validation(format!(
+                    "custom tool {}: a tool with this name already exists",
+                    config.name
+                )));
+            }
+            let tool = CustomTool::new(config.clone(), runner.clone())?;
+            self.register(tool.definition(), Arc::new(tool));
+        }
+        Ok(())
+    }
+
+    /// Register every tool of a connected MCP server, returning their names.
+    /// Tools whose names are already taken are skipped.
+    pub async fn import_mcp(&mut self, client: Arc<McpClient>) -> Result<Vec<String>, McpError> {
+        let mut names = Vec::new();
+        for tool in client.list_tools().await? {
+            let definition = McpToolExecutor::definition(client.name(), &tool);
+            if self.tools.contains_key(&definition.name) {
+                tracing::warn!("Skipping MCP tool {}: name already taken", definition.name);
+                continue;
+            }
+            names.push(definition.name.clone());
+            self.register(
+                definition,
+                Arc::new(McpToolExecutor::new(client.clone(), &tool)),
+            );
+        }
+        Ok(names)
+    }
+
+    /// Start the configured MCP servers and register their tools. A server
+    /// that fails to start is reported and left out.
+    pub async fn import_mcp_servers(&mut self, servers: &[McpServerConfig]) {
+        for server in servers {
+            let imported = match McpClient::connect(server).await {
+                Ok(client) => self.import_mcp(Arc::new(client)).await,
+                Err(e) => Err(e),
+            };
+            match imported {
+                Ok(names) => tracing::info!(
+                    "Registered {} tool(s) from MCP server {}",
+                    names.len(),
+                    server.name
+                ),
+                Err(e) => {
+                    tracing::warn!("{}", e);
+                    eprintln!("Warning: MCP server {} is unavailable: {}", server.name, e);
+                }
+            }
+        }
+    }
 }
 
 impl Default for ToolRegistry {
@@ -0,0 +1,222 @@
+use crate::error::{WorkflowError, XzardgzError};
+use schemars::JsonSchema;
+use schemars::generate::SchemaSettings;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// One way in which tool arguments do not match the tool's schema
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SchemaViolation {
+    /// JSON pointer to the offending value, e.g. `/paths/0`; empty for the arguments object
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() {
+            "arguments"
+        } else {
+            &self.path
+        };
+        write!(f, "{}: {}", path, self.message)
+    }
+}
+
+/// Parameters schema for a typed argument struct. Doc comments on the fields
+/// become the property descriptions the model sees.
+pub fn schema_for<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft2020_12().into_generator();
+    let mut schema = generator.into_root_schema_for::<T>().to_value();
+    if let Some(object) = schema.as_object_mut() {
+        object.remove("$schema");
+        object.remove("title");
+        object.remove("description");
+        object
+            .entry("required")
+            .or_insert_with(|| Value::Array(Vec::new()));
+        // Optional fields are simply left out, so `Option<T>` needs no "null" type
+        for property in object
+            .get_mut("properties")
+            .and_then(Value::as_object_mut)
+            .into_iter()
+            .flat_map(|properties| properties.values_mut())
+        {
+            if let Some(Value::Array(types)) = property.get_mut("type") {
+                types.retain(|name| name != "null");
+                if types.len() == 1 {
+                    property["type"] = types.remove(0);
+                }
+            }
+        }
+    }
+    schema
+}
+
+/// Deserialize validated arguments into a tool's typed argument struct
+pub fn parse_args<T: DeserializeOwned>(params: Value) -> Result<T, XzardgzError> {
+    serde_json::from_value(params).map_err(|e| {
+        XzardgzError::Workflow(WorkflowError::Execution(format!(
+            "Invalid tool arguments: {}",
+            e
+        )))
+    })
+}
+
+/// Check `value` against `schema`, filling in defaults for missing
+/// properties. Supports the keywords tool schemas use: `type`, `enum`,
+/// `minimum`, `maximum`, `properties`, `required`, `additionalProperties`,
+/// `items` and `default`. Unknown keywords are ignored.
+pub fn validate(schema: &Value, value: &mut Value) -> Result<(), Vec<SchemaViolation>> {
+    let mut violations = Vec::new();
+    check(schema, value, "", &mut violations);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
+
+fn check(schema: &Value, value: &mut Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let mut violation = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(types) = schema.get("type") {
+        let types: Vec<&str> = match types {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|name| is_type(value, name)) {
+            violation(format!(
+                "expected {}, got {}",
+                types.join(" or "),
+                kind(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+        violation(format!("must be one of {}", allowed.join(", ")));
+        return;
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64)
+            && number < minimum
+        {
+            violation(format!("must be at least {}", minimum));
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64)
+            && number > maximum
+        {
+            violation(format!("must be at most {}", maximum));
+        }
+    }
+
+    match value {
+        Value::Object(object) => check_object(schema, object, path, violations),
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter_mut().enumerate() {
+                    check(
+                        item_schema,
+                        item,
+                        &format!("{}/{}", path, index),
+                        violations,
+                    );
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_object(
+    schema: &Value,
+    object: &mut Map<String, Value>,
+    path: &str,
+    violations: &mut Vec<SchemaViolation>,
+) {
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|names| names.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let empty = Map::new();
+    let properties = schema
+        .get("properties")
+        .and_then(Value::as_object)
+        .unwrap_or(&empty);
+
+    // Models often send null for optional arguments they mean to leave out
+    object.retain(|name, value| !value.is_null() || required.contains(&name.as_str()));
+
+    for (name, property) in properties {
+        match object.get_mut(name) {
+            Some(value) => check(property, value, &format!("{}/{}", path, name), violations),
+            None => {
+                if let Some(default) = property.get("default") {
+                    object.insert(name.clone(), default.clone());
+                }
+            }
+        }
+    }
+
+    for name in required {
+        if !object.contains_key(name) {
+            violations.push(SchemaViolation {
+                path: format!("{}/{}", path, name),
+                message: "is required".to_string(),
+            });
+        }
+    }
+
+    if schema.get("additionalProperties") == Some(&Value::Bool(false)) {
+        for name in object.keys().filter(|name| !properties.contains_key(*name)) {
+            let known: Vec<&str> = properties.keys().map(String::as_str).collect();
+            violations.push(SchemaViolation {
+                path: format!("{}/{}", path, name),
+                message: format!("is not a known argument (expected {})", known.join(", ")),
+            });
+        }
+    }
+}
+
+fn is_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn kind(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(_) => "boolean".to_string(),
+        Value::Number(number) if number.is_f64() => format!("number {}", number),
+        Value::Number(number) => format!("integer {}", number),
+        Value::String(text) if text.chars().count() > 40 => {
+            format!("string {:?}...", text.chars().take(40).collect::<String>())
+        }
+        Value::String(text) => format!("string {:?}", text),
+        Value::Array(_) => "array".to_string(),
+        Value::Object(_) => "object".to_string(),
+    }
+}
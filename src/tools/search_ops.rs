@@ -0,0 +1,318 @@
+use crate::error::XzardgzError;
+use crate::providers::types::Tool;
+use crate::repository::scanner::{RepositoryScanner, ScanEntry};
+use crate::tools::schema::{parse_args, schema_for};
+use crate::tools::workspace::Workspace;
+use crate::tools::{ToolExecutor, ToolResult};
+use async_trait::async_trait;
+use globset::GlobBuilder;
+use regex::RegexBuilder;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Entries listed before the rest are elided
+const MAX_LISTED_ENTRIES: usize = 500;
+const MAX_DEPTH: u64 = 5;
+const MAX_GLOB_RESULTS: usize = 200;
+const DEFAULT_GREP_RESULTS: u64 = 50;
+const MAX_GREP_RESULTS: u64 = 200;
+const MAX_CONTEXT_LINES: u64 = 5;
+
+/// Walk `path` (relative to the workspace) the way `RepositoryScanner` does,
+/// keeping only entries the workspace lets the model read
+pub(crate) fn walk(
+    workspace: &Workspace,
+    ignore_patterns: &[String],
+    path: &str,
+    depth: Option<usize>,
+) -> Result<Vec<ScanEntry>, String> {
+    let root = workspace
+        .resolve_read(path)
+        .map_err(|violation| violation.to_string())?;
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let mut scanner = RepositoryScanner::new(&root, ignore_patterns.to_vec());
+    if let Some(depth) = depth {
+        scanner = scanner.with_max_depth(depth);
+    }
+    let mut entries: Vec<ScanEntry> = scanner
+        .entries()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|entry| workspace.is_readable(&entry.path))
+        // Hidden files are walked, but git's own metadata is only noise
+        .filter(|entry| {
+            !entry
+                .path
+                .strip_prefix(workspace.root())
+                .is_ok_and(|relative| relative.components().any(|c| c.as_os_str() == ".git"))
+        })
+        .collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(entries)
+}
+
+pub(crate) fn elide(mut lines: Vec<String>, limit: usize) -> String {
+    if lines.len() > limit {
+        let more = lines.len() - limit;
+        lines.truncate(limit);
+        lines.push(format!("... and {} more", more));
+    }
+    lines.join("\n")
+}
+
+pub struct ListDirectoryTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ListDirectoryArgs {
+    /// Directory relative to the workspace root (default: the root)
+    pub path: Option<String>,
+    /// How many levels to descend (default: 1, max: 5)
+    pub depth: Option<u64>,
+}
+
+impl ListDirectoryTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "list_directory".to_string(),
+            description: "List files and directories, skipping anything ignored by .gitignore"
+                .to_string(),
+            parameters: schema_for::<ListDirectoryArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for ListDirectoryTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: ListDirectoryArgs = parse_args(params)?;
+        let path = args.path.as_deref().unwrap_or(".");
+        let depth = args.depth.unwrap_or(1).clamp(1, MAX_DEPTH) as usize;
+
+        let entries = match walk(&self.workspace, &self.ignore_patterns, path, Some(depth)) {
+            Ok(entries) => entries,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        if entries.is_empty() {
+            return Ok(ToolResult::success(format!("{} is empty", path)));
+        }
+
+        let lines = entries
+            .iter()
+            .map(|entry| {
+                let relative = self.workspace.relative(&entry.path);
+                if entry.is_dir {
+                    format!("{}/", relative)
+                } else {
+                    relative
+                }
+            })
+            .collect();
+        Ok(ToolResult::success(elide(lines, MAX_LISTED_ENTRIES)))
+    }
+}
+
+pub struct GlobFilesTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GlobFilesArgs {
+    /// Glob relative to the workspace root; * does not cross directories, ** does
+    pub pattern: String,
+}
+
+impl GlobFilesTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "glob_files".to_string(),
+            description: "Find files whose path matches a glob such as src/**/*.rs".to_string(),
+            parameters: schema_for::<GlobFilesArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GlobFilesTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GlobFilesArgs = parse_args(params)?;
+        let pattern = args.pattern.as_str();
+        let matcher = match GlobBuilder::new(pattern).literal_separator(true).build() {
+            Ok(glob) => glob.compile_matcher(),
+            Err(e) => return Ok(ToolResult::failure(format!("Invalid glob: {}", e))),
+        };
+
+        let entries = match walk(&self.workspace, &self.ignore_patterns, ".", None) {
+            Ok(entries) => entries,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let matches: Vec<String> = entries
+            .iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| self.workspace.relative(&entry.path))
+            .filter(|relative| matcher.is_match(relative))
+            .collect();
+
+        if matches.is_empty() {
+            return Ok(ToolResult::success(format!("No files match {}", pattern)));
+        }
+        Ok(ToolResult::success(elide(matches, MAX_GLOB_RESULTS)))
+    }
+}
+
+pub struct GrepTool {
+    workspace: Arc<Workspace>,
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GrepArgs {
+    /// Regular expression (Rust regex syntax)
+    pub pattern: String,
+    /// Directory to search, relative to the workspace root (default: the root)
+    pub path: Option<String>,
+    /// Only search files whose path matches this glob, e.g. **/*.rs
+    pub glob: Option<String>,
+    /// Lines of context around each match (default: 0, max: 5)
+    pub context: Option<u64>,
+    /// Maximum matching lines to return (default: 50, max: 200)
+    pub max_results: Option<u64>,
+    /// Ignore case (default: false)
+    #[serde(default)]
+    pub case_insensitive: bool,
+}
+
+impl GrepTool {
+    pub fn new(workspace: Arc<Workspace>, ignore_patterns: Vec<String>) -> Self {
+        Self {
+            workspace,
+            ignore_patterns,
+        }
+    }
+
+    pub fn definition() -> Tool {
+        Tool {
+            name: "grep".to_string(),
+            description: "Search file contents with a regular expression. Matches are shown as \
+                path:line: text, context lines as path-line- text."
+                .to_string(),
+            parameters: schema_for::<GrepArgs>(),
+        }
+    }
+}
+
+#[async_trait]
+impl ToolExecutor for GrepTool {
+    async fn execute(&self, params: Value) -> Result<ToolResult, XzardgzError> {
+        let args: GrepArgs = parse_args(params)?;
+        let regex = match RegexBuilder::new(&args.pattern)
+            .case_insensitive(args.case_insensitive)
+            .build()
+        {
+            Ok(regex) => regex,
+            Err(e) => return Ok(ToolResult::failure(format!("Invalid regex: {}", e))),
+        };
+        let filter = match args.glob.as_deref() {
+            Some(glob) => match GlobBuilder::new(glob).literal_separator(true).build() {
+                Ok(glob) => Some(glob.compile_matcher()),
+                Err(e) => return Ok(ToolResult::failure(format!("Invalid glob: {}", e))),
+            },
+            None => None,
+        };
+        let context = args.context.unwrap_or(0).min(MAX_CONTEXT_LINES) as usize;
+        let max_results = args
+            .max_results
+            .unwrap_or(DEFAULT_GREP_RESULTS)
+            .clamp(1, MAX_GREP_RESULTS) as usize;
+
+        let path = args.path.as_deref().unwrap_or(".");
+        let entries = match walk(&self.workspace, &self.ignore_patterns, path, None) {
+            Ok(entries) => entries,
+            Err(e) => return Ok(ToolResult::failure(e)),
+        };
+        let files: Vec<PathBuf> = entries
+            .into_iter()
+            .filter(|entry| !entry.is_dir)
+            .map(|entry| entry.path)
+            .filter(|path| {
+                filter
+                    .as_ref()
+                    .is_none_or(|glob| glob.is_match(self.workspace.relative(path)))
+            })
+            .collect();
+
+        let mut blocks = Vec::new();
+        let mut found = 0;
+        'files: for file in files {
+            // Binary and non-UTF-8 files are skipped
+            let Ok(content) = std::fs::read_to_string(&file) else {
+                continue;
+            };
+            let relative = self.workspace.relative(&file);
+            let lines: Vec<&str> = content.lines().collect();
+            let mut last_shown: Option<usize> = None;
+
+            for (index, line) in lines.iter().enumerate() {
+                if !regex.is_match(line) {
+                    continue;
+                }
+                if found == max_results {
+                    blocks.push(format!("(stopped after {} matches)", max_results));
+                    break 'files;
+                }
+                found += 1;
+
+                let start = index.saturating_sub(context);
+                let start = last_shown.map_or(start, |shown| start.max(shown + 1));
+                let end = (index + context).min(lines.len() - 1);
+                // Like grep, separate non-adjacent groups when showing context
+                let adjacent = last_shown.is_some_and(|shown| start <= shown + 1);
+                if context > 0 && !adjacent && !blocks.is_empty() {
+                    blocks.push("--".to_string());
+                }
+                for (number, text) in lines.iter().enumerate().take(end + 1).skip(start) {
+                    let separator = if regex.is_match(text) { ':' } else { '-' };
+                    blocks.push(format!(
+                        "{}{sep}{}{sep} {}",
+                        relative,
+                        number + 1,
+                        text,
+                        sep = separator
+                    ));
+                }
+                last_shown = Some(end);
+            }
+        }
+
+        if found == 0 {
+            return Ok(ToolResult::success(format!(
+                "No matches for {}",
+                args.pattern
+            )));
+        }
+        Ok(ToolResult::success(blocks.join("\n")))
+    }
+}
@@ -0,0 +1,236 @@
+use crate::error::{ConfigError, XzardgzError};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceConfig {
+    /// Directory the tools are confined to (default: the current directory)
+    #[serde(default)]
+    pub root: Option<String>,
+    /// Globs, relative to the root, that tools may neither read nor write
+    #[serde(default = "default_deny")]
+    pub deny: Vec<String>,
+    /// Globs, relative to the root, that tools may read but not write
+    #[serde(default = "default_read_only")]
+    pub read_only: Vec<String>,
+    /// Keep the previous version of a file as `<name>.bak` (or `.bak.1`, ...)
+    /// before tools change it
+    #[serde(default)]
+    pub backups: bool,
+}
+
+fn default_deny() -> Vec<String> {
+    [".env", ".env.*", "*.pem", "*.key", "id_rsa*", "id_ed25519*"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_read_only() -> Vec<String> {
+    vec![".git".to_string()]
+}
+
+impl Default for WorkspaceConfig {
+    fn default() -> Self {
+        Self {
+            root: None,
+            deny: default_deny(),
+            read_only: default_read_only(),
+            backups: false,
+        }
+    }
+}
+
+/// Why a tool may not touch a path. Tools report these as failures.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PathViolation {
+    #[error("{0} is outside the workspace")]
+    OutsideWorkspace(String),
+    #[error("{0} goes through a symlink")]
+    Symlink(String),
+    #[error("Access to {0} is denied by the workspace policy")]
+    Denied(String),
+    #[error("{0} is read-only")]
+    ReadOnly(String),
+}
+
+/// Directory that file tools are confined to, shared by all tools
+#[derive(Debug)]
+pub struct Workspace {
+    root: PathBuf,
+    deny: GlobSet,
+    read_only: GlobSet,
+    backups: bool,
+}
+
+impl Workspace {
+    pub fn new<P: AsRef<Path>>(root: P, config: &WorkspaceConfig) -> Result<Self, XzardgzError> {
+        let root = root.as_ref().canonicalize().map_err(|e| {
+            ConfigError::Validation(format!("Invalid workspace root {:?}: {}", root.as_ref(), e))
+        })?;
+        Ok(Self {
+            root,
+            deny: build_globs(&config.deny)?,
+            read_only: build_globs(&config.read_only)?,
+            backups: config.backups,
+        })
+    }
+
+    /// Workspace at the configured root, or the current directory
+    pub fn from_config(config: &WorkspaceConfig) -> Result<Self, XzardgzError> {
+        let root = config.root.as_deref().unwrap_or(".");
+        Self::new(root, config)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Absolute path for reading `path`, which is relative to the root or
+    /// absolute inside it
+    pub fn resolve_read(&self, path: &str) -> Result<PathBuf, PathViolation> {
+        let (resolved, relative) = self.resolve(path)?;
+        if self.matches(&self.deny, &relative) {
+            return Err(PathViolation::Denied(path.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    /// Like `resolve_read`, and also refuses read-only paths
+    pub fn resolve_write(&self, path: &str) -> Result<PathBuf, PathViolation> {
+        let resolved = self.resolve_read(path)?;
+        let relative = resolved.strip_prefix(&self.root).unwrap_or(&resolved);
+        if self.matches(&self.read_only, relative) {
+            return Err(PathViolation::ReadOnly(path.to_string()));
+        }
+        Ok(resolved)
+    }
+
+    /// Copy an existing file to `<name>.bak` if backups are enabled, before a
+    /// tool overwrites it. Earlier backups are kept by moving on to
+    /// `<name>.bak.1`, `<name>.bak.2` and so on. Returns the backup path.
+    pub fn backup(&self, path: &Path) -> std::io::Result<Option<PathBuf>> {
+        if !self.backups || !path.is_file() {
+            return Ok(None);
+        }
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let mut attempt = 0;
+        loop {
+            let candidate = match attempt {
+                0 => path.with_file_name(format!("{}.bak", name)),
+                n => path.with_file_name(format!("{}.bak.{}", name, n)),
+            };
+            // The backup is written like any other file, so the policy applies to it too
+            let backup = self
+                .resolve_write(&candidate.to_string_lossy())
+                .map_err(|violation| {
+                    std::io::Error::new(std::io::ErrorKind::PermissionDenied, violation)
+                })?;
+            // `create_new` also refuses a dangling symlink in place of the backup
+            let mut target = match std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&backup)
+            {
+                Ok(target) => target,
+                Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                    attempt += 1;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            std::io::copy(&mut std::fs::File::open(path)?, &mut target)?;
+            return Ok(Some(backup));
+        }
+    }
+
+    /// Whether a path found inside the root (e.g. by a directory walk) may be read
+    pub fn is_readable(&self, path: &Path) -> bool {
+        match path.strip_prefix(&self.root) {
+            Ok(relative) => !self.matches(&self.deny, relative),
+            Err(_) => false,
+        }
+    }
+
+    /// `path` relative to the root, as shown to the model
+    pub fn relative(&self, path: &Path) -> String {
+        path.strip_prefix(&self.root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+    }
+
+    /// Normalize `path` lexically, then walk it from the root so that no
+    /// existing component is a symlink
+    fn resolve(&self, path: &str) -> Result<(PathBuf, PathBuf), PathViolation> {
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.root.join(requested)
+        };
+
+        let mut normalized = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::ParentDir => {
+                    if !normalized.pop() {
+                        return Err(PathViolation::OutsideWorkspace(path.to_string()));
+                    }
+                }
+                Component::CurDir => {}
+                other => normalized.push(other),
+            }
+        }
+        let relative = normalized
+            .strip_prefix(&self.root)
+            .map_err(|_| PathViolation::OutsideWorkspace(path.to_string()))?
+            .to_path_buf();
+
+        let mut current = self.root.clone();
+        for component in relative.components() {
+            current.push(component);
+            match current.symlink_metadata() {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    return Err(PathViolation::Symlink(path.to_string()));
+                }
+                Ok(_) => {}
+                // The rest of the path does not exist yet (e.g. a new file)
+                Err(_) => break,
+            }
+        }
+        Ok((normalized, relative))
+    }
+
+    /// A glob matches a path when it matches the path or any directory above it
+    fn matches(&self, globs: &GlobSet, relative: &Path) -> bool {
+        relative
+            .ancestors()
+            .filter(|ancestor| !ancestor.as_os_str().is_empty())
+            .any(|ancestor| globs.is_match(ancestor))
+    }
+}
+
+fn build_globs(patterns: &[String]) -> Result<GlobSet, XzardgzError> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = pattern.trim_end_matches('/');
+        // Like .gitignore, a pattern without a slash matches at any depth
+        let expanded = if pattern.contains('/') {
+            vec![pattern.to_string()]
+        } else {
+            vec![pattern.to_string(), format!("**/{}", pattern)]
+        };
+        for pattern in expanded {
+            let glob = Glob::new(&pattern).map_err(|e| {
+                ConfigError::Validation(format!("Invalid workspace glob {:?}: {}", pattern, e))
+            })?;
+            builder.add(glob);
+        }
+    }
+    builder
+        .build()
+        .map_err(|e| ConfigError::Validation(format!("Invalid workspace globs: {}", e)).into())
+}
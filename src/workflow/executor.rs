@@ -1,13 +1,33 @@
+use crate::agent::budget::{BudgetTracker, Exceeded};
 use crate::agent::core::Agent;
-use crate::error::WorkflowError;
+use crate::agent::review::ReviewReport;
+use crate::error::{WorkflowError, XzardgzError};
+use crate::tools::command_ops::CommandRunner;
+use crate::tools::profile::{ToolProfile, ToolProfilesConfig};
 use crate::workflow::plan::{Action, Plan, WorkflowStep};
+use crate::workflow::state::RunState;
 use std::collections::HashSet;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+/// Critique results for the answer an agent step produced
+#[derive(Debug, Clone)]
+pub struct StepReview {
+    pub step: String,
+    pub report: ReviewReport,
+}
 
 pub struct WorkflowExecutor {
     agent: Arc<Agent>,
     plan: Plan,
     completed_steps: HashSet<String>,
+    interrupted_step: Option<String>,
+    cancel: CancellationToken,
+    reviews: Vec<StepReview>,
+    budget: Option<Arc<BudgetTracker>>,
+    commands: Option<Arc<CommandRunner>>,
+    dry_run: bool,
+    tool_profiles: ToolProfilesConfig,
 }
 
 impl WorkflowExecutor {
@@ -16,10 +36,88 @@ impl WorkflowExecutor {
             agent,
             plan,
             completed_steps: HashSet::new(),
+            interrupted_step: None,
+            cancel: CancellationToken::new(),
+            reviews: Vec::new(),
+            budget: None,
+            commands: None,
+            dry_run: false,
+            tool_profiles: ToolProfilesConfig::default(),
+        }
+    }
+
+    /// Run `execute_command` steps with `runner`; without one they fail
+    pub fn with_command_runner(mut self, runner: Arc<CommandRunner>) -> Self {
+        self.commands = Some(runner);
+        self
+    }
+
+    /// Print `execute_command` steps instead of running them
+    pub fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Resolve the `tools` of steps in `profiles`. Its default profile is the
+    /// run's, which `execute_command` steps must also satisfy; the agent is
+    /// expected to hold only that profile's tools already.
+    pub fn with_tool_profiles(mut self, profiles: ToolProfilesConfig) -> Self {
+        self.tool_profiles = profiles;
+        self
+    }
+
+    /// Stop at the current step once `cancel` fires
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    /// Stop before the next step once the workflow's time budget is spent. The
+    /// same tracker should be shared with the agent to limit work within steps.
+    pub fn with_budget(mut self, budget: Arc<BudgetTracker>) -> Self {
+        self.budget = Some(budget);
+        self
+    }
+
+    /// Skip the steps an earlier, interrupted run already completed. Fails
+    /// unless `state` was saved for this very plan.
+    pub fn resume_from(mut self, state: RunState) -> Result<Self, WorkflowError> {
+        state.check_plan(&self.plan)?;
+        self.completed_steps = state.completed_steps.into_iter().collect();
+        Ok(self)
+    }
+
+    /// Progress so far, including the step that was interrupted (if any)
+    pub fn state(&self) -> RunState {
+        let completed_steps = self
+            .plan
+            .steps
+            .iter()
+            .filter(|step| self.completed_steps.contains(&step.id))
+            .map(|step| step.id.clone())
+            .collect();
+        RunState {
+            plan: self.plan.name.clone(),
+            plan_hash: RunState::plan_hash(&self.plan),
+            completed_steps,
+            interrupted_step: self.interrupted_step.clone(),
         }
     }
 
+    /// Review results of the agent steps run so far, in execution order
+    pub fn reviews(&self) -> &[StepReview] {
+        &self.reviews
+    }
+
     pub async fn execute(&mut self) -> Result<(), WorkflowError> {
+        // An unknown profile should stop the plan before any step has run
+        self.tool_profiles
+            .selected()
+            .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+        for step in &self.plan.steps {
+            self.step_profile(step)?;
+        }
+
         // Simple execution loop: find executable steps, execute them, repeat.
         loop {
             let executable_steps = self.get_executable_steps();
@@ -34,8 +132,35 @@ impl WorkflowExecutor {
             }
 
             for step in executable_steps {
+                if self.cancel.is_cancelled() {
+                    self.interrupted_step = Some(step.id.clone());
+                    return Err(WorkflowError::Interrupted(step.id.clone()));
+                }
+                if let Some(Err(exceeded)) = self.budget.as_ref().map(|b| b.check_time()) {
+                    self.interrupted_step = Some(step.id.clone());
+                    return Err(WorkflowError::BudgetExceeded {
+                        step: step.id.clone(),
+                        reason: exceeded.to_string(),
+                        partial: None,
+                    });
+                }
                 println!("Executing step: {}", step.id);
-                self.execute_step(&step).await?;
+                let reviewed = self.agent.reviews().len();
+                if let Err(e) = self.execute_step(&step).await {
+                    if matches!(
+                        e,
+                        WorkflowError::Interrupted(_) | WorkflowError::BudgetExceeded { .. }
+                    ) {
+                        self.interrupted_step = Some(step.id.clone());
+                    }
+                    return Err(e);
+                }
+                for report in self.agent.reviews().into_iter().skip(reviewed) {
+                    self.reviews.push(StepReview {
+                        step: step.id.clone(),
+                        report,
+                    });
+                }
                 self.completed_steps.insert(step.id.clone());
             }
         }
@@ -56,6 +181,15 @@ impl WorkflowExecutor {
             .collect()
     }
 
+    /// The profile a step's `tools` names, if any
+    fn step_profile(&self, step: &WorkflowStep) -> Result<Option<ToolProfile>, WorkflowError> {
+        step.tools
+            .as_deref()
+            .map(|name| self.tool_profiles.get(name))
+            .transpose()
+            .map_err(|e| WorkflowError::Execution(format!("Step {}: {}", step.id, e)))
+    }
+
     async fn execute_step(&self, step: &WorkflowStep) -> Result<(), WorkflowError> {
         match &step.action {
             Action::ScanRepository => {
@@ -73,17 +207,98 @@ impl WorkflowExecutor {
             }
             Action::ExecuteCommand { command } => {
                 println!("Executing command: {}", command);
-                // TODO: Execute command safely
-                Ok(())
+                self.execute_command(step, command).await
             }
             Action::AgentTask { prompt } => {
                 println!("Agent task: {}", prompt);
-                self.agent
-                    .run(prompt)
-                    .await
-                    .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+                let result = match self.step_profile(step)? {
+                    Some(profile) => {
+                        self.agent
+                            .run_with_profile(prompt, &self.cancel, &profile)
+                            .await
+                    }
+                    None => self.agent.run_with_cancel(prompt, &self.cancel).await,
+                };
+                result.map_err(|e| match e {
+                    XzardgzError::Cancelled => WorkflowError::Interrupted(step.id.clone()),
+                    XzardgzError::BudgetExceeded {
+                        kind,
+                        used,
+                        limit,
+                        partial,
+                    } => WorkflowError::BudgetExceeded {
+                        step: step.id.clone(),
+                        reason: Exceeded { kind, used, limit }.to_string(),
+                        partial,
+                    },
+                    e => WorkflowError::Execution(e.to_string()),
+                })?;
                 Ok(())
             }
         }
     }
+
+    /// Run a plan's command under the same policy as the `run_command` tool.
+    /// Shell syntax is only honoured when `commands.allow_shell` is set.
+    async fn execute_command(
+        &self,
+        step: &WorkflowStep,
+        command: &str,
+    ) -> Result<(), WorkflowError> {
+        // Commands are what `run_command` would run, so its profile rules apply
+        let profiles = [
+            Some(self.tool_profiles.default.as_str()),
+            step.tools.as_deref(),
+        ];
+        for name in profiles.into_iter().flatten() {
+            let profile = self
+                .tool_profiles
+                .get(name)
+                .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+            if !profile.allows("run_command", true) {
+                return Err(WorkflowError::Execution(format!(
+                    "Step {} runs a command, but tool profile {} does not allow run_command",
+                    step.id, name
+                )));
+            }
+        }
+        let runner = self
+            .commands
+            .as_ref()
+            .ok_or_else(|| WorkflowError::Execution("No command runner configured".to_string()))?;
+        let spec = runner
+            .parse(command, runner.config().allow_shell)
+            .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+        let dir = runner
+            .working_dir(None)
+            .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+        if self.dry_run {
+            println!("[dry run] Would run: {}", spec.display());
+            return Ok(());
+        }
+
+        let output = tokio::select! {
+            output = runner.run(&spec, &dir, None) => {
+                output.map_err(|e| WorkflowError::Execution(e.to_string()))?
+            }
+            _ = self.cancel.cancelled() => {
+                return Err(WorkflowError::Interrupted(step.id.clone()));
+            }
+        };
+        print!("{}", output.stdout);
+        if output.success() {
+            return Ok(());
+        }
+        let status = match output.exit_code {
+            _ if output.timed_out => "timed out".to_string(),
+            Some(code) => format!("exited with code {}", code),
+            None => "was killed".to_string(),
+        };
+        Err(WorkflowError::Execution(format!(
+            "Command `{}` {}: {}",
+            command,
+            status,
+            output.stderr.trim()
+        )))
+    }
 }
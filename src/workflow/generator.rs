@@ -0,0 +1,116 @@
+use crate::agent::core::Agent;
+use crate::error::{WorkflowError, XzardgzError};
+use crate::workflow::parser::parse_plan;
+use crate::workflow::plan::Plan;
+use std::sync::Arc;
+
+const PLAN_FORMAT_HINT: &str = r#"name: "Short plan name"
+description: "What the plan achieves"
+repository: "."
+steps:
+  - id: "scan"
+    description: "Scan repository structure"
+    action:
+      type: scan_repository
+      params: null
+    dependencies: []
+  - id: "overview"
+    description: "Write the architecture explanation"
+    action:
+      type: agent_task
+      params:
+        prompt: "Read the main modules and explain the architecture"
+    dependencies: ["scan"]
+  - id: "reference"
+    description: "Generate the CLI reference"
+    action:
+      type: generate_docs
+      params:
+        category: reference   # tutorial, how_to, explanation or reference
+    dependencies: ["overview"]
+deliverables:
+  - name: "Architecture"
+    description: "Architecture explanation"
+    path: "docs/explanation/architecture.md""#;
+
+/// Turns a natural-language goal into a validated workflow `Plan`
+pub struct PlanGenerator {
+    agent: Arc<Agent>,
+    max_repairs: usize,
+}
+
+impl PlanGenerator {
+    pub fn new(agent: Arc<Agent>, max_repairs: usize) -> Self {
+        Self { agent, max_repairs }
+    }
+
+    /// Ask the agent for a plan, feeding parse and validation errors back until
+    /// it produces a valid one or runs out of repair attempts.
+    pub async fn generate(
+        &self,
+        goal: &str,
+        repository_context: &str,
+    ) -> Result<Plan, XzardgzError> {
+        let mut prompt = self.build_prompt(goal, repository_context);
+
+        for attempt in 0..=self.max_repairs {
+            let response = self.agent.run(&prompt).await?;
+            let error = match Self::parse(&response) {
+                Ok(plan) => match plan.validate() {
+                    Ok(()) => return Ok(plan),
+                    Err(e) => e,
+                },
+                Err(e) => e.to_string(),
+            };
+
+            tracing::warn!(
+                "Generated plan invalid (attempt {}): {}",
+                attempt + 1,
+                error
+            );
+            prompt = format!(
+                "The plan you returned is invalid: {}\nReturn the complete corrected plan as YAML only.",
+                error
+            );
+        }
+
+        Err(WorkflowError::Parse(format!(
+            "No valid plan after {} repair attempts",
+            self.max_repairs
+        ))
+        .into())
+    }
+
+    /// Accept raw YAML as well as YAML wrapped in a markdown code block
+    pub fn parse(response: &str) -> Result<Plan, XzardgzError> {
+        if response.contains("```") {
+            parse_plan(response, "md")
+        } else {
+            parse_plan(response, "yaml")
+        }
+    }
+
+    fn build_prompt(&self, goal: &str, repository_context: &str) -> String {
+        format!(
+            r#"Create a workflow plan for this goal: {goal}
+
+Inspect the repository with your tools before deciding on steps.
+
+Repository files:
+{repository_context}
+
+Output Format:
+Return only a YAML plan in this format (no commentary):
+{format}
+
+Rules:
+- Step ids must be unique
+- Dependencies must refer to ids of other steps in the plan
+- Prefer agent_task steps with specific prompts that name the files involved
+"#,
+            goal = goal,
+            repository_context = repository_context,
+            format = PLAN_FORMAT_HINT
+        )
+    }
+}
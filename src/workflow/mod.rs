@@ -1,3 +1,5 @@
 pub mod executor;
+pub mod generator;
 pub mod parser;
 pub mod plan;
+pub mod state;
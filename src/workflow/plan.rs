@@ -18,6 +18,9 @@ pub struct WorkflowStep {
     pub action: Action,
     #[serde(default)]
     pub dependencies: Vec<String>,
+    /// Tool profile for this step; it can only narrow the run's profile
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tools: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -0,0 +1,66 @@
+use crate::error::WorkflowError;
+use crate::workflow::plan::Plan;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+
+/// Progress of a workflow run, saved when it is interrupted so it can be resumed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RunState {
+    pub plan: String,
+    /// `RunState::plan_hash` of the plan the progress belongs to
+    #[serde(default)]
+    pub plan_hash: String,
+    pub completed_steps: Vec<String>,
+    #[serde(default)]
+    pub interrupted_step: Option<String>,
+}
+
+impl RunState {
+    /// `plans/docs.yaml` keeps its state in `plans/docs.state.json`
+    pub fn path_for(plan_path: &Path) -> PathBuf {
+        plan_path.with_extension("state.json")
+    }
+
+    /// SHA-256 of the parsed plan, so reformatting the file keeps the hash but
+    /// changing a step does not
+    pub fn plan_hash(plan: &Plan) -> String {
+        let canonical = serde_json::to_vec(plan).unwrap_or_default();
+        Sha256::digest(&canonical)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// Refuse progress saved for a different plan, or an edited version of
+    /// this one, whose completed steps may no longer mean the same thing
+    pub fn check_plan(&self, plan: &Plan) -> Result<(), WorkflowError> {
+        let mismatch = if self.plan != plan.name {
+            format!("The saved run state belongs to plan {}", self.plan)
+        } else if self.plan_hash != Self::plan_hash(plan) {
+            "The plan changed since its run was interrupted".to_string()
+        } else {
+            return Ok(());
+        };
+        Err(WorkflowError::Execution(format!(
+            "{}; run {} again without --resume to start over",
+            mismatch, plan.name
+        )))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, WorkflowError> {
+        let content = std::fs::read_to_string(path).map_err(|e| {
+            WorkflowError::Execution(format!("Failed to read run state {:?}: {}", path, e))
+        })?;
+        serde_json::from_str(&content)
+            .map_err(|e| WorkflowError::Parse(format!("Invalid run state {:?}: {}", path, e)))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), WorkflowError> {
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| WorkflowError::Execution(e.to_string()))?;
+        std::fs::write(path, content).map_err(|e| {
+            WorkflowError::Execution(format!("Failed to write run state {:?}: {}", path, e))
+        })
+    }
+}
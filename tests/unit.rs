@@ -17,3 +17,18 @@ mod tool_tests;
 
 #[path = "unit/docgen_tests.rs"]
 mod docgen_tests;
+
+#[path = "unit/session_tests.rs"]
+mod session_tests;
+
+#[path = "unit/support.rs"]
+mod support;
+
+#[path = "unit/agent_tests.rs"]
+mod agent_tests;
+
+#[path = "unit/workflow_tests.rs"]
+mod workflow_tests;
+
+#[path = "unit/mcp_tests.rs"]
+mod mcp_tests;
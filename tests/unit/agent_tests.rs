@@ -0,0 +1,559 @@
+use crate::support::{ScriptedProvider, tool_call, workspace};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
+use xzardgz::agent::budget::{Budget, BudgetKind, BudgetTracker};
+use xzardgz::agent::core::Agent;
+use xzardgz::agent::events::{AgentEvent, AgentObserver};
+use xzardgz::agent::message::{Message, Role};
+use xzardgz::agent::review::{ReviewConfig, Reviewer};
+use xzardgz::error::XzardgzError;
+use xzardgz::tools::delegate::{DelegateTaskTool, DelegationConfig};
+use xzardgz::tools::file_ops::ReadFileTool;
+use xzardgz::tools::registry::ToolRegistry;
+use xzardgz::tools::{ToolContext, ToolExecutor};
+
+#[derive(Default)]
+struct RecordingObserver {
+    events: Mutex<Vec<AgentEvent>>,
+}
+
+impl AgentObserver for RecordingObserver {
+    fn on_event(&self, event: &AgentEvent) {
+        self.events.lock().unwrap().push(event.clone());
+    }
+}
+
+#[tokio::test]
+async fn test_agent_emits_events() {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "read_file", r#"{"path": "does/not/exist"}"#),
+        Message::assistant("All done"),
+    ]));
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace())),
+    );
+
+    let observer = Arc::new(RecordingObserver::default());
+    let agent = Agent::new(provider, "test".to_string(), registry).with_observer(observer.clone());
+
+    let response = agent.run("Read a file").await.unwrap();
+    assert_eq!(response, "All done");
+
+    let events = observer.events.lock().unwrap();
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| match e {
+            AgentEvent::TurnStarted { .. } => "turn_started",
+            AgentEvent::TurnCompleted { .. } => "turn_completed",
+            AgentEvent::ProviderRequest { .. } => "provider_request",
+            AgentEvent::ProviderResponse { .. } => "provider_response",
+            AgentEvent::ToolCallStarted { .. } => "tool_started",
+            AgentEvent::ToolCallFinished { .. } => "tool_finished",
+            AgentEvent::ContextCompacted { .. } => "compacted",
+            AgentEvent::ReviewCompleted { .. } => "reviewed",
+            AgentEvent::Error { .. } => "error",
+        })
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "turn_started",
+            "provider_request",
+            "provider_response",
+            "tool_started",
+            "tool_finished",
+            "provider_request",
+            "provider_response",
+            "turn_completed",
+        ]
+    );
+
+    match &events[4] {
+        AgentEvent::ToolCallFinished { name, error, .. } => {
+            assert_eq!(name, "read_file");
+            assert!(error.as_deref().unwrap().contains("File not found"));
+        }
+        other => panic!("Unexpected event {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_agent_emits_error_event() {
+    let provider = Arc::new(ScriptedProvider::new(vec![]));
+    let observer = Arc::new(RecordingObserver::default());
+    let agent = Agent::new(provider, "test".to_string(), ToolRegistry::new())
+        .with_observer(observer.clone());
+
+    assert!(agent.run("Hello").await.is_err());
+
+    let events = observer.events.lock().unwrap();
+    assert!(matches!(events.last(), Some(AgentEvent::Error { .. })));
+}
+
+/// Cancels the turn as soon as the provider answers
+struct CancelOnResponse(CancellationToken);
+
+impl AgentObserver for CancelOnResponse {
+    fn on_event(&self, event: &AgentEvent) {
+        if let AgentEvent::ProviderResponse { .. } = event {
+            self.0.cancel();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_cancelled_turn_keeps_context_consistent() {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "read_file", r#"{"path": "Cargo.toml"}"#),
+        Message::assistant("Still here"),
+    ]));
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace())),
+    );
+
+    let cancel = CancellationToken::new();
+    let agent = Agent::new(provider, "test".to_string(), registry)
+        .with_observer(Arc::new(CancelOnResponse(cancel.clone())));
+
+    let result = agent.run_with_cancel("Read Cargo.toml", &cancel).await;
+    assert!(matches!(result, Err(XzardgzError::Cancelled)));
+
+    // The tool request is answered, so the conversation can continue
+    let context = agent.context().unwrap();
+    let last = context.get_messages().last().unwrap();
+    assert_eq!(last.role, Role::Tool);
+    assert_eq!(last.tool_call_id.as_deref(), Some("call_1"));
+    assert!(last.content.contains("Cancelled"));
+
+    assert_eq!(agent.run("Are you there?").await.unwrap(), "Still here");
+}
+
+#[tokio::test]
+async fn test_cancelled_before_provider_call() {
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant("Hi")]));
+    let agent = Agent::new(provider.clone(), "test".to_string(), ToolRegistry::new());
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let result = agent.run_with_cancel("Hello", &cancel).await;
+    assert!(matches!(result, Err(XzardgzError::Cancelled)));
+    assert!(provider.requests.lock().unwrap().is_empty());
+}
+
+fn read_only_tools() -> ToolRegistry {
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace())),
+    );
+    registry
+}
+
+#[tokio::test]
+async fn test_delegate_returns_only_child_answer() {
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant(
+        "child answer",
+    )]));
+    let tool = DelegateTaskTool::new(
+        provider.clone(),
+        read_only_tools(),
+        DelegationConfig::default(),
+    );
+
+    let result = tool
+        .execute(json!({"task": "summarize", "system_prompt": "Be brief."}))
+        .await
+        .unwrap();
+
+    assert!(result.error.is_none());
+    assert_eq!(result.output, "child answer");
+    let requests = provider.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0][0].content, "summarize");
+}
+
+#[tokio::test]
+async fn test_delegate_rejects_unknown_tools() {
+    let provider = Arc::new(ScriptedProvider::new(vec![]));
+    let tool = DelegateTaskTool::new(provider, read_only_tools(), DelegationConfig::default());
+
+    let result = tool
+        .execute(json!({"task": "edit", "tools": ["write_file"]}))
+        .await
+        .unwrap();
+
+    assert!(result.error.is_some());
+    assert!(result.error.unwrap().contains("write_file"));
+}
+
+#[tokio::test]
+async fn test_delegate_only_hands_out_the_step_tools() {
+    let provider = Arc::new(ScriptedProvider::new(vec![]));
+    let tool = DelegateTaskTool::new(provider, read_only_tools(), DelegationConfig::default());
+    // A step whose profile leaves out read_file
+    let step = ToolContext {
+        tools: Some(ToolRegistry::new()),
+        ..ToolContext::default()
+    };
+
+    let result = tool
+        .execute_in(json!({"task": "read", "tools": ["read_file"]}), &step)
+        .await
+        .unwrap();
+
+    assert!(result.error.is_some());
+    assert!(result.error.unwrap().contains("read_file"));
+}
+
+#[tokio::test]
+async fn test_delegate_budget_is_enforced() {
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant("first")]));
+    let config = DelegationConfig {
+        max_depth: 1,
+        max_delegations: 1,
+    };
+    let tool = DelegateTaskTool::new(provider, read_only_tools(), config);
+    let run = ToolContext::default();
+
+    assert!(
+        tool.execute_in(json!({"task": "a"}), &run)
+            .await
+            .unwrap()
+            .error
+            .is_none()
+    );
+    let second = tool.execute_in(json!({"task": "b"}), &run).await.unwrap();
+    assert!(second.error.is_some());
+    assert!(second.error.unwrap().contains("budget"));
+}
+
+#[tokio::test]
+async fn test_delegation_limit_resets_every_run() {
+    let parent = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "delegate_task", r#"{"task": "first"}"#),
+        Message::assistant("done 1"),
+        tool_call("call_2", "delegate_task", r#"{"task": "second"}"#),
+        Message::assistant("done 2"),
+    ]));
+    let child = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("first result"),
+        Message::assistant("second result"),
+    ]));
+    let config = DelegationConfig {
+        max_depth: 1,
+        max_delegations: 1,
+    };
+    let mut registry = read_only_tools();
+    registry.register(
+        DelegateTaskTool::definition(),
+        Arc::new(DelegateTaskTool::new(child, read_only_tools(), config)),
+    );
+    let agent = Agent::new(parent.clone(), "test".to_string(), registry);
+
+    assert_eq!(agent.run("one").await.unwrap(), "done 1");
+    assert_eq!(agent.run("two").await.unwrap(), "done 2");
+    let requests = parent.requests.lock().unwrap();
+    assert_eq!(requests[3].last().unwrap().content, "second result");
+}
+
+#[tokio::test]
+async fn test_delegate_stops_with_the_parent_run() {
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant("late")]));
+    let tool = DelegateTaskTool::new(
+        provider.clone(),
+        read_only_tools(),
+        DelegationConfig::default(),
+    );
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let result = tool
+        .execute_in(json!({"task": "a"}), &ToolContext::new(cancel))
+        .await;
+    assert!(matches!(result, Err(XzardgzError::Cancelled)));
+    assert!(provider.requests.lock().unwrap().is_empty());
+}
+
+fn review_config(max_revisions: usize) -> ReviewConfig {
+    ReviewConfig {
+        enabled: true,
+        threshold: 4,
+        max_revisions,
+    }
+}
+
+#[tokio::test]
+async fn test_review_revises_until_threshold() {
+    let writer = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("draft"),
+        Message::assistant("revised"),
+    ]));
+    let reviewer = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant(
+            r#"{"accuracy": 2, "diataxis_fit": 5, "completeness": 4, "feedback": "Cite the source"}"#,
+        ),
+        Message::assistant(
+            "```json\n{\"accuracy\": 5, \"diataxis_fit\": 5, \"completeness\": 4}\n```",
+        ),
+    ]));
+    let agent = Agent::new(writer.clone(), "test".to_string(), ToolRegistry::new())
+        .with_reviewer(Reviewer::new(reviewer, review_config(2)));
+
+    let answer = agent.run("Explain the parser").await.unwrap();
+    assert_eq!(answer, "revised");
+
+    let revision_request = &writer.requests.lock().unwrap()[1];
+    assert!(
+        revision_request
+            .last()
+            .unwrap()
+            .content
+            .contains("Cite the source")
+    );
+
+    let reviews = agent.reviews();
+    assert_eq!(reviews.len(), 1);
+    assert!(reviews[0].passed);
+    assert_eq!(reviews[0].revisions(), 1);
+    assert_eq!(reviews[0].final_scores().unwrap().accuracy, 5);
+}
+
+#[tokio::test]
+async fn test_review_stops_after_max_revisions() {
+    let writer = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("draft"),
+        Message::assistant("still weak"),
+    ]));
+    let low = r#"{"accuracy": 3, "diataxis_fit": 3, "completeness": 3, "feedback": "More"}"#;
+    let reviewer = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant(low),
+        Message::assistant(low),
+    ]));
+    let agent = Agent::new(writer, "test".to_string(), ToolRegistry::new())
+        .with_reviewer(Reviewer::new(reviewer, review_config(1)));
+
+    let answer = agent.run("Explain the parser").await.unwrap();
+    assert_eq!(answer, "still weak");
+
+    let reviews = agent.reviews();
+    assert!(!reviews[0].passed);
+    assert_eq!(reviews[0].rounds.len(), 2);
+}
+
+#[tokio::test]
+async fn test_unparsable_review_keeps_answer() {
+    let writer = Arc::new(ScriptedProvider::new(vec![Message::assistant("draft")]));
+    let reviewer = Arc::new(ScriptedProvider::new(vec![Message::assistant(
+        "Looks good!",
+    )]));
+    let agent = Agent::new(writer, "test".to_string(), ToolRegistry::new())
+        .with_reviewer(Reviewer::new(reviewer, review_config(2)));
+
+    assert_eq!(agent.run("Explain").await.unwrap(), "draft");
+    assert!(agent.reviews()[0].rounds.is_empty());
+}
+
+#[tokio::test]
+async fn test_provider_call_budget_returns_partial_result() {
+    let mut first = tool_call("call_1", "read_file", r#"{"path": "Cargo.toml"}"#);
+    first.content = "Looking at the manifest first".to_string();
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        first,
+        Message::assistant("never reached"),
+    ]));
+    let agent = Agent::new(provider, "test".to_string(), read_only_tools()).with_budget(Budget {
+        max_provider_calls: Some(1),
+        ..Budget::default()
+    });
+
+    match agent.run("Describe the crate").await {
+        Err(XzardgzError::BudgetExceeded {
+            kind,
+            used,
+            limit,
+            partial,
+        }) => {
+            assert_eq!(kind, BudgetKind::ProviderCalls);
+            assert_eq!((used, limit), (1, 1));
+            assert_eq!(partial.as_deref(), Some("Looking at the manifest first"));
+        }
+        other => panic!("expected budget error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_unknown_tool_is_reported_to_the_model() {
+    let mut garbled = tool_call("call_2", "read_file", "");
+    garbled.tool_calls.as_mut().unwrap()[0].function.arguments = "{path".to_string();
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "read_everything", "{}"),
+        garbled,
+        Message::assistant("done"),
+    ]));
+    let agent = Agent::new(provider, "test".to_string(), read_only_tools());
+
+    assert_eq!(agent.run("Read it").await.unwrap(), "done");
+    let context = agent.context().unwrap();
+    let results: Vec<&Message> = context
+        .get_messages()
+        .iter()
+        .filter(|m| m.role == Role::Tool)
+        .collect();
+    assert!(results[0].content.contains("unknown_tool"));
+    assert!(results[1].content.contains("not valid JSON"));
+}
+
+#[tokio::test]
+async fn test_iteration_cap_returns_partial_result() {
+    let responses = (1..=5)
+        .map(|i| {
+            let mut call = tool_call(&format!("call_{}", i), "read_file", r#"{"path": "a"}"#);
+            call.content = format!("Still looking ({})", i);
+            call
+        })
+        .collect();
+    let provider = Arc::new(ScriptedProvider::new(responses));
+    let agent = Agent::new(provider, "test".to_string(), read_only_tools());
+
+    match agent.run("Keep reading").await {
+        Err(XzardgzError::BudgetExceeded {
+            kind,
+            used,
+            limit,
+            partial,
+        }) => {
+            assert_eq!(kind, BudgetKind::Iterations);
+            assert_eq!((used, limit), (5, 5));
+            assert_eq!(partial.as_deref(), Some("Still looking (5)"));
+        }
+        other => panic!("expected iteration limit, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_review_counts_against_budget() {
+    let writer = Arc::new(ScriptedProvider::new(vec![Message::assistant("draft")]));
+    let reviewer = Arc::new(ScriptedProvider::new(vec![Message::assistant(
+        r#"{"accuracy": 5, "diataxis_fit": 5, "completeness": 5}"#,
+    )]));
+    let tracker = Arc::new(BudgetTracker::new(Budget::default()));
+    let agent = Agent::new(writer.clone(), "test".to_string(), ToolRegistry::new())
+        .with_reviewer(Reviewer::new(reviewer.clone(), review_config(2)))
+        .with_shared_budget(tracker.clone());
+    assert_eq!(agent.run("Explain").await.unwrap(), "draft");
+    assert_eq!(tracker.usage().provider_calls, 2);
+
+    // The draft used the only call, so the review may not run
+    let writer = Arc::new(ScriptedProvider::new(vec![Message::assistant("draft")]));
+    let agent = Agent::new(writer, "test".to_string(), ToolRegistry::new())
+        .with_reviewer(Reviewer::new(reviewer.clone(), review_config(2)))
+        .with_budget(Budget {
+            max_provider_calls: Some(1),
+            ..Budget::default()
+        });
+    match agent.run("Explain").await {
+        Err(XzardgzError::BudgetExceeded { kind, partial, .. }) => {
+            assert_eq!(kind, BudgetKind::ProviderCalls);
+            assert_eq!(partial.as_deref(), Some("draft"));
+        }
+        other => panic!("expected budget error, got {:?}", other),
+    }
+    assert_eq!(reviewer.requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_tool_call_budget_answers_skipped_calls() {
+    let mut calls = tool_call("call_1", "read_file", r#"{"path": "a"}"#);
+    calls.tool_calls.as_mut().unwrap().push(
+        tool_call("call_2", "read_file", r#"{"path": "b"}"#)
+            .tool_calls
+            .unwrap()
+            .remove(0),
+    );
+    let provider = Arc::new(ScriptedProvider::new(vec![calls]));
+    let agent = Agent::new(provider, "test".to_string(), read_only_tools()).with_budget(Budget {
+        max_tool_calls: Some(1),
+        ..Budget::default()
+    });
+
+    let error = agent.run("Read both").await.unwrap_err();
+    assert!(matches!(
+        error,
+        XzardgzError::BudgetExceeded {
+            kind: BudgetKind::ToolCalls,
+            ..
+        }
+    ));
+
+    // Both calls still have a result, so the conversation can continue
+    let context = agent.context().unwrap();
+    let results: Vec<&Message> = context
+        .get_messages()
+        .iter()
+        .filter(|m| m.role == Role::Tool)
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[1].content.contains("Budget exceeded"));
+}
+
+#[tokio::test]
+async fn test_shared_budget_spans_runs() {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("one"),
+        Message::assistant("two"),
+    ]));
+    let budget = Arc::new(BudgetTracker::new(Budget {
+        max_provider_calls: Some(1),
+        ..Budget::default()
+    }));
+    let agent = Agent::new(provider, "test".to_string(), ToolRegistry::new())
+        .with_shared_budget(budget.clone());
+
+    assert_eq!(agent.run("first").await.unwrap(), "one");
+    assert!(matches!(
+        agent.run("second").await,
+        Err(XzardgzError::BudgetExceeded { .. })
+    ));
+    assert_eq!(budget.usage().provider_calls, 1);
+}
+
+#[tokio::test]
+async fn test_sub_agent_usage_counts_against_the_parent_budget() {
+    let parent = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "delegate_task", r#"{"task": "t"}"#),
+        Message::assistant("done"),
+    ]));
+    // About 100 tokens, all spent by the sub-agent
+    let child = Arc::new(ScriptedProvider::new(vec![Message::assistant(
+        "x".repeat(400),
+    )]));
+    let workflow = Arc::new(BudgetTracker::new(Budget::default()));
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        DelegateTaskTool::definition(),
+        Arc::new(DelegateTaskTool::new(
+            child,
+            ToolRegistry::new(),
+            DelegationConfig::default(),
+        )),
+    );
+    let agent = Agent::new(parent.clone(), "test".to_string(), registry)
+        .with_budget(Budget {
+            max_tokens: Some(50),
+            ..Budget::default()
+        })
+        .with_shared_budget(workflow.clone());
+
+    match agent.run("go").await {
+        Err(XzardgzError::BudgetExceeded { kind, .. }) => assert_eq!(kind, BudgetKind::Tokens),
+        other => panic!("expected budget error, got {:?}", other),
+    }
+    assert_eq!(parent.requests.lock().unwrap().len(), 1);
+    // Counted once per budget, not once per level of delegation
+    assert_eq!(workflow.usage().provider_calls, 2);
+}
@@ -1,9 +1,19 @@
+use async_trait::async_trait;
+use futures::stream::Stream;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use xzardgz::agent::budget::{Budget, BudgetTracker, Budgets};
+use xzardgz::agent::compaction::{self, CompactionStrategy};
 use xzardgz::agent::context::ConversationContext;
-use xzardgz::agent::message::Message;
+use xzardgz::agent::message::{FunctionCall, Message, Role, ToolCall};
+use xzardgz::error::ProviderError;
+use xzardgz::providers::Provider;
+use xzardgz::providers::types::{ProviderCapabilities, ProviderMetadata, Tool};
 
 #[test]
 fn test_context_management() {
     let mut ctx = ConversationContext::new("You are a bot".to_string(), 100);
+    assert_eq!(ctx.strategy(), CompactionStrategy::default());
 
     ctx.add_message(Message::user("Hello"));
     assert_eq!(ctx.get_messages().len(), 1);
@@ -17,11 +27,12 @@ fn test_context_management() {
 
 #[test]
 fn test_compaction() {
-    // 100 tokens max. 1 token approx 4 chars.
-    // "Hello" is 5 chars ~ 1 token.
-    let mut ctx = ConversationContext::new("System".to_string(), 5);
+    // 1 token approx 4 chars.
+    let mut ctx = ConversationContext::new("System".to_string(), 5)
+        .with_strategy(CompactionStrategy::Truncate);
 
-    // Add a long message
+    // Add an earlier turn and a long message
+    ctx.add_message(Message::assistant("b".repeat(40)));
     let long_msg = "a".repeat(40); // 10 tokens
     ctx.add_message(Message::user(&long_msg));
 
@@ -29,5 +40,207 @@ fn test_compaction() {
 
     let compacted = ctx.compact_if_needed().unwrap();
     assert!(compacted);
-    assert_eq!(ctx.get_messages().len(), 0); // Should remove the message
+    // The earlier turn goes, the latest input stays even though it alone is too large
+    assert_eq!(ctx.get_messages().len(), 1);
+    assert_eq!(ctx.get_messages()[0].content, long_msg);
+}
+
+#[test]
+fn test_compaction_keeps_an_input_larger_than_the_budget() {
+    for strategy in [
+        CompactionStrategy::Truncate,
+        CompactionStrategy::PinFirst,
+        CompactionStrategy::Summarize,
+    ] {
+        let mut ctx = ConversationContext::new("System".to_string(), 5).with_strategy(strategy);
+        ctx.add_message(Message::user("a".repeat(400)));
+
+        assert_eq!(ctx.compaction_range(), None);
+        assert!(!ctx.compact_if_needed().unwrap());
+        assert_eq!(ctx.get_messages().len(), 1);
+        assert_eq!(ctx.get_messages()[0].role, Role::User);
+    }
+}
+
+fn tool_call_turn(id: &str) -> Vec<Message> {
+    let mut request = Message::assistant("");
+    request.tool_calls = Some(vec![ToolCall {
+        id: id.to_string(),
+        function: FunctionCall {
+            name: "read_file".to_string(),
+            arguments: "{}".to_string(),
+        },
+    }]);
+    let mut result = Message::user("b".repeat(40));
+    result.role = Role::Tool;
+    result.tool_call_id = Some(id.to_string());
+    vec![request, result]
+}
+
+#[test]
+fn test_compaction_keeps_tool_pairs_together() {
+    let mut ctx = ConversationContext::new("System".to_string(), 25)
+        .with_strategy(CompactionStrategy::Truncate);
+    ctx.add_message(Message::user("a".repeat(40)));
+    for message in tool_call_turn("call_1") {
+        ctx.add_message(message);
+    }
+    ctx.add_message(Message::assistant("c".repeat(40)));
+    ctx.add_message(Message::user("d".repeat(40)));
+
+    assert!(ctx.compact_if_needed().unwrap());
+
+    // The tool result must never be left without its assistant request
+    let first = &ctx.get_messages()[0];
+    assert_ne!(first.role, Role::Tool);
+    assert_eq!(ctx.get_messages().len(), 2);
+    assert_eq!(first.content, "c".repeat(40));
+}
+
+#[test]
+fn test_compaction_pins_first_user_message() {
+    let mut ctx = ConversationContext::new("System".to_string(), 25)
+        .with_strategy(CompactionStrategy::PinFirst);
+    ctx.add_message(Message::user("task ".repeat(8)));
+    ctx.add_message(Message::assistant("a".repeat(40)));
+    for message in tool_call_turn("call_1") {
+        ctx.add_message(message);
+    }
+    ctx.add_message(Message::assistant("c".repeat(40)));
+
+    assert!(ctx.compact_if_needed().unwrap());
+
+    let messages = ctx.get_messages();
+    assert_eq!(messages[0].content, "task ".repeat(8));
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1].content, "c".repeat(40));
+}
+
+#[test]
+fn test_truncating_a_summarized_context_leaves_room_for_a_summary() {
+    let mut ctx = ConversationContext::new("System".to_string(), 45)
+        .with_strategy(CompactionStrategy::Summarize);
+    ctx.add_message(Message::user("task ".repeat(8)));
+    for reply in ["a", "b", "c", "d"] {
+        ctx.add_message(Message::assistant(reply.repeat(40)));
+    }
+
+    assert!(ctx.compact_if_needed().unwrap());
+
+    // Pinned like PinFirst, but cut to half the budget instead of just under it
+    let messages = ctx.get_messages();
+    assert_eq!(messages[0].content, "task ".repeat(8));
+    assert_eq!(messages.len(), 2);
+    assert!(ctx.current_tokens() <= 22);
+}
+
+struct SummaryProvider;
+
+#[async_trait]
+impl Provider for SummaryProvider {
+    fn metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            name: "summary".to_string(),
+            models: vec![],
+            capabilities: ProviderCapabilities {
+                streaming: false,
+                tools: false,
+                vision: false,
+            },
+        }
+    }
+
+    async fn complete(
+        &self,
+        _messages: &[Message],
+        _tools: &[Tool],
+    ) -> Result<Message, ProviderError> {
+        Ok(Message::assistant("read a file"))
+    }
+
+    async fn complete_streaming(
+        &self,
+        _messages: &[Message],
+        _tools: &[Tool],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Message, ProviderError>> + Send>>, ProviderError>
+    {
+        Err(ProviderError::Api("not supported".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn test_compaction_summarizes_dropped_turns() {
+    let ctx = ConversationContext::new("System".to_string(), 25)
+        .with_strategy(CompactionStrategy::Summarize);
+    let ctx = Mutex::new(ctx);
+    {
+        let mut ctx = ctx.lock().unwrap();
+        ctx.add_message(Message::user("task ".repeat(8)));
+        for message in tool_call_turn("call_1") {
+            ctx.add_message(message);
+        }
+        ctx.add_message(Message::assistant("c".repeat(40)));
+    }
+
+    assert!(
+        compaction::compact(&SummaryProvider, &ctx, &Budgets::default())
+            .await
+            .unwrap()
+    );
+
+    let ctx = ctx.lock().unwrap();
+    let messages = ctx.get_messages();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].content, "task ".repeat(8));
+    assert_eq!(messages[1].role, Role::System);
+    assert!(messages[1].content.starts_with(compaction::SUMMARY_PREFIX));
+    assert!(messages[1].content.contains("read a file"));
+}
+
+#[tokio::test]
+async fn test_compaction_summary_counts_against_budget() {
+    let turns = || {
+        let ctx = ConversationContext::new("System".to_string(), 25)
+            .with_strategy(CompactionStrategy::Summarize);
+        let ctx = Mutex::new(ctx);
+        {
+            let mut ctx = ctx.lock().unwrap();
+            ctx.add_message(Message::user("task ".repeat(8)));
+            for message in tool_call_turn("call_1") {
+                ctx.add_message(message);
+            }
+            ctx.add_message(Message::assistant("c".repeat(40)));
+        }
+        ctx
+    };
+
+    let tracker = Arc::new(BudgetTracker::new(Budget::default()));
+    let budgets = Budgets::new(vec![tracker.clone()]);
+    assert!(
+        compaction::compact(&SummaryProvider, &turns(), &budgets)
+            .await
+            .unwrap()
+    );
+    assert_eq!(tracker.usage().provider_calls, 1);
+    assert!(tracker.usage().tokens > 0);
+
+    // Without provider calls left, the dropped turns are truncated instead
+    let tracker = Arc::new(BudgetTracker::new(Budget {
+        max_provider_calls: Some(0),
+        ..Budget::default()
+    }));
+    let ctx = turns();
+    let budgets = Budgets::new(vec![tracker.clone()]);
+    assert!(
+        compaction::compact(&SummaryProvider, &ctx, &budgets)
+            .await
+            .unwrap()
+    );
+    assert_eq!(tracker.usage().provider_calls, 0);
+    let ctx = ctx.lock().unwrap();
+    assert!(
+        ctx.get_messages()
+            .iter()
+            .all(|m| !m.content.starts_with(compaction::SUMMARY_PREFIX))
+    );
 }
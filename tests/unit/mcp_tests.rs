@@ -0,0 +1,337 @@
+use serde_json::json;
+use std::sync::Arc;
+use tempfile::TempDir;
+use xzardgz::mcp::McpServerConfig;
+use xzardgz::mcp::client::McpClient;
+use xzardgz::mcp::protocol::Message;
+use xzardgz::mcp::server::McpServer;
+use xzardgz::providers::types::{FunctionCall, ToolCall};
+use xzardgz::tools::executor::ToolExecutionDispatcher;
+use xzardgz::tools::file_ops::{ReadFileTool, WriteFileTool};
+use xzardgz::tools::registry::ToolRegistry;
+use xzardgz::tools::workspace::{Workspace, WorkspaceConfig};
+
+/// A tiny MCP server in sh: it answers by matching request lines, sends a
+/// notification while listing tools, pings the client during calls and
+/// stalls halfway through its answer to `slow`
+const SERVER: &str = r#"
+while IFS= read -r line; do
+  id=$(printf '%s' "$line" | sed -n 's/.*"id":\([0-9]*\).*/\1/p')
+  case "$line" in
+    *'"method":"initialize"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"protocolVersion\":\"2025-06-18\",\"capabilities\":{\"tools\":{}},\"serverInfo\":{\"name\":\"fixture\",\"version\":\"1.0\"}}}" ;;
+    *'"method":"tools/list"'*)
+      echo '{"jsonrpc":"2.0","method":"notifications/message","params":{"level":"info","data":"listing"}}'
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"tools\":[{\"name\":\"echo\",\"description\":\"Echo text\",\"inputSchema\":{\"type\":\"object\",\"properties\":{\"text\":{\"type\":\"string\"}},\"required\":[\"text\"]},\"annotations\":{\"readOnlyHint\":true}},{\"name\":\"fail\",\"inputSchema\":{\"type\":\"object\"}}]}}" ;;
+    *'"method":"tools/call"'*'"name":"echo"'*)
+      text=$(printf '%s' "$line" | sed -n 's/.*"text":"\([^"]*\)".*/\1/p')
+      echo '{"jsonrpc":"2.0","id":"server-1","method":"ping"}'
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"echo: $text\"},{\"type\":\"image\",\"data\":\"\",\"mimeType\":\"image/png\"}]}}" ;;
+    *'"method":"tools/call"'*'"name":"slow"'*)
+      printf '{"jsonrpc":"2.0",'
+      sleep 2
+      echo "\"id\":$id,\"result\":{\"content\":[]}}" ;;
+    *'"method":"tools/call"'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"result\":{\"content\":[{\"type\":\"text\",\"text\":\"it broke\"}],\"isError\":true}}" ;;
+    *'"id":'*'"method":'*)
+      echo "{\"jsonrpc\":\"2.0\",\"id\":$id,\"error\":{\"code\":-32601,\"message\":\"no such method\"}}" ;;
+  esac
+done
+"#;
+
+fn server_config(dir: &TempDir) -> McpServerConfig {
+    let script = dir.path().join("server.sh");
+    std::fs::write(&script, SERVER).unwrap();
+    McpServerConfig {
+        name: "fixture".to_string(),
+        command: "sh".to_string(),
+        args: vec![script.display().to_string()],
+        env: Default::default(),
+        timeout_seconds: 10,
+    }
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+        id: "call_mcp".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_mcp_tools_are_imported_and_called() {
+    let dir = TempDir::new().unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.import_mcp_servers(&[server_config(&dir)]).await;
+
+    let echo = registry.get_tool("fixture__echo").unwrap();
+    assert_eq!(echo.description, "Echo text");
+    assert_eq!(echo.parameters["required"], json!(["text"]));
+    assert_eq!(
+        registry.get_tool("fixture__fail").unwrap().description,
+        "fail"
+    );
+    assert!(
+        !registry
+            .get_executor("fixture__echo")
+            .unwrap()
+            .is_mutating()
+    );
+    assert!(
+        registry
+            .get_executor("fixture__fail")
+            .unwrap()
+            .is_mutating()
+    );
+
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry));
+    let result = dispatcher
+        .execute(&call("fixture__echo", json!({"text": "hello"})))
+        .await
+        .unwrap();
+    assert!(result.error.is_none());
+    assert_eq!(result.output, "echo: hello\n[image: image/png]");
+
+    // Arguments are checked against the server's schema before forwarding
+    let result = dispatcher
+        .execute(&call("fixture__echo", json!({})))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("/text"));
+
+    let result = dispatcher
+        .execute(&call("fixture__fail", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(result.error.as_deref(), Some("it broke"));
+}
+
+#[tokio::test]
+async fn test_mcp_client_errors() {
+    let dir = TempDir::new().unwrap();
+    let client = McpClient::connect(&server_config(&dir)).await.unwrap();
+    assert_eq!(client.name(), "fixture");
+    assert_eq!(client.list_tools().await.unwrap().len(), 2);
+
+    let mut missing = server_config(&dir);
+    missing.command = "xzardgz-no-such-server".to_string();
+    let error = McpClient::connect(&missing).await.err().unwrap();
+    assert!(
+        error
+            .to_string()
+            .starts_with("Failed to start MCP server fixture")
+    );
+
+    // An unavailable server is left out instead of failing startup
+    let mut registry = ToolRegistry::new();
+    registry.import_mcp_servers(&[missing]).await;
+    assert!(registry.list_tools().is_empty());
+
+    let mut silent = server_config(&dir);
+    silent.args = vec!["-c".to_string(), "sleep 5".to_string()];
+    silent.timeout_seconds = 1;
+    let error = McpClient::connect(&silent).await.err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "MCP server fixture did not answer initialize within 1s"
+    );
+
+    // A timeout can leave half a line unread, so the connection is not reused
+    let mut slow = server_config(&dir);
+    slow.timeout_seconds = 1;
+    let client = McpClient::connect(&slow).await.unwrap();
+    let error = client.call_tool("slow", json!({})).await.err().unwrap();
+    assert!(matches!(error, xzardgz::error::McpError::Timeout { .. }));
+    let error = client.list_tools().await.err().unwrap();
+    assert!(matches!(error, xzardgz::error::McpError::Broken(_)));
+
+    let mut exits = server_config(&dir);
+    exits.args = vec!["-c".to_string(), "exit 0".to_string()];
+    let error = McpClient::connect(&exits).await.err().unwrap();
+    assert!(matches!(
+        error,
+        xzardgz::error::McpError::Closed(_) | xzardgz::error::McpError::Io(_)
+    ));
+}
+
+fn file_server(dir: &TempDir) -> McpServer {
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
+    McpServer::new(registry)
+}
+
+fn request(id: u64, method: &str, params: serde_json::Value) -> Message {
+    Message::request(id, method, params)
+}
+
+#[tokio::test]
+async fn test_mcp_server_lists_and_calls_tools() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "hello\n").unwrap();
+    let server = file_server(&dir);
+
+    let reply = server
+        .handle(request(1, "initialize", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(reply.result.unwrap()["serverInfo"]["name"], "xzardgz");
+    assert!(
+        server
+            .handle(Message::notification("notifications/initialized"))
+            .await
+            .is_none()
+    );
+
+    let reply = server
+        .handle(request(2, "tools/list", json!({})))
+        .await
+        .unwrap();
+    let tools = reply.result.unwrap()["tools"].clone();
+    assert_eq!(tools[0]["name"], "read_file");
+    assert_eq!(tools[0]["annotations"]["readOnlyHint"], true);
+    assert_eq!(tools[0]["inputSchema"]["required"], json!(["path"]));
+    assert_eq!(tools[1]["name"], "write_file");
+    assert_eq!(tools[1]["annotations"]["readOnlyHint"], false);
+
+    let reply = server
+        .handle(request(
+            3,
+            "tools/call",
+            json!({"name": "write_file", "arguments": {"path": "out.txt", "content": "x"}}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(reply.result.unwrap()["isError"], false);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("out.txt")).unwrap(),
+        "x"
+    );
+
+    // Tool failures are results, unknown tools and methods are protocol errors
+    let reply = server
+        .handle(request(
+            4,
+            "tools/call",
+            json!({"name": "read_file", "arguments": {}}),
+        ))
+        .await
+        .unwrap();
+    let result = reply.result.unwrap();
+    assert_eq!(result["isError"], true);
+    assert!(
+        result["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("invalid_arguments")
+    );
+
+    let reply = server
+        .handle(request(
+            5,
+            "tools/call",
+            json!({"name": "delete_everything"}),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(reply.error.unwrap().code, -32602);
+
+    let reply = server
+        .handle(request(6, "resources/list", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(reply.error.unwrap().code, -32601);
+}
+
+#[tokio::test]
+async fn test_mcp_server_serves_stdio_framing() {
+    let dir = TempDir::new().unwrap();
+    let server = file_server(&dir);
+    let input = concat!(
+        r#"{"jsonrpc":"2.0","id":1,"method":"ping"}"#,
+        "\n\nnot json\n",
+        r#"{"jsonrpc":"2.0","method":"notifications/initialized"}"#,
+        "\n",
+        r#"{"jsonrpc":"2.0","id":"b","method":"ping"}"#,
+        "\n",
+    );
+    let mut output = Vec::new();
+    server.serve(input.as_bytes(), &mut output).await.unwrap();
+
+    let replies: Vec<serde_json::Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(replies.len(), 3);
+    assert_eq!(replies[0], json!({"jsonrpc": "2.0", "id": 1, "result": {}}));
+    assert_eq!(replies[1]["error"]["code"], -32700);
+    assert_eq!(replies[2]["id"], "b");
+}
+
+#[tokio::test]
+async fn test_mcp_client_talks_to_mcp_serve() {
+    // Keeps the server's audit log out of the real data directory
+    let data = TempDir::new().unwrap();
+    let config = McpServerConfig {
+        name: "self".to_string(),
+        command: env!("CARGO_BIN_EXE_xzardgz").to_string(),
+        args: vec!["mcp".to_string(), "serve".to_string()],
+        env: [(
+            "XDG_DATA_HOME".to_string(),
+            data.path().display().to_string(),
+        )]
+        .into(),
+        timeout_seconds: 30,
+    };
+    let mut registry = ToolRegistry::new();
+    let client = Arc::new(McpClient::connect(&config).await.unwrap());
+    let names = registry.import_mcp(client).await.unwrap();
+    assert!(names.contains(&"self__read_file".to_string()));
+    assert!(names.contains(&"self__write_doc".to_string()));
+    assert!(!registry.get_executor("self__grep").unwrap().is_mutating());
+    assert!(
+        registry
+            .get_executor("self__run_command")
+            .unwrap()
+            .is_mutating()
+    );
+
+    // The server runs in this crate's directory
+    let result = registry
+        .get_executor("self__read_file")
+        .unwrap()
+        .execute(json!({"path": "Cargo.toml", "limit": 2}))
+        .await
+        .unwrap();
+    assert!(result.output.starts_with("[package]\nname = \"xzardgz\""));
+
+    // The server wrote an audit log for the call
+    let logs: Vec<_> = walk_files(data.path());
+    assert_eq!(logs.len(), 1);
+    let log = std::fs::read_to_string(&logs[0]).unwrap();
+    assert!(log.contains(r#""tool":"read_file""#));
+}
+
+fn walk_files(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(walk_files(&path));
+        } else {
+            files.push(path);
+        }
+    }
+    files
+}
@@ -0,0 +1,192 @@
+use crate::support::{ScriptedProvider, tool_call, workspace};
+use std::sync::Arc;
+use tempfile::TempDir;
+use xzardgz::agent::context::ConversationContext;
+use xzardgz::agent::core::Agent;
+use xzardgz::agent::message::{Message, Role};
+use xzardgz::agent::session::{ExportFormat, Session, SessionStore};
+use xzardgz::agent::transcript::Transcript;
+use xzardgz::error::SessionError;
+use xzardgz::tools::file_ops::ReadFileTool;
+use xzardgz::tools::registry::ToolRegistry;
+
+fn sample_session() -> Session {
+    let mut ctx = ConversationContext::new("You are a bot".to_string(), 100);
+    ctx.add_message(Message::user("Document the scanner"));
+    ctx.add_message(Message::assistant("Done"));
+    Session::new("ollama".to_string(), Some("qwen2.5-coder".to_string()), ctx)
+}
+
+#[test]
+fn test_session_roundtrip() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::new(dir.path());
+
+    let mut session = sample_session();
+    store.save(&mut session).unwrap();
+
+    let loaded = store.load(&session.id).unwrap();
+    assert_eq!(loaded.id, session.id);
+    assert_eq!(loaded.context.get_messages().len(), 2);
+    assert_eq!(loaded.context.system_prompt(), "You are a bot");
+
+    let sessions = store.list().unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].title, "Document the scanner");
+}
+
+#[test]
+fn test_session_not_found() {
+    let dir = TempDir::new().unwrap();
+    let store = SessionStore::new(dir.path());
+    assert!(matches!(
+        store.load("missing"),
+        Err(SessionError::NotFound(_))
+    ));
+}
+
+#[test]
+fn test_session_ids_cannot_leave_the_store() {
+    let dir = TempDir::new().unwrap();
+    std::fs::write(dir.path().join("outside.json"), "{}").unwrap();
+    let store = SessionStore::new(dir.path().join("sessions"));
+    for id in ["../outside", "a/b", "..", "/etc/passwd", ""] {
+        assert!(
+            matches!(store.load(id), Err(SessionError::InvalidId(_))),
+            "{}",
+            id
+        );
+    }
+
+    let mut session = sample_session();
+    session.id = "../outside".to_string();
+    assert!(matches!(
+        store.save(&mut session),
+        Err(SessionError::InvalidId(_))
+    ));
+}
+
+#[test]
+fn test_session_fork() {
+    let session = sample_session();
+    let forked = session.fork();
+    assert_eq!(forked.forked_from.as_deref(), Some(session.id.as_str()));
+    assert_eq!(forked.context.get_messages().len(), 2);
+}
+
+#[test]
+fn test_session_export() {
+    let session = sample_session();
+
+    let md = session.export(ExportFormat::Md).unwrap();
+    assert!(md.contains("## User\n\nDocument the scanner"));
+    assert!(md.contains("## Assistant\n\nDone"));
+
+    let json = session.export(ExportFormat::Json).unwrap();
+    let parsed: Session = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.id, session.id);
+}
+
+async fn recorded_transcript() -> Transcript {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        tool_call("call_1", "read_file", r#"{"path": "missing.txt"}"#),
+        Message::assistant("The file is missing"),
+        Message::assistant("Nothing else"),
+    ]));
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace())),
+    );
+    let agent = Agent::new(provider, "You are a bot".to_string(), registry);
+
+    agent.run("Read missing.txt").await.unwrap();
+    agent.run("Anything else?").await.unwrap();
+    agent.transcript().unwrap()
+}
+
+#[tokio::test]
+async fn test_transcript_records_turns_and_timings() {
+    let transcript = recorded_transcript().await;
+
+    assert_eq!(transcript.provider, "scripted");
+    assert_eq!(transcript.turns(), 2);
+    assert_eq!(
+        transcript.inputs(),
+        vec!["Read missing.txt", "Anything else?"]
+    );
+    let roles: Vec<Role> = transcript
+        .entries
+        .iter()
+        .map(|e| e.message.role.clone())
+        .collect();
+    assert_eq!(
+        roles,
+        vec![
+            Role::User,
+            Role::Assistant,
+            Role::Tool,
+            Role::Assistant,
+            Role::User,
+            Role::Assistant
+        ]
+    );
+    assert!(transcript.entries[1].duration_ms.is_some());
+    assert!(transcript.entries[2].duration_ms.is_some());
+
+    let usage = transcript.usage();
+    assert_eq!(usage.provider_calls, 3);
+    assert_eq!(usage.tool_calls, 1);
+    assert_eq!(transcript.usage_by_turn().len(), 2);
+}
+
+#[tokio::test]
+async fn test_transcript_json_roundtrip_restores_context() {
+    let transcript = recorded_transcript().await;
+    let json = transcript.export(ExportFormat::Json).unwrap();
+    let loaded = Transcript::from_json(&json).unwrap();
+
+    let context = loaded.to_context(4096);
+    assert_eq!(context.system_prompt(), "You are a bot");
+    assert_eq!(context.get_messages().len(), 6);
+
+    // A continued conversation keeps numbering turns after the loaded ones
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant("Third")]));
+    let agent =
+        Agent::new(provider, "ignored".to_string(), ToolRegistry::new()).with_transcript(loaded);
+    agent.run("One more").await.unwrap();
+    let continued = agent.transcript().unwrap();
+    assert_eq!(continued.turns(), 3);
+    assert_eq!(agent.context().unwrap().get_messages().len(), 8);
+}
+
+#[tokio::test]
+async fn test_transcript_markdown_export() {
+    let md = recorded_transcript()
+        .await
+        .export(ExportFormat::Md)
+        .unwrap();
+    assert!(md.contains("## Turn 1"));
+    assert!(md.contains("## Turn 2"));
+    assert!(md.contains("### Tool result (read_file"));
+    assert!(md.contains("tokens"));
+}
+
+#[tokio::test]
+async fn test_transcript_replay_sends_inputs_to_other_provider() {
+    let original = recorded_transcript().await;
+    let other = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("Replayed one"),
+        Message::assistant("Replayed two"),
+    ]));
+    let agent = Agent::new(
+        other.clone(),
+        original.system_prompt.clone(),
+        ToolRegistry::new(),
+    );
+
+    let replayed = original.replay(&agent).await.unwrap();
+    assert_eq!(replayed.inputs(), original.inputs());
+    assert_eq!(replayed.usage().provider_calls, 2);
+    assert_eq!(other.requests.lock().unwrap().len(), 2);
+}
@@ -0,0 +1,81 @@
+use async_trait::async_trait;
+use futures::stream::Stream;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use xzardgz::error::ProviderError;
+use xzardgz::providers::Provider;
+use xzardgz::providers::types::{
+    FunctionCall, Message, ProviderCapabilities, ProviderMetadata, Tool, ToolCall,
+};
+use xzardgz::tools::workspace::{Workspace, WorkspaceConfig};
+
+/// Provider that replays canned responses and records every request
+pub struct ScriptedProvider {
+    responses: Mutex<VecDeque<Message>>,
+    pub requests: Mutex<Vec<Vec<Message>>>,
+}
+
+impl ScriptedProvider {
+    pub fn new(responses: Vec<Message>) -> Self {
+        Self {
+            responses: Mutex::new(responses.into()),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl Provider for ScriptedProvider {
+    fn metadata(&self) -> ProviderMetadata {
+        ProviderMetadata {
+            name: "scripted".to_string(),
+            models: vec![],
+            capabilities: ProviderCapabilities {
+                streaming: false,
+                tools: true,
+                vision: false,
+            },
+        }
+    }
+
+    async fn complete(
+        &self,
+        messages: &[Message],
+        _tools: &[Tool],
+    ) -> Result<Message, ProviderError> {
+        self.requests.lock().unwrap().push(messages.to_vec());
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .ok_or_else(|| ProviderError::Api("No scripted response left".to_string()))
+    }
+
+    async fn complete_streaming(
+        &self,
+        _messages: &[Message],
+        _tools: &[Tool],
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<Message, ProviderError>> + Send>>, ProviderError>
+    {
+        Err(ProviderError::Api("Streaming not supported".to_string()))
+    }
+}
+
+/// Assistant message requesting a single tool call
+pub fn tool_call(id: &str, name: &str, arguments: &str) -> Message {
+    let mut message = Message::assistant("");
+    message.tool_calls = Some(vec![ToolCall {
+        id: id.to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }]);
+    message
+}
+
+/// Workspace rooted at the current directory (the crate root under `cargo test`)
+pub fn workspace() -> Arc<Workspace> {
+    Arc::new(Workspace::new(".", &WorkspaceConfig::default()).unwrap())
+}
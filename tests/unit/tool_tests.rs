@@ -1,23 +1,61 @@
+use serde_json::json;
 use std::sync::Arc;
-use tempfile::NamedTempFile;
+use tempfile::TempDir;
+use xzardgz::docgen::templates::TemplateRegistry;
+use xzardgz::error::CommandError;
 use xzardgz::providers::types::{FunctionCall, ToolCall};
+use xzardgz::repository::analyzer::{self, SymbolKind};
+use xzardgz::repository::cargo::{CargoProject, DependencyKind, TargetKind};
+use xzardgz::tools::ToolExecutor;
+use xzardgz::tools::approval::{
+    ApprovalConfig, ApprovalMode, ApprovalRequest, Approver, PolicyApprover,
+};
+use xzardgz::tools::audit::{AuditLog, AuditOutcome};
+use xzardgz::tools::builtin::workspace_tools;
+use xzardgz::tools::code_ops::{
+    CargoMetadataTool, FindDefinitionTool, FindReferencesTool, ListSymbolsTool,
+};
+use xzardgz::tools::command_ops::{CommandConfig, CommandRunner, RunCommandTool};
+use xzardgz::tools::custom_ops::CustomToolConfig;
+use xzardgz::tools::doc_ops::{
+    ListDocsTool, ReadDocTool, RenderTemplateTool, SearchDocsTool, WriteDocTool,
+};
+use xzardgz::tools::edit_ops::{ApplyPatchTool, EditFileTool, InsertLinesTool};
 use xzardgz::tools::executor::ToolExecutionDispatcher;
-use xzardgz::tools::file_ops::{ReadFileTool, WriteFileTool};
+use xzardgz::tools::file_ops::{MAX_FILE_BYTES, ReadFileTool, WriteFileTool};
+use xzardgz::tools::git_ops::{
+    GitBlameTool, GitBranchesTool, GitDiffTool, GitLogTool, GitShowTool, GitStatusTool,
+};
+use xzardgz::tools::profile::{ToolProfile, ToolProfilesConfig};
 use xzardgz::tools::registry::ToolRegistry;
+use xzardgz::tools::search_ops::{GlobFilesTool, GrepTool, ListDirectoryTool};
+use xzardgz::tools::workspace::{PathViolation, Workspace, WorkspaceConfig};
+
+fn temp_workspace() -> (TempDir, Arc<Workspace>) {
+    let dir = TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    (dir, workspace)
+}
 
 #[tokio::test]
 async fn test_tool_execution() {
+    let (_dir, workspace) = temp_workspace();
     let mut registry = ToolRegistry::new();
 
     // Register tools
-    registry.register(ReadFileTool::definition(), Arc::new(ReadFileTool));
-    registry.register(WriteFileTool::definition(), Arc::new(WriteFileTool));
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
 
     let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry));
 
     // Test Write
-    let temp_file = NamedTempFile::new().unwrap();
-    let path = temp_file.path().to_str().unwrap().to_string();
+    let path = "notes.txt";
 
     let write_call = ToolCall {
         id: "call_1".to_string(),
@@ -58,6 +96,1977 @@ async fn test_unknown_tool() {
         },
     };
 
-    let result = dispatcher.execute(&call).await;
-    assert!(result.is_err());
+    // Reported to the model rather than ending its turn
+    let result = dispatcher.execute(&call).await.unwrap();
+    let report: serde_json::Value = serde_json::from_str(&result.error.unwrap()).unwrap();
+    assert_eq!(report["error"], "unknown_tool");
+    assert_eq!(report["tool"], "unknown");
+}
+
+#[tokio::test]
+async fn test_mutating_tool_denied() {
+    let (dir, workspace) = temp_workspace();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
+
+    let config = ApprovalConfig {
+        mode: ApprovalMode::Approve,
+        auto_approve: vec![],
+        auto_deny: vec!["write_file".to_string()],
+    };
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry))
+        .with_approver(Arc::new(PolicyApprover::new(&config)));
+
+    let path = dir.path().join("denied.txt");
+    let call = ToolCall {
+        id: "call_4".to_string(),
+        function: FunctionCall {
+            name: "write_file".to_string(),
+            arguments: json!({"path": path, "content": "nope"}).to_string(),
+        },
+    };
+
+    let result = dispatcher.execute(&call).await.unwrap();
+    assert!(result.error.unwrap().contains("denied (auto_deny)"));
+    assert!(!path.exists());
+
+    let approvals = dispatcher.approvals();
+    assert_eq!(approvals.len(), 1);
+    assert_eq!(approvals[0].call_id, "call_4");
+    assert!(!approvals[0].decision.is_approved());
+}
+
+#[tokio::test]
+async fn test_policy_approver_lists() {
+    let config = ApprovalConfig {
+        mode: ApprovalMode::Deny,
+        auto_approve: vec!["write_file".to_string()],
+        auto_deny: vec![],
+    };
+    let approver = PolicyApprover::new(&config);
+
+    let request = |tool: &str| ApprovalRequest {
+        tool: tool.to_string(),
+        arguments: json!({}),
+        preview: None,
+    };
+
+    let decision = approver.approve(&request("write_file")).await;
+    assert!(decision.is_approved());
+    assert_eq!(decision.policy(), "auto_approve");
+
+    let decision = approver.approve(&request("other_tool")).await;
+    assert!(!decision.is_approved());
+    assert_eq!(decision.policy(), "deny");
+}
+
+#[test]
+fn test_write_file_preview_is_diff() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("notes.txt"), "old line\n").unwrap();
+
+    let preview = WriteFileTool::new(workspace)
+        .preview(&json!({"path": "notes.txt", "content": "new line\n"}))
+        .unwrap();
+    assert!(preview.contains("-old line"));
+    assert!(preview.contains("+new line"));
+}
+
+#[test]
+fn test_workspace_rejects_escapes() {
+    let (_dir, workspace) = temp_workspace();
+
+    assert!(workspace.resolve_read("docs/guide.md").is_ok());
+    assert!(workspace.resolve_read("docs/../README.md").is_ok());
+    assert_eq!(
+        workspace.resolve_read("../outside.txt"),
+        Err(PathViolation::OutsideWorkspace(
+            "../outside.txt".to_string()
+        ))
+    );
+    assert!(matches!(
+        workspace.resolve_read("/etc/passwd"),
+        Err(PathViolation::OutsideWorkspace(_))
+    ));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_workspace_rejects_symlinks() {
+    let (dir, workspace) = temp_workspace();
+    let outside = TempDir::new().unwrap();
+    std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+
+    assert_eq!(
+        workspace.resolve_read("link/secret.txt"),
+        Err(PathViolation::Symlink("link/secret.txt".to_string()))
+    );
+}
+
+#[test]
+fn test_workspace_deny_and_read_only_globs() {
+    let (_dir, workspace) = temp_workspace();
+
+    assert!(matches!(
+        workspace.resolve_read("config/.env"),
+        Err(PathViolation::Denied(_))
+    ));
+    assert!(matches!(
+        workspace.resolve_read("certs/server.pem"),
+        Err(PathViolation::Denied(_))
+    ));
+    assert!(workspace.resolve_read(".git/config").is_ok());
+    assert_eq!(
+        workspace.resolve_write(".git/config"),
+        Err(PathViolation::ReadOnly(".git/config".to_string()))
+    );
+}
+
+#[tokio::test]
+async fn test_file_tools_report_violations_as_failures() {
+    let (_dir, workspace) = temp_workspace();
+
+    let result = ReadFileTool::new(workspace.clone())
+        .execute(json!({"path": "../../etc/passwd"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("outside the workspace"));
+
+    let result = WriteFileTool::new(workspace)
+        .execute(json!({"path": ".git/HEAD", "content": "oops"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("read-only"));
+}
+
+/// A small git checkout: `.gitignore` only applies inside a repository
+fn search_workspace() -> (TempDir, Arc<Workspace>) {
+    let (dir, workspace) = temp_workspace();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join(".git")).unwrap();
+    std::fs::create_dir_all(root.join("src/tools")).unwrap();
+    std::fs::create_dir_all(root.join("build")).unwrap();
+    std::fs::write(root.join(".gitignore"), "build/\n").unwrap();
+    std::fs::write(root.join(".git/HEAD"), "ref: refs/heads/main\n").unwrap();
+    std::fs::write(root.join(".env"), "TOKEN=fn secret\n").unwrap();
+    std::fs::write(root.join("build/out.rs"), "fn generated() {}\n").unwrap();
+    std::fs::write(
+        root.join("src/lib.rs"),
+        "mod tools;\n\npub fn run() {}\n\nfn helper() {}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("src/tools/mod.rs"), "pub fn tool() {}\n").unwrap();
+    std::fs::write(root.join("README.md"), "# Demo\n").unwrap();
+    (dir, workspace)
+}
+
+#[tokio::test]
+async fn test_list_directory_respects_depth_and_ignores() {
+    let (_dir, workspace) = search_workspace();
+    let tool = ListDirectoryTool::new(workspace, vec![]);
+
+    let output = tool.execute(json!({})).await.unwrap().output;
+    let listed: Vec<&str> = output.lines().collect();
+    assert_eq!(listed, vec![".gitignore", "README.md", "src/"]);
+
+    let output = tool
+        .execute(json!({"path": "src", "depth": 2}))
+        .await
+        .unwrap()
+        .output;
+    let listed: Vec<&str> = output.lines().collect();
+    assert_eq!(listed, vec!["src/lib.rs", "src/tools/", "src/tools/mod.rs"]);
+
+    let result = tool.execute(json!({"path": ".."})).await.unwrap();
+    assert!(result.error.unwrap().contains("outside the workspace"));
+}
+
+#[tokio::test]
+async fn test_glob_files_matches_relative_paths() {
+    let (_dir, workspace) = search_workspace();
+    let tool = GlobFilesTool::new(workspace, vec![]);
+
+    let output = tool
+        .execute(json!({"pattern": "src/**/*.rs"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, "src/lib.rs\nsrc/tools/mod.rs");
+
+    // `*` stays within one directory
+    let output = tool
+        .execute(json!({"pattern": "src/*.rs"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, "src/lib.rs");
+
+    let result = tool.execute(json!({"pattern": "src/[.rs"})).await.unwrap();
+    assert!(result.error.unwrap().contains("Invalid glob"));
+}
+
+#[tokio::test]
+async fn test_grep_context_and_max_results() {
+    let (_dir, workspace) = search_workspace();
+    let tool = GrepTool::new(workspace, vec![]);
+
+    // Ignored and denied files are never searched
+    let output = tool
+        .execute(json!({"pattern": r"\bfn \w+"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/lib.rs:3: pub fn run() {}\nsrc/lib.rs:5: fn helper() {}\nsrc/tools/mod.rs:1: pub fn tool() {}"
+    );
+
+    let output = tool
+        .execute(json!({"pattern": "RUN", "case_insensitive": true, "context": 1}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/lib.rs-2- \nsrc/lib.rs:3: pub fn run() {}\nsrc/lib.rs-4- "
+    );
+
+    let output = tool
+        .execute(json!({"pattern": "fn", "glob": "src/*.rs", "max_results": 1}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/lib.rs:3: pub fn run() {}\n(stopped after 1 matches)"
+    );
+
+    let result = tool.execute(json!({"pattern": "("})).await.unwrap();
+    assert!(result.error.unwrap().contains("Invalid regex"));
+}
+
+#[tokio::test]
+async fn test_edit_file_requires_unique_match() {
+    let (dir, workspace) = temp_workspace();
+    let path = dir.path().join("guide.md");
+    std::fs::write(&path, "# Guide\n\nStep one.\nStep two.\n").unwrap();
+    let tool = EditFileTool::new(workspace);
+
+    let result = tool
+        .execute(json!({"path": "guide.md", "old_text": "Step three.", "new_text": "x"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("was not found in guide.md"));
+
+    let result = tool
+        .execute(json!({"path": "guide.md", "old_text": "Step", "new_text": "Stage"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("occurs 2 times"));
+
+    let params = json!({"path": "guide.md", "old_text": "Step two.", "new_text": "Step 2."});
+    let preview = tool.preview(&params).unwrap();
+    assert!(preview.contains("-Step two.\n+Step 2."));
+    let result = tool.execute(params).await.unwrap();
+    assert!(result.error.is_none());
+
+    let result = tool
+        .execute(json!({"path": "guide.md", "old_text": "Step", "new_text": "Stage", "replace_all": true}))
+        .await
+        .unwrap();
+    assert!(result.error.is_none());
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "# Guide\n\nStage one.\nStage 2.\n"
+    );
+}
+
+#[tokio::test]
+async fn test_insert_lines() {
+    let (dir, workspace) = temp_workspace();
+    let path = dir.path().join("list.txt");
+    std::fs::write(&path, "a\nc").unwrap();
+    let tool = InsertLinesTool::new(workspace);
+
+    tool.execute(json!({"path": "list.txt", "line": 2, "content": "b"}))
+        .await
+        .unwrap();
+    tool.execute(json!({"path": "list.txt", "line": 4, "content": "d\n"}))
+        .await
+        .unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "a\nb\nc\nd\n");
+
+    let result = tool
+        .execute(json!({"path": "list.txt", "line": 9, "content": "z"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("has 4 lines; use 1 to 5"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_and_backups() {
+    let dir = TempDir::new().unwrap();
+    let config = WorkspaceConfig {
+        backups: true,
+        ..WorkspaceConfig::default()
+    };
+    let workspace = Arc::new(Workspace::new(dir.path(), &config).unwrap());
+    let path = dir.path().join("notes.md");
+    std::fs::write(&path, "one\ntwo\nthree\n").unwrap();
+    let tool = ApplyPatchTool::new(workspace);
+
+    let patch = "--- a/notes.md\n+++ b/notes.md\n@@ -1,3 +1,3 @@\n one\n-two\n+TWO\n three\n";
+    let result = tool
+        .execute(json!({"path": "notes.md", "patch": patch}))
+        .await
+        .unwrap();
+    assert_eq!(result.output, "Applied 1 hunk(s) to notes.md");
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\nTWO\nthree\n");
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("notes.md.bak")).unwrap(),
+        "one\ntwo\nthree\n"
+    );
+
+    // The same patch no longer matches
+    let result = tool
+        .execute(json!({"path": "notes.md", "patch": patch}))
+        .await
+        .unwrap();
+    assert!(
+        result
+            .error
+            .unwrap()
+            .contains("Patch does not apply to notes.md")
+    );
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "one\nTWO\nthree\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_backups_never_overwrite_or_follow_symlinks() {
+    let dir = TempDir::new().unwrap();
+    let config = WorkspaceConfig {
+        backups: true,
+        ..WorkspaceConfig::default()
+    };
+    let workspace = Workspace::new(dir.path(), &config).unwrap();
+    let path = dir.path().join("notes.md");
+
+    for version in ["one", "two"] {
+        std::fs::write(&path, version).unwrap();
+        workspace.backup(&path).unwrap();
+    }
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("notes.md.bak")).unwrap(),
+        "one"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("notes.md.bak.1")).unwrap(),
+        "two"
+    );
+
+    // A planted symlink must not redirect the backup outside the workspace
+    let outside = TempDir::new().unwrap();
+    let target = outside.path().join("stolen.md");
+    let plan = dir.path().join("plan.md");
+    std::fs::write(&plan, "secret").unwrap();
+    std::os::unix::fs::symlink(&target, dir.path().join("plan.md.bak")).unwrap();
+    let error = workspace.backup(&plan).unwrap_err();
+    assert!(error.to_string().contains("goes through a symlink"));
+    assert!(!target.exists());
+}
+
+#[tokio::test]
+async fn test_read_file_ranges_and_truncation() {
+    let (dir, workspace) = temp_workspace();
+    let content: String = (1..=10).map(|i| format!("line {}\n", i)).collect();
+    std::fs::write(dir.path().join("long.txt"), &content).unwrap();
+    let tool = ReadFileTool::new(workspace);
+
+    let output = tool
+        .execute(json!({"path": "long.txt"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, content);
+
+    let output = tool
+        .execute(json!({"path": "long.txt", "offset": 3, "limit": 2, "line_numbers": true}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "     3\tline 3\n     4\tline 4\n[lines 3-4 of 10; use offset=5 to read more]"
+    );
+
+    let output = tool
+        .execute(json!({"path": "long.txt", "offset": 9, "max_bytes": 10}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "line 9\n[lines 9-9 of 10, truncated at 10 bytes; use offset=10 to read more]"
+    );
+
+    let result = tool
+        .execute(json!({"path": "long.txt", "offset": 11}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("past the end"));
+}
+
+#[tokio::test]
+async fn test_read_file_detects_binary_and_encodings() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(
+        dir.path().join("image.png"),
+        b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR",
+    )
+    .unwrap();
+    let utf16: Vec<u8> = [0xFF, 0xFE]
+        .into_iter()
+        .chain("hi\n".encode_utf16().flat_map(u16::to_le_bytes))
+        .collect();
+    std::fs::write(dir.path().join("utf16.txt"), utf16).unwrap();
+    let tool = ReadFileTool::new(workspace);
+
+    let result = tool.execute(json!({"path": "image.png"})).await.unwrap();
+    assert!(result.error.unwrap().contains("Binary file"));
+
+    let output = tool
+        .execute(json!({"path": "utf16.txt"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, "hi\n[decoded from UTF-16]");
+}
+
+#[tokio::test]
+async fn test_read_file_refuses_huge_files_and_directories() {
+    let (dir, workspace) = temp_workspace();
+    // Sparse, so the test does not write the bytes
+    let huge = std::fs::File::create(dir.path().join("huge.log")).unwrap();
+    huge.set_len(MAX_FILE_BYTES + 1).unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    let tool = ReadFileTool::new(workspace);
+
+    let result = tool
+        .execute(json!({"path": "huge.log", "limit": 1}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("more than the"));
+    let result = tool.execute(json!({"path": "src"})).await.unwrap();
+    assert!(result.error.unwrap().contains("not a regular file"));
+}
+
+#[tokio::test]
+async fn test_dispatcher_truncates_tool_output() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("big.txt"), "é".repeat(100)).unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace)),
+    );
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry)).with_output_limit(15);
+
+    let call = ToolCall {
+        id: "call_1".to_string(),
+        function: FunctionCall {
+            name: "read_file".to_string(),
+            arguments: json!({"path": "big.txt"}).to_string(),
+        },
+    };
+    let result = dispatcher.execute(&call).await.unwrap();
+    assert_eq!(
+        result.output,
+        format!(
+            "{}\n[Output truncated: showing 14 of 200 bytes]",
+            "é".repeat(7)
+        )
+    );
+}
+
+/// Commit the current contents of `files` in a repository at `dir`
+fn commit_files(repo: &git2::Repository, files: &[&str], message: &str) {
+    let mut index = repo.index().unwrap();
+    for file in files {
+        index.add_path(std::path::Path::new(file)).unwrap();
+    }
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    let signature = git2::Signature::now("Ada", "ada@example.com").unwrap();
+    let parent = repo.head().ok().map(|head| head.peel_to_commit().unwrap());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .unwrap();
+}
+
+fn git_workspace() -> (TempDir, Arc<Workspace>) {
+    let (dir, workspace) = temp_workspace();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    std::fs::write(dir.path().join("README.md"), "# Demo\n").unwrap();
+    std::fs::write(dir.path().join(".env"), "TOKEN=1\n").unwrap();
+    commit_files(&repo, &["README.md", ".env"], "Initial commit");
+    std::fs::write(dir.path().join("guide.md"), "one\ntwo\n").unwrap();
+    commit_files(&repo, &["guide.md"], "Add guide\n\nWith two lines.");
+    (dir, workspace)
+}
+
+#[tokio::test]
+async fn test_git_log_show_and_blame() {
+    let (_dir, workspace) = git_workspace();
+
+    let output = GitLogTool::new(workspace.clone())
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].ends_with("Ada: Add guide"));
+    assert!(lines[1].ends_with("Ada: Initial commit"));
+
+    let output = GitLogTool::new(workspace.clone())
+        .execute(json!({"path": "README.md"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.ends_with("Ada: Initial commit") && output.lines().count() == 1);
+
+    let output = GitShowTool::new(workspace.clone())
+        .execute(json!({"rev": "HEAD"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("Author: Ada <ada@example.com>"));
+    assert!(output.contains("    With two lines."));
+    assert!(output.contains("+one\n+two\n"));
+
+    // Denied files are hidden from diffs
+    let output = GitShowTool::new(workspace.clone())
+        .execute(json!({"rev": "HEAD~1"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("+# Demo"));
+    assert!(!output.contains("TOKEN"));
+    assert!(output.contains("[1 file(s) hidden by the workspace policy]"));
+
+    let output = GitBlameTool::new(workspace.clone())
+        .execute(json!({"path": "guide.md", "start_line": 2}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("(Ada ") && output.ends_with("    2) two"));
+    assert_eq!(output.lines().count(), 1);
+
+    let result = GitBlameTool::new(workspace)
+        .execute(json!({"path": ".env"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("denied"));
+}
+
+#[tokio::test]
+async fn test_git_diff_and_branches() {
+    let (dir, workspace) = git_workspace();
+    let repo = git2::Repository::open(dir.path()).unwrap();
+    std::fs::write(dir.path().join("guide.md"), "one\n2\n").unwrap();
+    std::fs::write(dir.path().join("README.md"), "# Demo app\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(std::path::Path::new("README.md")).unwrap();
+    index.write().unwrap();
+    let tool = GitDiffTool::new(workspace.clone());
+
+    let unstaged = tool.execute(json!({})).await.unwrap().output;
+    assert!(unstaged.contains("-two\n+2\n") && !unstaged.contains("Demo app"));
+
+    let staged = tool.execute(json!({"staged": true})).await.unwrap().output;
+    assert!(staged.contains("+# Demo app") && !staged.contains("+2"));
+
+    let all = tool
+        .execute(json!({"from": "HEAD", "paths": ["guide.md"]}))
+        .await
+        .unwrap()
+        .output;
+    assert!(all.contains("+2") && !all.contains("Demo app"));
+
+    let between = tool
+        .execute(json!({"from": "HEAD~1", "to": "HEAD"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(between.contains("+++ b/guide.md"));
+
+    let result = tool.execute(json!({"from": "nope"})).await.unwrap();
+    assert!(result.error.is_some());
+
+    let head = repo.head().unwrap().peel_to_commit().unwrap();
+    repo.branch("feature", &head, false).unwrap();
+    let output = GitBranchesTool::new(workspace)
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines.iter().any(|line| line.starts_with("  feature ")));
+    assert!(lines.iter().any(|line| line.starts_with("* ")));
+}
+
+#[tokio::test]
+async fn test_git_tools_in_a_subdirectory_workspace() {
+    let dir = TempDir::new().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    std::fs::create_dir(dir.path().join("app")).unwrap();
+    std::fs::write(dir.path().join("top.md"), "top\n").unwrap();
+    std::fs::write(dir.path().join("app/main.rs"), "fn main() {}\n").unwrap();
+    commit_files(&repo, &["top.md", "app/main.rs"], "Initial commit");
+    std::fs::write(dir.path().join("top.md"), "top only\n").unwrap();
+    commit_files(&repo, &["top.md"], "Change the top");
+    std::fs::write(dir.path().join("top.md"), "top again\n").unwrap();
+    std::fs::write(dir.path().join("app/main.rs"), "fn main() { run() }\n").unwrap();
+
+    let workspace =
+        Arc::new(Workspace::new(dir.path().join("app"), &WorkspaceConfig::default()).unwrap());
+
+    // Paths are workspace-relative, and changes outside the workspace are left out
+    let output = GitDiffTool::new(workspace.clone())
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("+++ b/main.rs") && output.contains("+fn main() { run() }"));
+    assert!(!output.contains("top"));
+
+    let output = GitDiffTool::new(workspace.clone())
+        .execute(json!({"paths": ["main.rs"]}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("+++ b/main.rs"));
+
+    let output = GitShowTool::new(workspace.clone())
+        .execute(json!({"rev": "HEAD~1"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("+++ b/main.rs") && !output.contains("+top"));
+
+    let output = GitLogTool::new(workspace.clone())
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.ends_with("Ada: Initial commit") && output.lines().count() == 1);
+
+    let output = GitBlameTool::new(workspace.clone())
+        .execute(json!({"path": "main.rs"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.ends_with("    1) fn main() {}"));
+
+    std::fs::write(dir.path().join("app/.env"), "TOKEN=secret\n").unwrap();
+    std::fs::write(dir.path().join("app/new.rs"), "\n").unwrap();
+    let output = GitStatusTool::new(workspace)
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        " M main.rs\n?? new.rs\n[1 file(s) hidden by the workspace policy]\n"
+    );
+}
+
+fn command_tool(config: CommandConfig) -> (TempDir, RunCommandTool) {
+    let (dir, workspace) = temp_workspace();
+    let runner = Arc::new(CommandRunner::new(workspace, config));
+    (dir, RunCommandTool::new(runner))
+}
+
+#[tokio::test]
+async fn test_run_command_returns_structured_output() {
+    let (dir, tool) = command_tool(CommandConfig::default());
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+
+    let result = tool
+        .execute(json!({"command": "pwd", "cwd": "sub"}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["exit_code"], 0);
+    assert!(
+        output["stdout"]
+            .as_str()
+            .unwrap()
+            .trim_end()
+            .ends_with("/sub")
+    );
+
+    // Quoted words stay together and are never interpolated
+    let result = tool
+        .execute(json!({"command": "echo 'a  b' \"$HOME\""}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["stdout"], "a  b $HOME\n");
+
+    let result = tool
+        .execute(json!({"command": "ls", "args": ["does-not-exist"]}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_ne!(output["exit_code"], 0);
+    assert!(!output["stderr"].as_str().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_run_command_policy() {
+    let (_dir, tool) = command_tool(CommandConfig::default());
+    let failure = |result: xzardgz::tools::ToolResult| result.error.unwrap();
+
+    let result = tool
+        .execute(json!({"command": "/bin/rm -rf ."}))
+        .await
+        .unwrap();
+    assert!(failure(result).contains("rm is denied"));
+    let result = tool
+        .execute(json!({"command": "python3 -V"}))
+        .await
+        .unwrap();
+    assert!(failure(result).contains("python3 is not in the allowed programs"));
+    let result = tool
+        .execute(json!({"command": "cat Cargo.toml | head"}))
+        .await
+        .unwrap();
+    assert!(failure(result).contains("shell syntax"));
+    let result = tool
+        .execute(json!({"command": "ls", "cwd": "../.."}))
+        .await
+        .unwrap();
+    assert!(failure(result).contains("outside the workspace"));
+    let result = tool
+        .execute(json!({"command": "echo hi | head", "shell": true}))
+        .await
+        .unwrap();
+    assert!(failure(result).contains("Shell commands are disabled"));
+}
+
+#[tokio::test]
+async fn test_run_command_shell_env_and_limits() {
+    let config = CommandConfig {
+        allow: vec![],
+        allow_shell: true,
+        timeout_seconds: 1,
+        max_output_bytes: 4,
+        ..CommandConfig::default()
+    };
+    let (_dir, tool) = command_tool(config);
+
+    // Cargo sets this for the test process, but it is not in commands.env
+    let result = tool
+        .execute(json!({"command": "echo \"x${CARGO_MANIFEST_DIR}\" | tr -d x", "shell": true}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["stdout"], "\n");
+
+    let result = tool
+        .execute(json!({"command": "echo 0123456789"}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["stdout"], "0123");
+    assert_eq!(output["truncated"], true);
+
+    let result = tool
+        .execute(json!({"command": "sleep 5", "timeout_seconds": 30}))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["timed_out"], true);
+    assert_eq!(output["exit_code"], serde_json::Value::Null);
+
+    let result = tool
+        .execute(json!({"command": "echo `rm -rf /`", "shell": true}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("substitution"));
+}
+
+#[test]
+fn test_shell_commands_check_every_program() {
+    let dir = TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let config = CommandConfig {
+        allow_shell: true,
+        ..CommandConfig::default()
+    };
+    let runner = CommandRunner::new(workspace.clone(), config);
+    let error = |command: &str| runner.parse(command, true).unwrap_err().to_string();
+
+    for command in [
+        "cargo test 2>&1 | wc -l",
+        "echo 'a | b > c' && ls >/dev/null",
+        "for f in src; do ls \"$f\"; done",
+        "if [[ -d src ]]; then ls src; fi",
+        "RUST_LOG=debug cargo test",
+        "wc -c <<< hello",
+    ] {
+        assert!(runner.parse(command, true).is_ok(), "{}", command);
+    }
+
+    // Assignments, reserved words and grouping do not hide the program
+    assert_eq!(error("x=1 rm -rf ."), "rm is denied by the command policy");
+    assert_eq!(error("{ rm -rf .; }"), "rm is denied by the command policy");
+    assert_eq!(error("! /bin/rm x"), "rm is denied by the command policy");
+    assert_eq!(error("ls && (rm x)"), "rm is denied by the command policy");
+    assert!(error("x=rm; $x -rf .").contains("named literally"));
+    assert!(error("/bin/r? -rf .").contains("named literally"));
+    assert!(error("eval 'r''m -rf .'").contains("cannot be checked"));
+
+    // Wrappers must be allowed themselves, and what they run is checked too
+    assert_eq!(error("env rm -rf ."), "env is not in the allowed programs");
+    let config = CommandConfig {
+        allow: vec![],
+        allow_shell: true,
+        ..CommandConfig::default()
+    };
+    let open = CommandRunner::new(workspace, config);
+    let error = |command: &str| open.parse(command, true).unwrap_err().to_string();
+    for command in [
+        "env x=1 rm -rf .",
+        "exec rm -rf .",
+        "command rm -rf .",
+        "nohup rm -rf . &",
+        "ls | xargs rm",
+        "/usr/bin/env rm -rf .",
+        "time nohup env rm x",
+    ] {
+        assert_eq!(
+            error(command),
+            "rm is denied by the command policy",
+            "{}",
+            command
+        );
+    }
+    assert!(error("env -S 'rm -rf .'").contains("Options of `env`"));
+    assert!(error("xargs -I{} rm {}").contains("Options of `xargs`"));
+    assert!(open.parse("env LANG=C sort", true).is_ok());
+
+    // Files outside the file tools' reach cannot be written or read
+    assert!(error("echo hi > ../outside").contains("Redirects"));
+    assert!(error("echo hi >>notes.md").contains("Redirects"));
+    assert!(error("cat < /etc/passwd").contains("Redirects"));
+    assert!(error("ls 2>errors.log").contains("Redirects"));
+    assert!(error("ls &> all.log").contains("Redirects"));
+    assert!(error("cat <<EOF").contains("Here-documents"));
+    assert!(error("echo >").contains("needs a target"));
+}
+
+#[test]
+fn test_find_and_rg_cannot_run_other_programs() {
+    let (_dir, workspace) = temp_workspace();
+    let config = CommandConfig {
+        allow: vec!["find".to_string(), "rg".to_string(), "xargs".to_string()],
+        allow_shell: true,
+        ..CommandConfig::default()
+    };
+    let runner = CommandRunner::new(workspace, config);
+
+    for (command, shell) in [
+        ("find . -exec rm {} ';'", false),
+        ("find . -name '*.rs' -delete", false),
+        ("find . -execdir rm {} \\;", true),
+        ("find . | xargs find -ok rm {} \\;", true),
+        ("rg --pre=./script x", false),
+        ("rg --pre ./script x", true),
+    ] {
+        let error = runner.parse(command, shell).unwrap_err().to_string();
+        assert!(error.contains("is not allowed"), "{}: {}", command, error);
+    }
+    let error = runner
+        .spec(
+            "find".to_string(),
+            vec![".".to_string(), "-exec".to_string()],
+        )
+        .unwrap_err();
+    assert!(error.to_string().contains("`-exec` of `find`"));
+    assert!(runner.parse("find . -name '*.rs'", false).is_ok());
+    assert!(runner.parse("rg -n --type rust fn", false).is_ok());
+
+    // Programs that read any path are left out of the defaults
+    let (_dir, workspace) = temp_workspace();
+    let runner = CommandRunner::new(workspace, CommandConfig::default());
+    for command in [
+        "cat .env",
+        "head ../../etc/passwd",
+        "find . -exec rm {} ';'",
+    ] {
+        assert!(matches!(
+            runner.parse(command, false),
+            Err(CommandError::NotAllowed(_))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_run_command_timeout_reaches_background_processes() {
+    let dir = TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let config = CommandConfig {
+        allow: vec![],
+        timeout_seconds: 1,
+        ..CommandConfig::default()
+    };
+    let runner = CommandRunner::new(workspace, config);
+    let run = |script: &str| {
+        let spec = runner
+            .spec("sh".to_string(), vec!["-c".to_string(), script.to_string()])
+            .unwrap();
+        let dir = dir.path().to_path_buf();
+        let runner = &runner;
+        async move {
+            let started = std::time::Instant::now();
+            let output = runner.run(&spec, &dir, None).await.unwrap();
+            (output, started.elapsed())
+        }
+    };
+
+    // `cat` would keep stdout open after sh is killed
+    let (output, elapsed) = run("sleep 100 | cat").await;
+    assert!(output.timed_out);
+    assert!(elapsed < std::time::Duration::from_secs(5), "{:?}", elapsed);
+
+    // sh exits at once, but the backgrounded sleep still holds the pipes
+    let (output, elapsed) = run("echo started; sleep 100 &").await;
+    assert_eq!(output.exit_code, Some(0));
+    assert_eq!(output.stdout, "started\n");
+    assert!(output.truncated);
+    assert!(elapsed < std::time::Duration::from_secs(5), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn test_doc_tools_follow_diataxis_tree() {
+    let (dir, workspace) = temp_workspace();
+    let write = WriteDocTool::new(workspace.clone());
+
+    let result = write
+        .execute(json!({"category": "how_to", "name": "deploy", "content": "# Deploy\n\nRun make deploy.\n"}))
+        .await
+        .unwrap();
+    assert_eq!(result.output, "Wrote docs/how_to/deploy.md");
+    assert!(dir.path().join("docs/how_to/deploy.md").exists());
+
+    let result = write
+        .execute(json!({"category": "how-to", "name": "deploy.md", "content": "# Other\n"}))
+        .await
+        .unwrap();
+    assert!(
+        result
+            .error
+            .unwrap()
+            .contains("set overwrite to replace it")
+    );
+
+    let result = write
+        .execute(json!({"category": "reference", "name": "../../escape.md", "content": "x"}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("Invalid document name"));
+
+    let output = ListDocsTool::new(workspace.clone())
+        .execute(json!({}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("how-to (docs/how_to):\n  deploy.md - Deploy"));
+    assert!(output.contains("tutorial (docs/tutorials):\n  (none)"));
+
+    let output = ReadDocTool::new(workspace.clone())
+        .execute(json!({"category": "HowTo", "name": "deploy.md"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.starts_with("# Deploy"));
+
+    let output = SearchDocsTool::new(workspace)
+        .execute(json!({"query": "MAKE DEPLOY"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, "docs/how_to/deploy.md:3: Run make deploy.");
+}
+
+#[tokio::test]
+async fn test_render_template_tool() {
+    let tool = RenderTemplateTool::new(Arc::new(TemplateRegistry::new().unwrap()));
+
+    let output = tool
+        .execute(json!({
+            "category": "how-to",
+            "data": {"title": "deploy", "problem": "P", "solution": "S", "steps": ["one", "two"]}
+        }))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.starts_with("# How to deploy"));
+    assert!(output.contains("1. one\n") && output.contains("1. two\n"));
+
+    let result = tool
+        .execute(json!({"category": "novel", "data": {}}))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("Unknown category novel"));
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+        id: "call_schema".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    }
+}
+
+#[tokio::test]
+async fn test_dispatcher_rejects_invalid_arguments() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
+    // Denies everything, so a validation error must come before approval
+    let config = ApprovalConfig {
+        mode: ApprovalMode::Deny,
+        auto_approve: vec![],
+        auto_deny: vec![],
+    };
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry))
+        .with_approver(Arc::new(PolicyApprover::new(&config)));
+
+    let result = dispatcher
+        .execute(&call("read_file", json!({"path": 42, "limit": "ten"})))
+        .await
+        .unwrap();
+    let report: serde_json::Value = serde_json::from_str(&result.error.unwrap()).unwrap();
+    assert_eq!(report["error"], "invalid_arguments");
+    assert_eq!(report["tool"], "read_file");
+    let violations = report["violations"].as_array().unwrap();
+    assert_eq!(violations.len(), 2);
+    assert_eq!(violations[0]["path"], "/limit");
+    assert!(
+        violations[0]["message"]
+            .as_str()
+            .unwrap()
+            .contains("expected integer")
+    );
+    assert_eq!(violations[1]["path"], "/path");
+
+    let result = dispatcher
+        .execute(&call("write_file", json!({"path": "b.txt"})))
+        .await
+        .unwrap();
+    let error = result.error.unwrap();
+    assert!(error.contains("/content"));
+    assert!(error.contains("is required"));
+    assert!(!dir.path().join("b.txt").exists());
+
+    let result = dispatcher
+        .execute(&call("read_file", json!({"path": "a.txt", "offset": -1})))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("must be at least 0"));
+
+    let mut broken = call("read_file", json!({}));
+    broken.function.arguments = "{\"path\": ".to_string();
+    let result = dispatcher.execute(&broken).await.unwrap();
+    let report: serde_json::Value = serde_json::from_str(&result.error.unwrap()).unwrap();
+    assert_eq!(report["error"], "invalid_arguments");
+    assert!(
+        report["violations"][0]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("not valid JSON")
+    );
+}
+
+#[tokio::test]
+async fn test_dispatcher_applies_defaults_and_drops_nulls() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace)),
+    );
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry));
+
+    let result = dispatcher
+        .execute(&call(
+            "read_file",
+            json!({"path": "a.txt", "offset": null, "limit": null, "line_numbers": null}),
+        ))
+        .await
+        .unwrap();
+    assert!(result.error.is_none());
+    assert_eq!(result.output, "one\ntwo\n");
+}
+
+#[test]
+fn test_schema_validation_keywords() {
+    use xzardgz::tools::schema::validate;
+
+    let schema = json!({
+        "type": "object",
+        "properties": {
+            "mode": {"type": "string", "enum": ["fast", "slow"]},
+            "tags": {"type": "array", "items": {"type": "string"}},
+            "depth": {"type": "integer", "default": 3, "maximum": 5}
+        },
+        "required": ["mode"],
+        "additionalProperties": false
+    });
+
+    let mut value = json!({"mode": "fast"});
+    validate(&schema, &mut value).unwrap();
+    assert_eq!(value["depth"], 3);
+
+    let mut value = json!({"mode": "medium", "tags": ["a", 1], "depth": 9, "extra": true});
+    let violations = validate(&schema, &mut value).unwrap_err();
+    let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+    assert_eq!(paths, vec!["/depth", "/mode", "/tags/1", "/extra"]);
+    assert!(violations[1].message.contains(r#""fast", "slow""#));
+    assert!(
+        violations[3]
+            .to_string()
+            .starts_with("/extra: is not a known argument")
+    );
+
+    let violations = validate(&schema, &mut json!("fast")).unwrap_err();
+    assert_eq!(
+        violations[0].to_string(),
+        "arguments: expected object, got string \"fast\""
+    );
+}
+
+#[test]
+fn test_typed_arguments_generate_schema() {
+    let parameters = ReadFileTool::definition().parameters;
+    assert_eq!(parameters["type"], "object");
+    assert_eq!(parameters["required"], json!(["path"]));
+    assert!(parameters.get("$schema").is_none());
+    let properties = parameters["properties"].as_object().unwrap();
+    assert_eq!(properties["offset"]["type"], "integer");
+    assert_eq!(properties["line_numbers"]["default"], false);
+    for property in properties.values() {
+        assert!(
+            property["description"]
+                .as_str()
+                .is_some_and(|d| !d.is_empty())
+        );
+    }
+
+    let parameters = WriteFileTool::definition().parameters;
+    assert_eq!(parameters["required"], json!(["path", "content"]));
+
+    let parameters = RunCommandTool::definition().parameters;
+    assert_eq!(parameters["required"], json!(["command"]));
+    assert_eq!(parameters["properties"]["args"]["items"]["type"], "string");
+
+    // The category keeps its enum and stays optional where it filters
+    let parameters = SearchDocsTool::definition().parameters;
+    assert_eq!(parameters["required"], json!(["query"]));
+    assert_eq!(parameters["properties"]["category"]["enum"][1], "how-to");
+    assert!(
+        parameters["properties"]["category"]
+            .get("default")
+            .is_none()
+    );
+    let parameters = WriteDocTool::definition().parameters;
+    assert_eq!(
+        parameters["required"],
+        json!(["category", "name", "content"])
+    );
+}
+
+fn custom_tool(command: &[&str], parameters: serde_json::Value) -> CustomToolConfig {
+    serde_yaml::from_value(
+        serde_yaml::to_value(json!({
+            "name": "lint",
+            "description": "Lint some files",
+            "parameters": parameters,
+            "command": command,
+        }))
+        .unwrap(),
+    )
+    .unwrap()
+}
+
+#[tokio::test]
+async fn test_custom_tools_run_templated_commands() {
+    let (_dir, workspace) = temp_workspace();
+    let runner = Arc::new(CommandRunner::new(workspace, CommandConfig::default()));
+    let parameters = json!({
+        "type": "object",
+        "properties": {
+            "path": {"type": "string"},
+            "fix": {"type": "boolean", "default": false},
+            "files": {"type": "array", "items": {"type": "string"}}
+        },
+        "required": ["path"]
+    });
+    let mut stdin_tool = custom_tool(&["cat"], json!({"type": "object"}));
+    stdin_tool.name = "echo_json".to_string();
+    stdin_tool.input = xzardgz::tools::custom_ops::ArgumentInput::Stdin;
+    stdin_tool.mutating = false;
+
+    let mut registry = ToolRegistry::new();
+    registry
+        .register_custom_tools(
+            &[
+                custom_tool(
+                    &[
+                        "echo",
+                        "lint",
+                        "{{path}}",
+                        "{{#if fix}}--fix{{/if}}",
+                        "{{files}}",
+                    ],
+                    parameters,
+                ),
+                stdin_tool,
+            ],
+            &runner,
+        )
+        .unwrap();
+    assert_eq!(
+        registry.get_tool("lint").unwrap().description,
+        "Lint some files"
+    );
+    let lint = registry.get_executor("lint").unwrap();
+    assert!(lint.is_mutating());
+    assert!(!registry.get_executor("echo_json").unwrap().is_mutating());
+    assert_eq!(
+        lint.preview(&json!({"path": "src", "fix": true})).unwrap(),
+        "$ echo lint src --fix  (in .)"
+    );
+
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry));
+    let call = |name: &str, arguments: serde_json::Value| ToolCall {
+        id: "call_custom".to_string(),
+        function: FunctionCall {
+            name: name.to_string(),
+            arguments: arguments.to_string(),
+        },
+    };
+
+    // Each array item is its own word, and an empty flag is dropped
+    let result = dispatcher
+        .execute(&call(
+            "lint",
+            json!({"path": "src", "files": ["a.rs", "b c.rs"]}),
+        ))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    assert_eq!(output["stdout"], "lint src a.rs b c.rs\n");
+    assert_eq!(output["exit_code"], 0);
+
+    let result = dispatcher
+        .execute(&call("lint", json!({"fix": true})))
+        .await
+        .unwrap();
+    assert!(result.error.unwrap().contains("/path"));
+
+    let result = dispatcher
+        .execute(&call("echo_json", json!({"text": "<b> & \"q\""})))
+        .await
+        .unwrap();
+    let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+    let stdin: serde_json::Value =
+        serde_json::from_str(output["stdout"].as_str().unwrap()).unwrap();
+    assert_eq!(stdin, json!({"text": "<b> & \"q\""}));
+}
+
+#[test]
+fn test_custom_tool_config_errors() {
+    let (_dir, workspace) = temp_workspace();
+    let runner = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        CommandConfig::default(),
+    ));
+    let object = json!({"type": "object"});
+    let error = |tool: CustomToolConfig| {
+        let mut registry = ToolRegistry::new();
+        registry.register(
+            ReadFileTool::definition(),
+            Arc::new(ReadFileTool::new(workspace.clone())),
+        );
+        registry
+            .register_custom_tools(&[tool], &runner)
+            .unwrap_err()
+            .to_string()
+    };
+
+    assert!(error(custom_tool(&["{{program}}"], object.clone())).contains("cannot be templated"));
+    assert!(error(custom_tool(&[], object.clone())).contains("command is empty"));
+    assert!(error(custom_tool(&["echo", "{{#if x}}"], object.clone())).contains("command word"));
+    assert!(error(custom_tool(&["echo"], json!({"type": "string"}))).contains("object schema"));
+    let mut clash = custom_tool(&["echo"], object.clone());
+    clash.name = "read_file".to_string();
+    assert!(error(clash).contains("already exists"));
+    let mut bad_name = custom_tool(&["echo"], object);
+    bad_name.name = "run lint".to_string();
+    assert!(error(bad_name).contains("names may only use"));
+}
+
+#[tokio::test]
+async fn test_dispatcher_writes_audit_log() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("a.txt"), "hello").unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
+    let config = ApprovalConfig {
+        mode: ApprovalMode::Approve,
+        auto_approve: vec![],
+        auto_deny: vec!["write_file".to_string()],
+    };
+    let audit = Arc::new(AuditLog::create(dir.path().join("logs/run.jsonl")).unwrap());
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry))
+        .with_approver(Arc::new(PolicyApprover::new(&config)))
+        .with_audit_log(audit.clone());
+
+    for (name, arguments) in [
+        ("read_file", json!({"path": "a.txt"})),
+        ("read_file", json!({"path": "missing.txt"})),
+        ("read_file", json!({})),
+        ("write_file", json!({"path": "b.txt", "content": "x"})),
+    ] {
+        dispatcher.execute(&call(name, arguments)).await.unwrap();
+    }
+
+    let entries = audit.entries().unwrap();
+    let outcomes: Vec<AuditOutcome> = entries.iter().map(|entry| entry.outcome).collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            AuditOutcome::Success,
+            AuditOutcome::Failure,
+            AuditOutcome::Invalid,
+            AuditOutcome::Denied
+        ]
+    );
+    assert_eq!(entries[0].tool, "read_file");
+    assert_eq!(entries[0].arguments, json!({"path": "a.txt"}));
+    assert_eq!(entries[0].output.as_deref(), Some("hello"));
+    assert!(entries[0].approval.is_none());
+    assert!(entries[1].error.as_ref().unwrap().contains("missing.txt"));
+    assert!(
+        entries[2]
+            .error
+            .as_ref()
+            .unwrap()
+            .contains("invalid_arguments")
+    );
+    assert_eq!(entries[3].approval.as_deref(), Some("auto_deny"));
+
+    // Appends to an existing log rather than replacing it
+    let reopened = AuditLog::create(audit.path()).unwrap();
+    assert_eq!(reopened.entries().unwrap().len(), 4);
+}
+
+#[tokio::test]
+async fn test_dispatcher_dry_run_skips_mutating_tools() {
+    let (dir, workspace) = temp_workspace();
+    std::fs::write(dir.path().join("a.txt"), "one\n").unwrap();
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        EditFileTool::definition(),
+        Arc::new(EditFileTool::new(workspace.clone())),
+    );
+    let (_command_dir, command) = command_tool(CommandConfig::default());
+    registry.register(RunCommandTool::definition(), Arc::new(command));
+    // Nothing would be approved, but a dry run never asks
+    let config = ApprovalConfig {
+        mode: ApprovalMode::Deny,
+        auto_approve: vec![],
+        auto_deny: vec![],
+    };
+    let audit = Arc::new(AuditLog::create(dir.path().join("audit.jsonl")).unwrap());
+    let dispatcher = ToolExecutionDispatcher::new(Arc::new(registry))
+        .with_approver(Arc::new(PolicyApprover::new(&config)))
+        .with_audit_log(audit.clone())
+        .with_dry_run(true);
+
+    let result = dispatcher
+        .execute(&call(
+            "edit_file",
+            json!({"path": "a.txt", "old_text": "one", "new_text": "two"}),
+        ))
+        .await
+        .unwrap();
+    assert!(result.error.is_none());
+    assert!(result.output.starts_with("[dry run] edit_file was not run"));
+    assert!(result.output.contains("-one\n+two"));
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+        "one\n"
+    );
+
+    let result = dispatcher
+        .execute(&call("run_command", json!({"command": "echo made"})))
+        .await
+        .unwrap();
+    assert!(result.output.contains("$ echo made"));
+
+    let result = dispatcher
+        .execute(&call("read_file", json!({"path": "a.txt"})))
+        .await
+        .unwrap();
+    assert_eq!(result.output, "one\n");
+
+    let outcomes: Vec<AuditOutcome> = audit
+        .entries()
+        .unwrap()
+        .iter()
+        .map(|entry| entry.outcome)
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            AuditOutcome::DryRun,
+            AuditOutcome::DryRun,
+            AuditOutcome::Success
+        ]
+    );
+}
+
+const CODE_SAMPLE: &str = r#"//! Shapes
+
+/// A point on the plane.
+///
+/// Both coordinates are in pixels.
+#[derive(Debug, Clone)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+pub enum Shape {
+    Dot(Point),
+    Line { from: Point, to: Point },
+}
+
+impl Point {
+    /// The origin
+    pub fn origin() -> Self {
+        Point { x: 0, y: 0 }
+    }
+
+    fn norm(&self) -> i32 {
+        self.x.abs() + self.y.abs()
+    }
+}
+
+pub trait Area {
+    /// Area in square pixels
+    fn area(&self) -> u32;
+}
+
+impl Area for Shape {
+    fn area(&self) -> u32 {
+        // A point has no area
+        0
+    }
+}
+
+// Not documented
+pub fn scale(
+    point: Point,
+    factor: i32,
+) -> Point {
+    Point { x: point.x * factor, y: point.y * factor }
+}
+
+pub const ORIGIN: Point = Point { x: 0, y: 0 };
+
+mod inner {
+    pub fn hidden() {}
+}
+"#;
+
+#[test]
+fn test_parse_symbols_signatures_docs_and_lines() {
+    let symbols = analyzer::parse_symbols(CODE_SAMPLE, "crate::shapes").unwrap();
+    let summary: Vec<(&str, usize, &str, bool)> = symbols
+        .iter()
+        .map(|s| (s.name.as_str(), s.line, s.signature.as_str(), s.public))
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("Point", 7, "pub struct Point", true),
+            ("Shape", 12, "pub enum Shape", true),
+            ("Point", 17, "impl Point", true),
+            ("Point::origin", 19, "pub fn origin() -> Self", true),
+            ("Point::norm", 23, "fn norm(&self) -> i32", false),
+            ("Area", 28, "pub trait Area", true),
+            ("Area::area", 30, "fn area(&self) -> u32", true),
+            ("Shape", 33, "impl Area for Shape", true),
+            ("Shape::area", 34, "fn area(&self) -> u32", true),
+            (
+                "scale",
+                41,
+                "pub fn scale(point: Point, factor: i32) -> Point",
+                true
+            ),
+            ("ORIGIN", 48, "pub const ORIGIN: Point", true),
+            ("inner", 50, "mod inner", false),
+            ("hidden", 51, "pub fn hidden()", true),
+        ]
+    );
+
+    let point = &symbols[0];
+    assert_eq!(point.kind, SymbolKind::Struct);
+    assert_eq!(
+        point.docs.as_deref(),
+        Some("A point on the plane.\n\nBoth coordinates are in pixels.")
+    );
+    assert_eq!(
+        point.definition,
+        "pub struct Point {\n    pub x: i32,\n    pub y: i32,\n}"
+    );
+    assert_eq!(symbols[3].parent.as_deref(), Some("impl Point"));
+    assert_eq!(symbols[3].definition, "pub fn origin() -> Self");
+    assert_eq!(symbols[9].docs, None);
+    assert_eq!(symbols[12].module, "crate::shapes::inner");
+
+    let error = analyzer::parse_symbols("fn broken( {}", "crate").unwrap_err();
+    assert!(error.to_string().contains("line 1"));
+
+    assert_eq!(
+        analyzer::module_path(std::path::Path::new("crates/core/src/tools/mod.rs")),
+        "crate::tools"
+    );
+    assert_eq!(
+        analyzer::module_path(std::path::Path::new("src/lib.rs")),
+        "crate"
+    );
+    assert_eq!(
+        analyzer::module_path(std::path::Path::new("src/tools/schema.rs")),
+        "crate::tools::schema"
+    );
+}
+
+#[test]
+fn test_find_references_skips_comments_and_strings() {
+    let source =
+        "/// Uses Point\nfn f(p: Point) -> Point {\n    let s = \"Point\"; // Point\n    p\n}\n";
+    let references = analyzer::find_references(source, "Point").unwrap();
+    let locations: Vec<(usize, usize)> = references.iter().map(|r| (r.line, r.column)).collect();
+    assert_eq!(locations, vec![(2, 9), (2, 19)]);
+}
+
+#[tokio::test]
+async fn test_code_tools_over_workspace() {
+    let (dir, workspace) = search_workspace();
+    std::fs::write(dir.path().join("src/shapes.rs"), CODE_SAMPLE).unwrap();
+    std::fs::write(dir.path().join("src/broken.rs"), "fn broken( {}").unwrap();
+
+    let list = ListSymbolsTool::new(workspace.clone(), vec![]);
+    let output = list
+        .execute(json!({"path": "src/shapes.rs"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output.lines().take(6).collect::<Vec<_>>(),
+        vec![
+            "src/shapes.rs (crate::shapes)",
+            "  7: pub struct Point",
+            "      A point on the plane.",
+            "  12: pub enum Shape",
+            "  17: impl Point",
+            "    19: pub fn origin() -> Self",
+        ]
+    );
+    assert!(!output.contains("fn norm"));
+    let output = list
+        .execute(json!({"path": "src", "include_private": true}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.contains("src/broken.rs: Could not parse Rust source: line 1"));
+    assert!(
+        output.contains("src/lib.rs (crate)\n  1: mod tools\n  3: pub fn run()\n  5: fn helper()")
+    );
+    assert!(output.contains("    23: fn norm(&self) -> i32"));
+    let result = list.execute(json!({"path": "README.md"})).await.unwrap();
+    assert!(result.error.unwrap().contains("Not a Rust source file"));
+
+    let find = FindDefinitionTool::new(workspace.clone(), vec![]);
+    let output = find
+        .execute(json!({"name": "Point::origin"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/shapes.rs:19 (crate::shapes, in impl Point)\n/// The origin\npub fn origin() -> Self"
+    );
+    let output = find
+        .execute(json!({"name": "crate::shapes::Point"}))
+        .await
+        .unwrap()
+        .output;
+    assert!(output.starts_with(
+        "src/shapes.rs:7 (crate::shapes)\n/// A point on the plane.\n///\n/// Both coordinates"
+    ));
+    assert!(output.ends_with("pub y: i32,\n}"));
+    let output = find.execute(json!({"name": "area"})).await.unwrap().output;
+    assert_eq!(output.matches("src/shapes.rs:").count(), 2);
+    let output = find
+        .execute(json!({"name": "Missing"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(output, "No definition of Missing found");
+
+    let references = FindReferencesTool::new(workspace, vec![]);
+    let output = references
+        .execute(json!({"name": "Point::origin", "path": "src"}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/broken.rs: Could not parse Rust source: line 1: cannot parse string into token stream (skipped)\nsrc/shapes.rs:19:12: pub fn origin() -> Self {"
+    );
+    let output = references
+        .execute(json!({"name": "Point", "path": "src/shapes.rs", "max_results": 2}))
+        .await
+        .unwrap()
+        .output;
+    assert_eq!(
+        output,
+        "src/shapes.rs:7:12: pub struct Point {\nsrc/shapes.rs:13:9: Dot(Point),\n(stopped after 2 references)"
+    );
+}
+
+fn cargo_workspace() -> (TempDir, Arc<Workspace>) {
+    let (dir, workspace) = temp_workspace();
+    let root = dir.path();
+    for path in [
+        "crates/core/src",
+        "crates/cli/src/bin",
+        "crates/cli/examples",
+        "crates/old/src",
+    ] {
+        std::fs::create_dir_all(root.join(path)).unwrap();
+    }
+    std::fs::write(
+        root.join("Cargo.toml"),
+        r#"[workspace]
+members = ["crates/*"]
+exclude = ["crates/old"]
+
+[workspace.package]
+version = "0.3.0"
+edition = "2024"
+
+[workspace.dependencies]
+serde = { version = "1.0", default-features = false }
+"#,
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("crates/core/Cargo.toml"),
+        r#"[package]
+name = "demo-core"
+version.workspace = true
+edition.workspace = true
+description = "Core library"
+
+[features]
+default = ["json"]
+json = ["dep:serde_json"]
+
+[dependencies]
+serde = { workspace = true, features = ["derive"] }
+serde_json = { version = "1", optional = true }
+regex = "1"
+old_syn = { package = "syn", version = "1" }
+
+[target.'cfg(windows)'.dependencies]
+winapi = "0.3"
+
+[dev-dependencies]
+syn = "2"
+"#,
+    )
+    .unwrap();
+    std::fs::write(root.join("crates/core/src/lib.rs"), "").unwrap();
+    std::fs::write(root.join("crates/core/build.rs"), "fn main() {}").unwrap();
+    std::fs::write(
+        root.join("crates/cli/Cargo.toml"),
+        r#"[package]
+name = "demo"
+version = "0.3.1"
+
+[[bin]]
+name = "demo-admin"
+path = "src/admin.rs"
+required-features = ["admin"]
+
+[dependencies]
+demo-core = { path = "../core" }
+clap = { version = "4", optional = true }
+"#,
+    )
+    .unwrap();
+    std::fs::write(root.join("crates/cli/src/main.rs"), "fn main() {}").unwrap();
+    std::fs::write(root.join("crates/cli/src/admin.rs"), "fn main() {}").unwrap();
+    std::fs::write(root.join("crates/cli/src/bin/helper.rs"), "fn main() {}").unwrap();
+    std::fs::write(root.join("crates/cli/examples/basic.rs"), "fn main() {}").unwrap();
+    std::fs::write(
+        root.join("crates/old/Cargo.toml"),
+        "[package]\nname = \"old\"\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("Cargo.lock"),
+        r#"version = 4
+
+[[package]]
+name = "demo-core"
+version = "0.3.0"
+dependencies = ["regex", "serde", "serde_json", "syn 1.0.109", "syn 2.0.110", "winapi"]
+
+[[package]]
+name = "regex"
+version = "1.12.2"
+
+[[package]]
+name = "serde"
+version = "1.0.228"
+
+[[package]]
+name = "serde_json"
+version = "1.0.145"
+
+[[package]]
+name = "syn"
+version = "1.0.109"
+
+[[package]]
+name = "syn"
+version = "2.0.110"
+
+[[package]]
+name = "winapi"
+version = "0.3.9"
+"#,
+    )
+    .unwrap();
+    (dir, workspace)
+}
+
+#[test]
+fn test_cargo_project_reads_workspace_manifests_and_lock() {
+    let (dir, _workspace) = cargo_workspace();
+    let project = CargoProject::load(dir.path()).unwrap();
+    assert!(project.locked);
+    assert_eq!(project.workspace_members, vec!["crates/cli", "crates/core"]);
+    let names: Vec<&str> = project.packages.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["demo", "demo-core"]);
+
+    let cli = &project.packages[0];
+    assert_eq!(cli.manifest, "crates/cli/Cargo.toml");
+    let targets: Vec<(TargetKind, &str, &str)> = cli
+        .targets
+        .iter()
+        .map(|t| (t.kind, t.name.as_str(), t.path.as_str()))
+        .collect();
+    assert_eq!(
+        targets,
+        vec![
+            (TargetKind::Bin, "demo-admin", "crates/cli/src/admin.rs"),
+            (TargetKind::Bin, "demo", "crates/cli/src/main.rs"),
+            (TargetKind::Bin, "helper", "crates/cli/src/bin/helper.rs"),
+            (TargetKind::Example, "basic", "crates/cli/examples/basic.rs"),
+        ]
+    );
+    assert_eq!(cli.targets[0].required_features, vec!["admin"]);
+    assert_eq!(cli.features["clap"], vec!["dep:clap"]);
+    assert_eq!(cli.dependencies[1].path.as_deref(), Some("../core"));
+
+    let core = &project.packages[1];
+    assert_eq!(core.version.as_deref(), Some("0.3.0"));
+    assert_eq!(core.edition.as_deref(), Some("2024"));
+    assert_eq!(core.description.as_deref(), Some("Core library"));
+    assert_eq!(
+        core.targets.iter().map(|t| t.kind).collect::<Vec<_>>(),
+        vec![TargetKind::Lib, TargetKind::CustomBuild]
+    );
+    assert_eq!(core.targets[0].name, "demo_core");
+    // `dep:` in a feature hides the implicit feature of the optional dependency
+    assert_eq!(core.features.len(), 2);
+
+    let dependency = |name: &str| {
+        core.dependencies
+            .iter()
+            .find(|d| d.name == name)
+            .unwrap()
+            .clone()
+    };
+    let serde = dependency("serde");
+    assert_eq!(serde.requirement.as_deref(), Some("1.0"));
+    assert!(!serde.default_features);
+    assert_eq!(serde.features, vec!["derive"]);
+    assert_eq!(serde.resolved.as_deref(), Some("1.0.228"));
+    assert!(dependency("serde_json").optional);
+    assert_eq!(dependency("regex").resolved.as_deref(), Some("1.12.2"));
+    let old_syn = dependency("old_syn");
+    assert_eq!(old_syn.package.as_deref(), Some("syn"));
+    assert_eq!(old_syn.resolved.as_deref(), Some("1.0.109"));
+    let syn = dependency("syn");
+    assert_eq!(syn.kind, DependencyKind::Dev);
+    assert_eq!(syn.resolved.as_deref(), Some("2.0.110"));
+    assert_eq!(dependency("winapi").target.as_deref(), Some("cfg(windows)"));
+}
+
+#[tokio::test]
+async fn test_cargo_metadata_tool() {
+    let (_dir, workspace) = cargo_workspace();
+    let tool = CargoMetadataTool::new(workspace);
+
+    let output = tool
+        .execute(json!({"package": "demo"}))
+        .await
+        .unwrap()
+        .output;
+    let metadata: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(metadata["packages"].as_array().unwrap().len(), 1);
+    assert_eq!(metadata["packages"][0]["version"], "0.3.1");
+    assert_eq!(metadata["packages"][0]["targets"][0]["kind"], "bin");
+    assert_eq!(metadata["workspace_members"][1], "crates/core");
+
+    // A member read on its own has no lock file next to it
+    let output = tool
+        .execute(json!({"path": "crates/core"}))
+        .await
+        .unwrap()
+        .output;
+    let metadata: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(metadata["locked"], false);
+    assert_eq!(metadata["packages"][0]["name"], "demo-core");
+
+    let result = tool.execute(json!({"package": "missing"})).await.unwrap();
+    assert_eq!(result.error.unwrap(), "No package named missing");
+    let result = tool.execute(json!({"path": "crates"})).await.unwrap();
+    assert_eq!(result.error.unwrap(), "No readable Cargo.toml in crates");
+}
+
+#[test]
+fn test_tool_profiles() {
+    let dir = TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let commands = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        CommandConfig::default(),
+    ));
+    let registry = workspace_tools(&workspace, &commands, &[]).unwrap();
+    let names = |registry: &ToolRegistry| -> Vec<String> {
+        let mut names: Vec<String> = registry.list_tools().into_iter().map(|t| t.name).collect();
+        names.sort();
+        names
+    };
+
+    let profiles = ToolProfilesConfig::default();
+    let read_only = names(&profiles.get("read-only").unwrap().apply(&registry));
+    assert!(read_only.contains(&"read_file".to_string()));
+    assert!(read_only.contains(&"git_log".to_string()));
+    for name in [
+        "write_file",
+        "edit_file",
+        "apply_patch",
+        "run_command",
+        "write_doc",
+    ] {
+        assert!(!read_only.contains(&name.to_string()), "{}", name);
+    }
+    let docs_writer = names(&profiles.get("docs-writer").unwrap().apply(&registry));
+    assert!(docs_writer.contains(&"write_doc".to_string()));
+    assert!(docs_writer.contains(&"edit_file".to_string()));
+    assert!(!docs_writer.contains(&"run_command".to_string()));
+    assert_eq!(
+        names(&profiles.selected().unwrap().apply(&registry)),
+        names(&registry)
+    );
+
+    // Delegation hands out every tool of the run, so it must be allowed by name
+    let profile = ToolProfile::builtin("read-only").unwrap();
+    assert!(!profile.allows("delegate_task", false));
+    assert!(
+        ToolProfile::builtin("full")
+            .unwrap()
+            .allows("delegate_task", false)
+    );
+
+    let profiles: ToolProfilesConfig = serde_yaml::from_str(
+        r#"
+default: reviewer
+profiles:
+  reviewer:
+    read_only_tools: true
+    allow: ["run_command"]
+    deny: ["git_*"]
+"#,
+    )
+    .unwrap();
+    let reviewer = names(&profiles.selected().unwrap().apply(&registry));
+    assert!(reviewer.contains(&"run_command".to_string()));
+    assert!(reviewer.contains(&"read_file".to_string()));
+    assert!(!reviewer.iter().any(|name| name.starts_with("git_")));
+    assert!(!reviewer.contains(&"write_file".to_string()));
+
+    let error = profiles.get("writer").unwrap_err().to_string();
+    assert!(error.contains("Unknown tool profile writer"));
+    assert!(error.contains("docs-writer, full, read-only, reviewer"));
+    let profiles = profiles.with_default(Some("broken".to_string()));
+    assert!(profiles.selected().is_err());
 }
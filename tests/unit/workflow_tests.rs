@@ -0,0 +1,388 @@
+use crate::support::{ScriptedProvider, tool_call};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use xzardgz::agent::budget::{Budget, BudgetTracker};
+use xzardgz::agent::core::Agent;
+use xzardgz::agent::message::Message;
+use xzardgz::error::WorkflowError;
+use xzardgz::tools::command_ops::{CommandConfig, CommandRunner};
+use xzardgz::tools::file_ops::{ReadFileTool, WriteFileTool};
+use xzardgz::tools::profile::ToolProfilesConfig;
+use xzardgz::tools::registry::ToolRegistry;
+use xzardgz::tools::workspace::{Workspace, WorkspaceConfig};
+use xzardgz::workflow::executor::WorkflowExecutor;
+use xzardgz::workflow::generator::PlanGenerator;
+use xzardgz::workflow::parser::{PlanParser, YamlPlanParser};
+use xzardgz::workflow::plan::Plan;
+use xzardgz::workflow::state::RunState;
+
+fn two_step_plan() -> Plan {
+    YamlPlanParser
+        .parse(
+            r#"
+name: Two Steps
+description: Two agent tasks
+steps:
+  - id: first
+    description: First task
+    action:
+      type: agent_task
+      params:
+        prompt: Do the first thing
+  - id: second
+    description: Second task
+    action:
+      type: agent_task
+      params:
+        prompt: Do the second thing
+    dependencies: [first]
+"#,
+        )
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_interrupted_run_records_step() {
+    let provider = Arc::new(ScriptedProvider::new(vec![]));
+    let agent = Arc::new(Agent::new(
+        provider,
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+
+    let cancel = CancellationToken::new();
+    cancel.cancel();
+    let mut executor = WorkflowExecutor::new(agent, two_step_plan()).with_cancellation(cancel);
+
+    let result = executor.execute().await;
+    assert!(matches!(result, Err(WorkflowError::Interrupted(ref step)) if step == "first"));
+
+    let state = executor.state();
+    assert_eq!(state.interrupted_step.as_deref(), Some("first"));
+    assert!(state.completed_steps.is_empty());
+}
+
+#[tokio::test]
+async fn test_resumed_run_skips_completed_steps() {
+    let provider = Arc::new(ScriptedProvider::new(vec![Message::assistant("done")]));
+    let agent = Arc::new(Agent::new(
+        provider.clone(),
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+
+    let state = RunState {
+        plan: "Two Steps".to_string(),
+        plan_hash: RunState::plan_hash(&two_step_plan()),
+        completed_steps: vec!["first".to_string()],
+        interrupted_step: Some("second".to_string()),
+    };
+    let mut executor = WorkflowExecutor::new(agent, two_step_plan())
+        .resume_from(state)
+        .unwrap();
+    executor.execute().await.unwrap();
+
+    let requests = provider.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0][0].content, "Do the second thing");
+    assert_eq!(executor.state().completed_steps, vec!["first", "second"]);
+}
+
+#[test]
+fn test_run_state_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let plan_path = dir.path().join("plan.yaml");
+    let path = RunState::path_for(&plan_path);
+    assert_eq!(path, dir.path().join("plan.state.json"));
+
+    let state = RunState {
+        plan: "Two Steps".to_string(),
+        plan_hash: RunState::plan_hash(&two_step_plan()),
+        completed_steps: vec!["first".to_string()],
+        interrupted_step: Some("second".to_string()),
+    };
+    state.save(&path).unwrap();
+
+    let loaded = RunState::load(&path).unwrap();
+    assert_eq!(loaded.completed_steps, vec!["first"]);
+    assert_eq!(loaded.interrupted_step.as_deref(), Some("second"));
+    assert!(loaded.check_plan(&two_step_plan()).is_ok());
+}
+
+#[test]
+fn test_resume_refuses_a_changed_plan() {
+    let agent = Arc::new(Agent::new(
+        Arc::new(ScriptedProvider::new(vec![])),
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+    let state = RunState {
+        plan: "Two Steps".to_string(),
+        plan_hash: RunState::plan_hash(&two_step_plan()),
+        completed_steps: vec!["first".to_string()],
+        interrupted_step: None,
+    };
+
+    let mut edited = two_step_plan();
+    edited.steps[0].description = "Something else now".to_string();
+    let error = WorkflowExecutor::new(agent.clone(), edited)
+        .resume_from(state.clone())
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("changed since"));
+
+    let mut renamed = two_step_plan();
+    renamed.name = "Other".to_string();
+    assert!(
+        WorkflowExecutor::new(agent.clone(), renamed)
+            .resume_from(state.clone())
+            .is_err()
+    );
+
+    // State files written before plans were hashed cannot be trusted either
+    let unhashed = RunState {
+        plan_hash: String::new(),
+        ..state
+    };
+    assert!(unhashed.check_plan(&two_step_plan()).is_err());
+}
+
+#[tokio::test]
+async fn test_plan_generator_repairs_invalid_plan() {
+    let invalid = r#"
+name: Docs
+description: Write docs
+steps:
+  - id: write
+    description: Write docs
+    action:
+      type: agent_task
+      params:
+        prompt: Write the docs
+    dependencies: [scan]
+"#;
+    let valid = r#"```yaml
+name: Docs
+description: Write docs
+steps:
+  - id: scan
+    description: Scan
+    action:
+      type: scan_repository
+      params: null
+  - id: write
+    description: Write docs
+    action:
+      type: agent_task
+      params:
+        prompt: Write the docs
+    dependencies: [scan]
+```"#;
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant(invalid),
+        Message::assistant(valid),
+    ]));
+    let agent = Arc::new(Agent::new(
+        provider.clone(),
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+
+    let plan = PlanGenerator::new(agent, 2)
+        .generate("Document the project", "src/main.rs")
+        .await
+        .unwrap();
+    assert_eq!(plan.steps.len(), 2);
+
+    let requests = provider.requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    let repair = &requests[1].last().unwrap().content;
+    assert!(repair.contains("depends on unknown step scan"));
+}
+
+#[tokio::test]
+async fn test_plan_generator_gives_up() {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("not a plan"),
+        Message::assistant("still not a plan"),
+    ]));
+    let agent = Arc::new(Agent::new(
+        provider,
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+
+    let result = PlanGenerator::new(agent, 1)
+        .generate("Document the project", "")
+        .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_budget_exceeded_stops_workflow() {
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        Message::assistant("first done"),
+        Message::assistant("second done"),
+    ]));
+    let budget = Arc::new(BudgetTracker::new(Budget {
+        max_provider_calls: Some(1),
+        ..Budget::default()
+    }));
+    let agent = Arc::new(
+        Agent::new(provider, "test".to_string(), ToolRegistry::new())
+            .with_shared_budget(budget.clone()),
+    );
+    let mut executor = WorkflowExecutor::new(agent, two_step_plan()).with_budget(budget);
+
+    match executor.execute().await {
+        Err(WorkflowError::BudgetExceeded { step, reason, .. }) => {
+            assert_eq!(step, "second");
+            assert_eq!(reason, "1 of 1 provider calls");
+        }
+        other => panic!("expected budget error, got {:?}", other),
+    }
+    let state = executor.state();
+    assert_eq!(state.completed_steps, vec!["first".to_string()]);
+    assert_eq!(state.interrupted_step.as_deref(), Some("second"));
+}
+
+fn command_plan(command: &str) -> Plan {
+    YamlPlanParser
+        .parse(&format!(
+            r#"
+name: Command
+description: Run a command
+steps:
+  - id: build
+    description: Build
+    action:
+      type: execute_command
+      params:
+        command: "{}"
+"#,
+            command
+        ))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_execute_command_step_uses_command_policy() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let runner = Arc::new(CommandRunner::new(workspace, CommandConfig::default()));
+    let agent = Arc::new(Agent::new(
+        Arc::new(ScriptedProvider::new(vec![])),
+        "test".to_string(),
+        ToolRegistry::new(),
+    ));
+
+    let mut executor = WorkflowExecutor::new(agent.clone(), command_plan("echo built"))
+        .with_command_runner(runner.clone());
+    executor.execute().await.unwrap();
+    assert_eq!(executor.state().completed_steps, vec!["build".to_string()]);
+
+    let mut executor = WorkflowExecutor::new(agent.clone(), command_plan("rm -rf target"))
+        .with_command_runner(runner.clone());
+    let error = executor.execute().await.unwrap_err();
+    assert!(error.to_string().contains("rm is denied"));
+
+    let mut executor = WorkflowExecutor::new(agent.clone(), command_plan("ls missing-dir"))
+        .with_command_runner(runner.clone());
+    let error = executor.execute().await.unwrap_err();
+    assert!(error.to_string().contains("exited with code"));
+
+    // A dry run only checks the command against the policy
+    let mut executor = WorkflowExecutor::new(agent.clone(), command_plan("ls missing-dir"))
+        .with_command_runner(runner.clone())
+        .with_dry_run(true);
+    executor.execute().await.unwrap();
+    let mut executor = WorkflowExecutor::new(agent, command_plan("rm -rf target"))
+        .with_command_runner(runner)
+        .with_dry_run(true);
+    assert!(executor.execute().await.is_err());
+}
+
+#[tokio::test]
+async fn test_step_tool_profiles() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let workspace = Arc::new(Workspace::new(dir.path(), &WorkspaceConfig::default()).unwrap());
+    let runner = Arc::new(CommandRunner::new(
+        workspace.clone(),
+        CommandConfig::default(),
+    ));
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        ReadFileTool::definition(),
+        Arc::new(ReadFileTool::new(workspace.clone())),
+    );
+    registry.register(
+        WriteFileTool::definition(),
+        Arc::new(WriteFileTool::new(workspace)),
+    );
+    let plan = |tools: &str| {
+        YamlPlanParser
+            .parse(&format!(
+                r#"
+name: Review
+description: Look without touching
+steps:
+  - id: review
+    description: Review
+    tools: {}
+    action:
+      type: agent_task
+      params:
+        prompt: Review the code
+"#,
+                tools
+            ))
+            .unwrap()
+    };
+
+    // A read-only step cannot reach write_file even though the agent has it
+    let provider = Arc::new(ScriptedProvider::new(vec![
+        tool_call(
+            "call_1",
+            "write_file",
+            r#"{"path": "notes.md", "content": "x"}"#,
+        ),
+        Message::assistant("Done"),
+    ]));
+    let agent = Arc::new(Agent::new(
+        provider.clone(),
+        "test".to_string(),
+        registry.clone(),
+    ));
+    let mut executor = WorkflowExecutor::new(agent, plan("read-only"));
+    executor.execute().await.unwrap();
+    assert!(!dir.path().join("notes.md").exists());
+    let result = provider.requests.lock().unwrap()[1].last().unwrap().clone();
+    assert!(
+        result
+            .content
+            .contains("Tool write_file is not available here")
+    );
+
+    // Unknown profiles fail before any step runs
+    let provider = Arc::new(ScriptedProvider::new(vec![]));
+    let agent = Arc::new(Agent::new(provider, "test".to_string(), registry.clone()));
+    let mut executor = WorkflowExecutor::new(agent.clone(), plan("writer"));
+    let error = executor.execute().await.unwrap_err();
+    assert!(error.to_string().contains("Unknown tool profile writer"));
+    assert!(executor.state().completed_steps.is_empty());
+
+    // Command steps need run_command in both the run's and the step's profile
+    let mut plan = command_plan("echo built");
+    plan.steps[0].tools = Some("read-only".to_string());
+    let mut executor =
+        WorkflowExecutor::new(agent.clone(), plan).with_command_runner(runner.clone());
+    let error = executor.execute().await.unwrap_err();
+    assert!(error.to_string().contains("does not allow run_command"));
+
+    let profiles = ToolProfilesConfig::default().with_default(Some("docs-writer".to_string()));
+    let mut executor = WorkflowExecutor::new(agent, command_plan("echo built"))
+        .with_command_runner(runner)
+        .with_tool_profiles(profiles);
+    let error = executor.execute().await.unwrap_err();
+    assert!(error.to_string().contains("tool profile docs-writer"));
+}